@@ -1,7 +1,15 @@
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{env, path::Path, sync::Arc};
 
-use service_core::{feature::FeatureContext, Feature, SystemClock};
+use service_core::{
+    AppConfig, AuthConfig, EnabledTransports, FeatureRegistry, InMemoryRouter, RequestVerifier,
+    SystemClock, TransportSupervisor,
+};
 use service_features::hello_world::HelloWorldFeature;
+use service_platform::{
+    autoselect::{follow_wifi, Selection},
+    network::NetworkWatcher,
+    wifi::{detector::ConnectivityProber, WifiDetector},
+};
 use service_transport::mock::MockTransport;
 
 #[tokio::main]
@@ -14,36 +22,274 @@ async fn main() -> anyhow::Result<()> {
         "Starting service-app with config: {} (transport features selected via Cargo)",
         config_path
     );
+    let config = if Path::new(&config_path).exists() {
+        AppConfig::load(&config_path)?
+    } else {
+        println!("Config file not found; using defaults.");
+        AppConfig::default()
+    };
 
-    let transport = MockTransport::new();
+    let mut router = InMemoryRouter::new()
+        .with_limits(config.limits)
+        .with_rate_limits(config.rate_limits);
+    let authenticator = config.auth.as_ref().map(AuthConfig::authenticator);
+    if authenticator.is_none() {
+        println!("No [auth] section; callers are not authenticated.");
+    }
+    let policy = config.acl.map(Arc::new);
+    if let Some(policy) = &policy {
+        router = router.with_policy(policy.clone());
+    }
+    if let Some(idempotency) = config.idempotency {
+        router = router.with_idempotency(idempotency);
+    }
+    if let Some(signing) = &config.signing {
+        router = router.with_request_verifier(Arc::new(RequestVerifier::from_config(signing)));
+    }
+    let transport = MockTransport::with_router(router);
     let registry = transport.registry();
     let events = transport.events();
     let clock = Arc::new(SystemClock);
 
-    let feature = HelloWorldFeature::with_clock(clock.clone());
-    feature
-        .init(FeatureContext::new(registry.clone(), events, clock))
-        .await
-        .expect("feature init");
+    let features = Arc::new(FeatureRegistry::new(
+        registry.clone(),
+        events.clone(),
+        clock.clone(),
+    ));
+    features
+        .add(Arc::new(HelloWorldFeature::with_clock(clock)))
+        .expect("add feature");
+    features.enable_all().await.expect("feature init");
+    features.register_admin().expect("register admin service");
+
+    // `[transport.enable]` runs the flagged transports side by side;
+    // without it a single one is selected by `[transport] mode`. `auto`
+    // registers both HTTP and BLE and runs whichever the Wi-Fi state picks.
+    let auto = config.transport_enable.is_none() && config.transport == "auto";
+    let enabled = match config.transport_enable {
+        Some(enabled) => enabled,
+        None if auto => EnabledTransports {
+            http: true,
+            ble: true,
+            local: false,
+        },
+        None => exclusive_transport(&config.transport),
+    };
+    let supervisor = TransportSupervisor::new()
+        .with_failure_handler(|id, err| println!("transport '{id}' stopped: {err}"));
 
     #[cfg(feature = "use_transport_http")]
     {
+        use std::net::SocketAddr;
+
+        use service_core::{Error, TopicAllowlist};
         use service_transport::http::HttpServerTransport;
 
-        let addr: SocketAddr = env::var("SERVICE_HTTP_ADDR")
-            .unwrap_or_else(|_| "127.0.0.1:8080".to_string())
-            .parse()
-            .map_err(|err| anyhow::anyhow!("invalid SERVICE_HTTP_ADDR: {err}"))?;
+        let mut server = HttpServerTransport::new(registry.clone())
+            .with_events(
+                events.clone(),
+                TopicAllowlist::new(config.events_client_topics.clone()),
+            )
+            .with_error_status(config.http_error_status)
+            .with_max_body_bytes(config.http_max_body_bytes)
+            .with_compression(config.http_compression);
+        if let Some(authenticator) = &authenticator {
+            server = server.with_authenticator(authenticator.clone());
+        }
+        if let Some(policy) = &policy {
+            server = server.with_access_policy(policy.clone());
+        }
+        if let Some(cors) = config.http_cors.clone() {
+            server = server.with_cors(cors);
+        }
 
-        println!("Starting HTTP RPC server on {addr}");
-        let server = HttpServerTransport::new(registry);
-        server.serve(addr).await?;
-    }
+        if enabled.local {
+            let unix = config.http_unix.clone().expect("validated with the config");
+            let local = server.clone();
+            println!("Serving local RPC on {}", unix.path.display());
+            supervisor.add("local", move || {
+                let (local, unix) = (local.clone(), unix.clone());
+                async move {
+                    local
+                        .serve_unix(&unix)
+                        .await
+                        .map_err(|err| Error::Transport(format!("{err:#}")))
+                }
+            })?;
+        }
+
+        if enabled.http {
+            let addr: SocketAddr = env::var("SERVICE_HTTP_ADDR")
+                .unwrap_or_else(|_| "127.0.0.1:8080".to_string())
+                .parse()
+                .map_err(|err| anyhow::anyhow!("invalid SERVICE_HTTP_ADDR: {err}"))?;
+            // In exclusive mode the HTTP server also owns the local socket.
+            if config.transport_enable.is_none() {
+                if let Some(unix) = config.http_unix.clone() {
+                    println!("Also serving on Unix socket {}", unix.path.display());
+                    server = server.with_unix_socket(unix);
+                }
+            }
+            #[cfg(feature = "use_transport_http_tls")]
+            if let Some(tls_config) = config.http_tls.clone() {
+                use service_transport::http::tls::HttpTls;
+
+                let tls = HttpTls::load(tls_config)?;
+                tls.reload_on_sighup(|reloaded| match reloaded {
+                    Ok(()) => println!("TLS certificates reloaded"),
+                    Err(err) => {
+                        println!("TLS reload failed, keeping previous certificates: {err:#}")
+                    }
+                })?;
+                server = server.with_tls(tls);
+                println!("TLS enabled; send SIGHUP to reload certificates");
+            }
+            #[cfg(not(feature = "use_transport_http_tls"))]
+            if config.http_tls.is_some() {
+                println!("[http.tls] ignored: built without use_transport_http_tls");
+            }
 
+            println!("Serving HTTP RPC on {addr}");
+            supervisor.add("http", move || {
+                let server = server.clone();
+                async move {
+                    server
+                        .serve(addr)
+                        .await
+                        .map_err(|err| Error::Transport(format!("{err:#}")))
+                }
+            })?;
+        }
+    }
     #[cfg(not(feature = "use_transport_http"))]
-    {
-        println!("HTTP transport feature disabled; no server started.");
+    if enabled.http || enabled.local {
+        println!("HTTP transport ignored: built without use_transport_http");
+    }
+
+    // Kept until shutdown: the BLE adapter backend hands connected centrals
+    // to the transport through this sender.
+    #[cfg(feature = "use_transport_ble")]
+    let _ble_links = if enabled.ble {
+        use service_core::TopicAllowlist;
+        use service_transport::ble::BleTransport;
+        use tokio::sync::{mpsc, Mutex};
+
+        let mut ble = BleTransport::new(registry.clone()).with_events(
+            events.clone(),
+            TopicAllowlist::new(config.events_client_topics.clone()),
+        );
+        if let Some(authenticator) = &authenticator {
+            ble = ble.with_authenticator(authenticator.clone());
+        }
+        if let Some(policy) = &policy {
+            ble = ble.with_access_policy(policy.clone());
+        }
+        match &config.ble_pairing {
+            Some(pairing_config) => {
+                use service_transport::ble::secure::{FilePairingStore, SecurePairing};
+
+                let store = Arc::new(FilePairingStore::open(&pairing_config.store_path)?);
+                let pairing = match &pairing_config.code {
+                    Some(code) => SecurePairing::new(code.clone(), store),
+                    None => {
+                        let pairing = SecurePairing::with_random_code(store);
+                        println!("BLE pairing code: {}", pairing.code());
+                        pairing
+                    }
+                };
+                ble = ble.with_secure_pairing(pairing);
+            }
+            None => println!("No [ble.pairing] section; BLE links are not encrypted."),
+        }
+        let (link_sender, links) = mpsc::channel(4);
+        let links = Arc::new(Mutex::new(links));
+        println!("Serving BLE RPC");
+        supervisor.add("ble", move || {
+            let (ble, links) = (ble.clone(), links.clone());
+            async move {
+                ble.serve_links(&mut *links.lock().await).await;
+                Ok(())
+            }
+        })?;
+        Some(link_sender)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "use_transport_ble"))]
+    if enabled.ble {
+        println!("BLE transport ignored: built without use_transport_ble");
+    }
+    #[cfg(not(feature = "use_transport_ble"))]
+    if config.ble_pairing.is_some() {
+        println!("[ble.pairing] ignored: built without use_transport_ble");
     }
 
+    if supervisor.transports().is_empty() {
+        println!("No network transport enabled; exiting.");
+        return Ok(());
+    }
+    if auto {
+        let watcher = match NetworkWatcher::netlink() {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                println!("Not watching network changes: {err}");
+                None
+            }
+        };
+        let shutdown = async {
+            if let Err(err) = tokio::signal::ctrl_c().await {
+                println!("Cannot listen for ctrl-c: {err}");
+            }
+        };
+        follow_wifi(
+            &supervisor,
+            registry.as_ref(),
+            &WifiDetector::new(),
+            watcher,
+            config.transport_probe.map(ConnectivityProber::new),
+            shutdown,
+            report_selection,
+        )
+        .await?;
+    } else {
+        supervisor.start_all()?;
+        tokio::signal::ctrl_c().await?;
+    }
+    println!("Shutting down transports");
+    supervisor.stop_all().await;
     Ok(())
 }
+
+/// Print what `auto` mode did.
+fn report_selection(selection: Selection) {
+    match selection {
+        Selection::Switched {
+            reason,
+            selected,
+            running,
+        } => {
+            let reason = reason.unwrap_or_else(|| "Auto mode".to_string());
+            match running {
+                Some(running) if running == selected => {
+                    println!("{reason}: running {running}")
+                }
+                Some(running) => {
+                    println!("{reason}: {selected} not built in; running {running} instead")
+                }
+                None => println!("{reason}: {selected} not built in and nothing to fall back to"),
+            }
+        }
+        Selection::WatcherStopped => println!("Network watcher stopped"),
+    }
+}
+
+/// Transport named by `[transport] mode` when `[transport.enable]` is
+/// absent and the mode is not `auto`; `mock` keeps the default of serving
+/// HTTP.
+fn exclusive_transport(mode: &str) -> EnabledTransports {
+    EnabledTransports {
+        http: mode != "ble",
+        ble: mode == "ble",
+        local: false,
+    }
+}
@@ -0,0 +1,210 @@
+//! Route lookup and dispatch cost of `InMemoryRouter`.
+//!
+//! `mutex_baseline` reproduces the previous design (a mutex-guarded map keyed
+//! by an owned `(String, String)`, and one mutex-guarded in-flight map keyed
+//! by an owned request id) so the snapshot router can be compared against
+//! it, single-threaded and with several threads hammering lookups and
+//! dispatches.
+
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use service_core::{
+    router::RpcFuture, rpc_handler, CancellationToken, InMemoryRouter, RpcError, RpcHandler,
+    RpcRegistry, RpcRequest, RpcResponse,
+};
+
+const SERVICES: usize = 16;
+const THREADS: usize = 4;
+const CALLS_PER_THREAD: usize = 1_000;
+
+fn handler() -> RpcHandler {
+    rpc_handler(|_req: RpcRequest| async {
+        Ok(RpcResponse {
+            payload: Vec::new(),
+        })
+    })
+}
+
+type BaselineCalls = HashMap<String, Vec<(u64, CancellationToken)>>;
+
+#[derive(Default)]
+struct MutexBaseline {
+    handlers: Mutex<HashMap<(String, String), RpcHandler>>,
+    in_flight: Mutex<BaselineCalls>,
+    next_call: AtomicU64,
+}
+
+impl MutexBaseline {
+    fn get(&self, service: &str, method: &str) -> Option<RpcHandler> {
+        let handlers = self.handlers.lock().expect("baseline mutex poisoned");
+        handlers
+            .get(&(service.to_string(), method.to_string()))
+            .cloned()
+    }
+
+    /// Dispatch the way the router does, but track the call in one global
+    /// in-flight map keyed by an owned request id.
+    fn dispatch(self: &Arc<Self>, req: RpcRequest) -> RpcFuture {
+        let Some(handler) = self.get(&req.service, &req.method) else {
+            return Box::pin(async { Err(RpcError::UnknownMethod) });
+        };
+        let call = self.next_call.fetch_add(1, Ordering::Relaxed);
+        let request_id = req.context.request_id.clone();
+        let token = req.context.cancellation().clone();
+        self.in_flight
+            .lock()
+            .expect("baseline mutex poisoned")
+            .entry(request_id.clone())
+            .or_default()
+            .push((call, token.clone()));
+        let baseline = self.clone();
+        Box::pin(async move {
+            let result = tokio::select! {
+                result = handler(req) => result,
+                _ = token.cancelled() => Err(RpcError::Cancelled),
+            };
+            let mut in_flight = baseline.in_flight.lock().expect("baseline mutex poisoned");
+            if let Some(calls) = in_flight.get_mut(&request_id) {
+                calls.retain(|(id, _)| *id != call);
+                if calls.is_empty() {
+                    in_flight.remove(&request_id);
+                }
+            }
+            result
+        })
+    }
+}
+
+fn routers() -> (Arc<InMemoryRouter>, Arc<MutexBaseline>) {
+    let router = InMemoryRouter::new();
+    let baseline = MutexBaseline::default();
+    for service in 0..SERVICES {
+        let service = format!("svc{service}");
+        for method in ["get", "set", "list", "watch"] {
+            router
+                .register(&service, method, handler())
+                .expect("register");
+            baseline
+                .handlers
+                .lock()
+                .expect("baseline mutex poisoned")
+                .insert((service.clone(), method.to_string()), handler());
+        }
+    }
+    (Arc::new(router), Arc::new(baseline))
+}
+
+/// Run `lookup` from several threads at once.
+fn contended(lookup: impl Fn() -> bool + Send + Sync + Clone + 'static) {
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let lookup = lookup.clone();
+            thread::spawn(move || {
+                for _ in 0..CALLS_PER_THREAD {
+                    black_box(lookup());
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker panicked");
+    }
+}
+
+fn lookup(c: &mut Criterion) {
+    let (router, baseline) = routers();
+    let mut group = c.benchmark_group("route_lookup");
+    group.bench_function("snapshot", |b| {
+        b.iter(|| router.contains(black_box("svc7"), black_box("watch")))
+    });
+    group.bench_function("mutex_baseline", |b| {
+        b.iter(|| {
+            baseline
+                .get(black_box("svc7"), black_box("watch"))
+                .is_some()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("route_lookup_contended");
+    group.bench_with_input(
+        BenchmarkId::new("snapshot", THREADS),
+        &router,
+        |b, router| {
+            b.iter(|| {
+                let router = router.clone();
+                contended(move || router.contains("svc7", "watch"))
+            })
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("mutex_baseline", THREADS),
+        &baseline,
+        |b, baseline| {
+            b.iter(|| {
+                let baseline = baseline.clone();
+                contended(move || baseline.get("svc7", "watch").is_some())
+            })
+        },
+    );
+    group.finish();
+}
+
+/// Dispatch `svc7.watch` through the router, polling it to completion.
+fn router_dispatch(router: &InMemoryRouter) -> bool {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut call = router.dispatch(RpcRequest::new("svc7", "watch", Vec::new(), 0));
+    matches!(call.as_mut().poll(&mut cx), Poll::Ready(Ok(_)))
+}
+
+fn baseline_dispatch(baseline: &Arc<MutexBaseline>) -> bool {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut call = baseline.dispatch(RpcRequest::new("svc7", "watch", Vec::new(), 0));
+    matches!(call.as_mut().poll(&mut cx), Poll::Ready(Ok(_)))
+}
+
+fn dispatch(c: &mut Criterion) {
+    let (router, baseline) = routers();
+    let mut group = c.benchmark_group("dispatch_unary");
+    group.bench_function("snapshot", |b| b.iter(|| assert!(router_dispatch(&router))));
+    group.bench_function("mutex_baseline", |b| {
+        b.iter(|| assert!(baseline_dispatch(&baseline)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("dispatch_unary_contended");
+    group.bench_with_input(
+        BenchmarkId::new("snapshot", THREADS),
+        &router,
+        |b, router| {
+            b.iter(|| {
+                let router = router.clone();
+                contended(move || router_dispatch(&router))
+            })
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("mutex_baseline", THREADS),
+        &baseline,
+        |b, baseline| {
+            b.iter(|| {
+                let baseline = baseline.clone();
+                contended(move || baseline_dispatch(&baseline))
+            })
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, lookup, dispatch);
+criterion_main!(benches);
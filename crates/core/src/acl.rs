@@ -0,0 +1,140 @@
+//! Access control: which callers may invoke which methods and use which
+//! event topics.
+
+use serde::Deserialize;
+
+use crate::{event::topic_matches, router::Principal};
+
+/// Grants a set of callers access to methods and topics.
+///
+/// A rule applies to a caller whose principal id is listed in `principals`
+/// or who holds one of `roles`. The principal `"*"` matches every caller,
+/// including unauthenticated ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AccessRule {
+    pub principals: Vec<String>,
+    pub roles: Vec<String>,
+    /// `service.method` patterns: an exact name, `service.*` or `*`.
+    pub methods: Vec<String>,
+    /// Topic patterns as understood by [`topic_matches`].
+    pub topics: Vec<String>,
+}
+
+impl AccessRule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn principals<I, S>(mut self, principals: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.principals = principals.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn roles<I, S>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.methods = methods.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn topics<I, S>(mut self, topics: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.topics = topics.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn applies_to(&self, principal: Option<&Principal>) -> bool {
+        if self.principals.iter().any(|id| id == "*") {
+            return true;
+        }
+        let Some(principal) = principal else {
+            return false;
+        };
+        self.principals.contains(&principal.id)
+            || self.roles.iter().any(|role| principal.has_role(role))
+    }
+}
+
+/// Deny-by-default policy made of [`AccessRule`]s; access is granted if any
+/// rule applying to the caller allows it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessPolicy {
+    rules: Vec<AccessRule>,
+}
+
+impl AccessPolicy {
+    pub fn new(rules: impl IntoIterator<Item = AccessRule>) -> Self {
+        Self {
+            rules: rules.into_iter().collect(),
+        }
+    }
+
+    pub fn rules(&self) -> &[AccessRule] {
+        &self.rules
+    }
+
+    pub fn allows_method(
+        &self,
+        principal: Option<&Principal>,
+        service: &str,
+        method: &str,
+    ) -> bool {
+        self.applicable(principal).any(|rule| {
+            rule.methods
+                .iter()
+                .any(|pattern| method_matches(pattern, service, method))
+        })
+    }
+
+    pub fn allows_topic(&self, principal: Option<&Principal>, topic: &str) -> bool {
+        self.applicable(principal).any(|rule| {
+            rule.topics
+                .iter()
+                .any(|pattern| topic_matches(pattern, topic))
+        })
+    }
+
+    fn applicable<'a>(
+        &'a self,
+        principal: Option<&'a Principal>,
+    ) -> impl Iterator<Item = &'a AccessRule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.applies_to(principal))
+    }
+}
+
+/// Matches the full `service.method` name against an exact name, a prefix
+/// ending in `.*` (`service.*`) or `*`. Service names may contain dots, so
+/// the pattern is never split into service and method parts.
+pub fn method_matches(pattern: &str, service: &str, method: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    let name = format!("{service}.{method}");
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => name
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.') && rest.len() > 1),
+        None => pattern == name,
+    }
+}
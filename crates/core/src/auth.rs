@@ -0,0 +1,230 @@
+//! Caller authentication shared by the transports.
+//!
+//! Transports extract a bearer token from the connection (the HTTP
+//! `Authorization` header, the BLE handshake message) and hand it to an
+//! [`Authenticator`], which resolves it to the [`Principal`] stored in the
+//! request context.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::router::Principal;
+
+/// Reasons a token is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// The caller presented no credentials.
+    Missing,
+    /// The token is malformed, unknown or its signature does not verify.
+    Invalid,
+    /// The token was valid but is past its expiry.
+    Expired,
+}
+
+impl Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Missing => write!(f, "missing credentials"),
+            AuthError::Invalid => write!(f, "invalid token"),
+            AuthError::Expired => write!(f, "token expired"),
+        }
+    }
+}
+
+impl Error for AuthError {}
+
+/// Resolves a bearer token to the identity of the caller.
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, token: &str) -> Result<Principal, AuthError>;
+}
+
+pub type DynAuthenticator = Arc<dyn Authenticator>;
+
+/// Fixed set of bearer tokens, each mapped to a principal.
+///
+/// Suited to a handful of provisioned clients; tokens are compared in
+/// constant time.
+#[derive(Default, Clone)]
+pub struct StaticTokenAuthenticator {
+    tokens: Vec<(String, Principal)>,
+}
+
+impl StaticTokenAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>, principal: Principal) -> Self {
+        self.tokens.push((token.into(), principal));
+        self
+    }
+}
+
+impl Authenticator for StaticTokenAuthenticator {
+    fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+        // Scan every entry so timing does not reveal which token matched.
+        let mut found = None;
+        for (candidate, principal) in &self.tokens {
+            if constant_time_eq(candidate.as_bytes(), token.as_bytes()) {
+                found = Some(principal);
+            }
+        }
+        found.cloned().ok_or(AuthError::Invalid)
+    }
+}
+
+/// Claims carried by an HMAC-signed token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct TokenClaims {
+    sub: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roles: Vec<String>,
+    /// Expiry as seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exp: Option<u64>,
+}
+
+/// Stateless tokens signed with a shared secret.
+///
+/// A token is `base64url(claims JSON) "." base64url(HMAC-SHA256(claims))`,
+/// where the claims are `{ "sub", "roles"?, "exp"? }`. Anyone holding the
+/// key can mint tokens with [`HmacTokenAuthenticator::issue`].
+#[derive(Clone)]
+pub struct HmacTokenAuthenticator {
+    key: Vec<u8>,
+}
+
+impl HmacTokenAuthenticator {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// Mint a token for `principal`, valid until `expires_at` (Unix seconds)
+    /// or forever if `None`.
+    pub fn issue(&self, principal: &Principal, expires_at: Option<u64>) -> String {
+        let claims = TokenClaims {
+            sub: principal.id.clone(),
+            roles: principal.roles.clone(),
+            exp: expires_at,
+        };
+        let claims = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).expect("claims serialize"));
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&claims).finalize().into_bytes());
+        format!("{claims}.{signature}")
+    }
+
+    fn mac(&self, claims: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(claims.as_bytes());
+        mac
+    }
+}
+
+impl Authenticator for HmacTokenAuthenticator {
+    fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+        let (claims, signature) = token.split_once('.').ok_or(AuthError::Invalid)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::Invalid)?;
+        self.mac(claims)
+            .verify_slice(&signature)
+            .map_err(|_| AuthError::Invalid)?;
+
+        let claims = URL_SAFE_NO_PAD
+            .decode(claims)
+            .map_err(|_| AuthError::Invalid)?;
+        let claims: TokenClaims =
+            serde_json::from_slice(&claims).map_err(|_| AuthError::Invalid)?;
+        if let Some(exp) = claims.exp {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0);
+            if now >= exp {
+                return Err(AuthError::Expired);
+            }
+        }
+        Ok(Principal::new(claims.sub).with_roles(claims.roles))
+    }
+}
+
+/// `[auth]` section of the config file.
+///
+/// Either list may be empty, but not both: provisioned `tokens` are checked
+/// first, then tokens signed with `hmac_key`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub tokens: Vec<TokenConfig>,
+    pub hmac_key: Option<String>,
+}
+
+/// One provisioned bearer token and the principal it authenticates as.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenConfig {
+    pub token: String,
+    pub principal: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl AuthConfig {
+    /// Authenticator accepting every configured kind of token.
+    pub fn authenticator(&self) -> DynAuthenticator {
+        let tokens = self
+            .tokens
+            .iter()
+            .fold(StaticTokenAuthenticator::new(), |tokens, entry| {
+                tokens.with_token(
+                    entry.token.clone(),
+                    Principal::new(entry.principal.clone()).with_roles(entry.roles.clone()),
+                )
+            });
+        Arc::new(ConfiguredAuthenticator {
+            tokens,
+            hmac: self
+                .hmac_key
+                .as_ref()
+                .map(|key| HmacTokenAuthenticator::new(key.as_bytes().to_vec())),
+        })
+    }
+}
+
+/// Static tokens first, then HMAC-signed ones.
+struct ConfiguredAuthenticator {
+    tokens: StaticTokenAuthenticator,
+    hmac: Option<HmacTokenAuthenticator>,
+}
+
+impl Authenticator for ConfiguredAuthenticator {
+    fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+        match (self.tokens.authenticate(token), &self.hmac) {
+            (Ok(principal), _) => Ok(principal),
+            (Err(_), Some(hmac)) => hmac.authenticate(token),
+            (Err(err), None) => Err(err),
+        }
+    }
+}
+
+/// Extract the token from an `Authorization: Bearer <token>` header value.
+pub fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -1,15 +1,522 @@
-/// Basic application configuration stub.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    acl::{AccessPolicy, AccessRule},
+    auth::AuthConfig,
+    error::Error,
+    router::{ConcurrencyLimit, IdempotencyConfig, RateLimits},
+    signing::SigningConfig,
+};
+
+/// Values accepted by `[transport] mode`.
+pub const TRANSPORT_MODES: [&str; 4] = ["auto", "http", "ble", "mock"];
+
+/// Application configuration, loaded from a TOML file such as
+/// `configs/default.toml`.
 #[derive(Debug, Clone)]
 pub struct AppConfig {
+    /// Exclusive transport selection (one of [`TRANSPORT_MODES`]), used
+    /// when `transport_enable` is `None`. `mock` serves HTTP when it is
+    /// built in, as the service always did before transports were selected.
     pub transport: String,
+    /// Transports to run side by side from `[transport.enable]`; `None`
+    /// selects a single one by `transport`.
+    pub transport_enable: Option<EnabledTransports>,
+    /// Reachability checks from `[transport.probe]` that keep `auto` mode
+    /// off HTTP while the network is unusable; `None` trusts the link state.
+    pub transport_probe: Option<ProbeConfig>,
     pub logging_level: String,
+    /// Per-method concurrency limits keyed by `service.method`.
+    pub limits: BTreeMap<String, ConcurrencyLimit>,
+    /// Bearer tokens accepted by the HTTP and BLE transports, from
+    /// `[auth]`; `None` serves callers without authenticating them.
+    pub auth: Option<AuthConfig>,
+    /// Access policy from `[[acl]]` rules; `None` allows every call.
+    pub acl: Option<AccessPolicy>,
+    /// Per-caller token buckets from `[rate_limits]`.
+    pub rate_limits: RateLimits,
+    /// Result cache for calls carrying an idempotency key, from
+    /// `[idempotency]`; `None` disables it.
+    pub idempotency: Option<IdempotencyConfig>,
+    /// Request signature checks from `[signing]`; `None` disables them.
+    pub signing: Option<SigningConfig>,
+    /// TLS settings for the HTTP server from `[http.tls]`; `None` serves
+    /// plain HTTP.
+    pub http_tls: Option<TlsConfig>,
+    /// `[http] error_status`: answer failed `POST /rpc` calls with the
+    /// status of the error class instead of `200`.
+    pub http_error_status: bool,
+    /// `[http] max_body_bytes`: requests with larger bodies get `413`.
+    pub http_max_body_bytes: usize,
+    /// `[http] compression`: gzip/deflate responses when the client accepts
+    /// them.
+    pub http_compression: bool,
+    /// Cross-origin policy from `[http.cors]`; `None` sends no CORS headers.
+    pub http_cors: Option<CorsConfig>,
+    /// Unix socket the HTTP router is also served on, from `[http.unix]`.
+    pub http_unix: Option<UnixSocketConfig>,
+    /// Encrypted BLE sessions from `[ble.pairing]`; `None` leaves BLE links
+    /// in plaintext.
+    pub ble_pairing: Option<BlePairingConfig>,
+    /// `[events] client_topics`: topics HTTP and BLE clients may publish
+    /// to, as [`TopicAllowlist`](crate::event::TopicAllowlist) patterns.
+    /// Empty by default, so clients cannot publish.
+    pub events_client_topics: Vec<String>,
+}
+
+/// Per-transport switches for running several transports at once.
+///
+/// `local` serves the HTTP router on the `[http.unix]` socket only, so it
+/// can run without exposing a TCP port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct EnabledTransports {
+    pub http: bool,
+    pub ble: bool,
+    pub local: bool,
+}
+
+/// Connectivity probing for `auto` transport selection.
+///
+/// Every probe checks for a default route and resolves `host`; with `port`
+/// set it also opens a TCP connection to `host:port`. The reported state
+/// only flips after `online_after` passing or `offline_after` failing
+/// probes in a row, so a single lost packet does not switch transports.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProbeConfig {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default = "default_probe_interval_secs")]
+    pub interval_secs: u64,
+    /// Limit for each of the DNS lookup and the TCP connect.
+    #[serde(default = "default_probe_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_probe_online_after")]
+    pub online_after: u32,
+    #[serde(default = "default_probe_offline_after")]
+    pub offline_after: u32,
+}
+
+impl ProbeConfig {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+            interval_secs: default_probe_interval_secs(),
+            timeout_ms: default_probe_timeout_ms(),
+            online_after: default_probe_online_after(),
+            offline_after: default_probe_offline_after(),
+        }
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Consecutive passing and failing probes needed to change state.
+    pub fn with_thresholds(mut self, online_after: u32, offline_after: u32) -> Self {
+        self.online_after = online_after;
+        self.offline_after = offline_after;
+        self
+    }
+}
+
+fn default_probe_interval_secs() -> u64 {
+    10
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_probe_online_after() -> u32 {
+    2
+}
+
+fn default_probe_offline_after() -> u32 {
+    3
+}
+
+/// Default cap on HTTP request bodies.
+pub const DEFAULT_HTTP_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Origins allowed to call the HTTP transport from a browser.
+///
+/// `allowed_origins` holds exact origins such as `https://dashboard.local`,
+/// or `"*"` for any. The protocol's own headers (`Authorization`,
+/// `Content-Type`, `X-Request-Id`, ...) are always allowed;
+/// `allowed_headers` adds more.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    #[serde(default)]
+    pub allowed_headers: Vec<String>,
+    /// How long browsers may cache a preflight response.
+    #[serde(default = "default_cors_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+impl CorsConfig {
+    pub fn new<I, S>(allowed_origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed_origins: allowed_origins.into_iter().map(Into::into).collect(),
+            allowed_headers: Vec::new(),
+            max_age_secs: default_cors_max_age_secs(),
+        }
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+fn default_cors_max_age_secs() -> u64 {
+    600
+}
+
+/// Unix domain socket for local IPC.
+///
+/// `mode` sets the socket file's permission bits (e.g. `0o660` to admit the
+/// owning group only); `None` leaves them to the process umask.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UnixSocketConfig {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub mode: Option<u32>,
+}
+
+impl UnixSocketConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+}
+
+/// Secure pairing for BLE links.
+///
+/// A new central must know `code`; without one a random six-digit code is
+/// generated at startup and printed. Pairing keys of known centrals are kept
+/// in the file at `store_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BlePairingConfig {
+    #[serde(default)]
+    pub code: Option<String>,
+    pub store_path: PathBuf,
+}
+
+/// Certificate material for serving HTTPS.
+///
+/// Paths point at PEM files. With `client_ca_path` set, clients must present
+/// a certificate signed by one of those CAs (mutual TLS).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            client_ca_path: None,
+        }
+    }
+
+    pub fn with_client_ca(mut self, client_ca_path: impl Into<PathBuf>) -> Self {
+        self.client_ca_path = Some(client_ca_path.into());
+        self
+    }
+}
+
+impl AppConfig {
+    /// Parse a config document; missing sections keep their defaults.
+    pub fn from_toml_str(text: &str) -> crate::Result<Self> {
+        let file: ConfigFile =
+            toml::from_str(text).map_err(|err| Error::Configuration(err.to_string()))?;
+        if let Some((name, _)) = file
+            .limits
+            .iter()
+            .find(|(_, limit)| limit.max_concurrency == 0)
+        {
+            return Err(Error::Configuration(format!(
+                "limits.\"{name}\": max_concurrency must be at least 1"
+            )));
+        }
+
+        let rate_limits = file
+            .rate_limits
+            .per_client
+            .iter()
+            .map(|limit| ("rate_limits.per_client".to_string(), limit))
+            .chain(
+                file.rate_limits
+                    .methods
+                    .iter()
+                    .map(|(name, limit)| (format!("rate_limits.methods.\"{name}\""), limit)),
+            );
+        for (name, limit) in rate_limits {
+            let valid = limit.per_second.is_finite() && limit.per_second > 0.0 && limit.burst > 0;
+            if !valid {
+                return Err(Error::Configuration(format!(
+                    "{name}: per_second must be positive and burst at least 1"
+                )));
+            }
+        }
+
+        if file
+            .signing
+            .as_ref()
+            .is_some_and(|signing| signing.key.is_empty())
+        {
+            return Err(Error::Configuration(
+                "signing.key must not be empty".to_string(),
+            ));
+        }
+
+        if let Some(auth) = &file.auth {
+            let problem = if auth.tokens.is_empty() && auth.hmac_key.is_none() {
+                Some("needs at least one of tokens or hmac_key")
+            } else if auth.hmac_key.as_ref().is_some_and(String::is_empty) {
+                Some("hmac_key must not be empty")
+            } else if auth
+                .tokens
+                .iter()
+                .any(|entry| entry.token.is_empty() || entry.principal.is_empty())
+            {
+                Some("tokens need a non-empty token and principal")
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                return Err(Error::Configuration(format!("auth: {problem}")));
+            }
+        }
+
+        if file.http.max_body_bytes == Some(0) {
+            return Err(Error::Configuration(
+                "http.max_body_bytes must be at least 1".to_string(),
+            ));
+        }
+        if let Some(origin) = file
+            .http
+            .cors
+            .iter()
+            .flat_map(|cors| &cors.allowed_origins)
+            .find(|origin| {
+                *origin != "*" && !origin.starts_with("http://") && !origin.starts_with("https://")
+            })
+        {
+            return Err(Error::Configuration(format!(
+                "http.cors.allowed_origins: {origin:?} is not \"*\" or an http(s) origin"
+            )));
+        }
+
+        if file
+            .http
+            .unix
+            .as_ref()
+            .and_then(|unix| unix.mode)
+            .is_some_and(|mode| mode > 0o777)
+        {
+            return Err(Error::Configuration(
+                "http.unix.mode must be permission bits no greater than 0o777".to_string(),
+            ));
+        }
+
+        if file
+            .transport
+            .enable
+            .is_some_and(|enable| enable.local && file.http.unix.is_none())
+        {
+            return Err(Error::Configuration(
+                "transport.enable.local requires an [http.unix] socket".to_string(),
+            ));
+        }
+
+        if let Some(mode) = file
+            .transport
+            .mode
+            .as_deref()
+            .filter(|mode| !TRANSPORT_MODES.contains(mode))
+        {
+            return Err(Error::Configuration(format!(
+                "transport.mode: {mode:?} is not one of {}",
+                TRANSPORT_MODES.join(", ")
+            )));
+        }
+
+        if let Some(probe) = &file.transport.probe {
+            let problem = if probe.host.is_empty() {
+                Some("host must not be empty")
+            } else if probe.interval_secs == 0 || probe.timeout_ms == 0 {
+                Some("interval_secs and timeout_ms must be at least 1")
+            } else if probe.online_after == 0 || probe.offline_after == 0 {
+                Some("online_after and offline_after must be at least 1")
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                return Err(Error::Configuration(format!("transport.probe.{problem}")));
+            }
+        }
+
+        if let Some(pairing) = &file.ble.pairing {
+            if pairing.code.as_ref().is_some_and(|code| code.len() < 6) {
+                return Err(Error::Configuration(
+                    "ble.pairing.code must be at least 6 characters".to_string(),
+                ));
+            }
+            if pairing.store_path.as_os_str().is_empty() {
+                return Err(Error::Configuration(
+                    "ble.pairing.store_path must not be empty".to_string(),
+                ));
+            }
+        }
+
+        if let Some(pattern) = file.events.client_topics.iter().find(|pattern| {
+            let prefix = pattern.strip_suffix("/*").unwrap_or(pattern);
+            pattern.as_str() != "*" && (prefix.is_empty() || prefix.contains('*'))
+        }) {
+            return Err(Error::Configuration(format!(
+                "events.client_topics: {pattern:?} is not a topic, a \"prefix/*\" or \"*\""
+            )));
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            transport: file.transport.mode.unwrap_or(defaults.transport),
+            transport_enable: file.transport.enable,
+            transport_probe: file.transport.probe,
+            logging_level: file.logging.level.unwrap_or(defaults.logging_level),
+            limits: file.limits,
+            auth: file.auth,
+            acl: file.acl.map(AccessPolicy::new),
+            rate_limits: file.rate_limits,
+            idempotency: file.idempotency,
+            signing: file.signing,
+            http_tls: file.http.tls,
+            http_error_status: file.http.error_status,
+            http_max_body_bytes: file
+                .http
+                .max_body_bytes
+                .unwrap_or(defaults.http_max_body_bytes),
+            http_compression: file.http.compression,
+            http_cors: file.http.cors,
+            http_unix: file.http.unix,
+            ble_pairing: file.ble.pairing,
+            events_client_topics: file.events.client_topics,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| Error::Configuration(format!("{}: {err}", path.display())))?;
+        Self::from_toml_str(&text)
+    }
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             transport: "mock".to_string(),
+            transport_enable: None,
+            transport_probe: None,
             logging_level: "info".to_string(),
+            limits: BTreeMap::new(),
+            auth: None,
+            acl: None,
+            rate_limits: RateLimits::default(),
+            idempotency: None,
+            signing: None,
+            http_tls: None,
+            http_error_status: false,
+            http_max_body_bytes: DEFAULT_HTTP_MAX_BODY_BYTES,
+            http_compression: false,
+            http_cors: None,
+            http_unix: None,
+            ble_pairing: None,
+            events_client_topics: Vec::new(),
         }
     }
 }
+
+/// On-disk layout of the config file.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ConfigFile {
+    transport: TransportSection,
+    logging: LoggingSection,
+    limits: BTreeMap<String, ConcurrencyLimit>,
+    auth: Option<AuthConfig>,
+    acl: Option<Vec<AccessRule>>,
+    rate_limits: RateLimits,
+    idempotency: Option<IdempotencyConfig>,
+    signing: Option<SigningConfig>,
+    http: HttpSection,
+    ble: BleSection,
+    events: EventsSection,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct TransportSection {
+    mode: Option<String>,
+    enable: Option<EnabledTransports>,
+    probe: Option<ProbeConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LoggingSection {
+    level: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct HttpSection {
+    tls: Option<TlsConfig>,
+    error_status: bool,
+    max_body_bytes: Option<usize>,
+    compression: bool,
+    cors: Option<CorsConfig>,
+    unix: Option<UnixSocketConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct BleSection {
+    pairing: Option<BlePairingConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct EventsSection {
+    client_topics: Vec<String>,
+}
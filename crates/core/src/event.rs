@@ -2,6 +2,8 @@ use std::{error::Error, fmt::Display, sync::Arc};
 
 use tokio::sync::broadcast;
 
+use crate::{acl::AccessPolicy, router::Principal};
+
 /// Event payload emitted over transports.
 #[derive(Debug, Clone)]
 pub struct TransportEvent {
@@ -13,12 +15,25 @@ pub struct TransportEvent {
 pub enum EventError {
     Publish(String),
     Receive(String),
+    /// The subscriber fell behind and the given number of events were dropped.
+    Lagged(u64),
+    /// A client attempted to publish to a topic outside the allowlist.
+    TopicNotAllowed(String),
+    /// The access policy does not let the caller use the topic.
+    PermissionDenied(String),
 }
 
 impl Display for EventError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             EventError::Publish(msg) | EventError::Receive(msg) => write!(f, "{}", msg),
+            EventError::Lagged(skipped) => write!(f, "subscriber lagged by {skipped} events"),
+            EventError::TopicNotAllowed(topic) => {
+                write!(f, "clients may not publish to topic '{topic}'")
+            }
+            EventError::PermissionDenied(topic) => {
+                write!(f, "caller may not use topic '{topic}'")
+            }
         }
     }
 }
@@ -36,10 +51,10 @@ impl EventSubscription {
     }
 
     pub async fn recv(&mut self) -> Result<TransportEvent, EventError> {
-        self.receiver
-            .recv()
-            .await
-            .map_err(|err| EventError::Receive(err.to_string()))
+        self.receiver.recv().await.map_err(|err| match err {
+            broadcast::error::RecvError::Lagged(skipped) => EventError::Lagged(skipped),
+            broadcast::error::RecvError::Closed => EventError::Receive(err.to_string()),
+        })
     }
 }
 
@@ -58,3 +73,101 @@ impl<T> EventBus for T where T: EventPublisher + EventSubscriber {}
 
 /// Shared type alias for an event bus trait object.
 pub type DynEventBus = Arc<dyn EventBus>;
+
+/// Set of topics remote clients are allowed to publish to.
+///
+/// Patterns are either exact topics (`phone/battery`), a prefix ending in
+/// `/*` matching every topic below it (`phone/*`), or `*` matching anything.
+/// The default allowlist is empty, so clients cannot publish at all.
+#[derive(Debug, Clone, Default)]
+pub struct TopicAllowlist {
+    patterns: Vec<String>,
+}
+
+impl TopicAllowlist {
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            patterns: patterns.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn allows(&self, topic: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| topic_matches(pattern, topic))
+    }
+}
+
+/// Matches a topic against an allowlist-style pattern (see [`TopicAllowlist`]).
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => topic
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/') && rest.len() > 1),
+        None => pattern == topic,
+    }
+}
+
+/// Gateway used by transports to publish client-originated events onto the bus.
+#[derive(Clone)]
+pub struct ClientEventPublisher {
+    events: DynEventBus,
+    allowlist: TopicAllowlist,
+    policy: Option<Arc<AccessPolicy>>,
+}
+
+impl ClientEventPublisher {
+    pub fn new(events: DynEventBus, allowlist: TopicAllowlist) -> Self {
+        Self {
+            events,
+            allowlist,
+            policy: None,
+        }
+    }
+
+    /// Additionally restrict publishing and delivery per caller.
+    pub fn with_policy(mut self, policy: Option<Arc<AccessPolicy>>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Event bus the gateway publishes to.
+    pub fn events(&self) -> &DynEventBus {
+        &self.events
+    }
+
+    /// Publish an event received from an anonymous remote client.
+    pub fn publish(&self, event: TransportEvent) -> Result<(), EventError> {
+        self.publish_as(None, event)
+    }
+
+    /// Publish an event received from a remote client, enforcing the
+    /// allowlist and then the caller's access policy.
+    pub fn publish_as(
+        &self,
+        principal: Option<&Principal>,
+        event: TransportEvent,
+    ) -> Result<(), EventError> {
+        if event.topic.trim().is_empty() || !self.allowlist.allows(&event.topic) {
+            return Err(EventError::TopicNotAllowed(event.topic));
+        }
+        if !self.may_use(principal, &event.topic) {
+            return Err(EventError::PermissionDenied(event.topic));
+        }
+        self.events.publish(event)
+    }
+
+    /// Whether the caller may publish to or receive events on `topic`.
+    pub fn may_use(&self, principal: Option<&Principal>, topic: &str) -> bool {
+        self.policy
+            .as_ref()
+            .is_none_or(|policy| policy.allows_topic(principal, topic))
+    }
+}
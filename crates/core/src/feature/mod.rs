@@ -1,13 +1,25 @@
 use std::{future::Future, pin::Pin, sync::Arc};
 
+use tokio_util::sync::CancellationToken;
+
 use crate::{event::DynEventBus, router::RpcRegistry, types::Clock};
 
+mod registry;
+
+pub use registry::{
+    FeatureRegistry, FeatureStatus, ADMIN_ROLE, ADMIN_SERVICE, METHOD_DISABLE_FEATURE,
+    METHOD_ENABLE_FEATURE, METHOD_FEATURES,
+};
+
 /// Context provided to features during initialization.
 #[derive(Clone)]
 pub struct FeatureContext {
     pub router: Arc<dyn RpcRegistry>,
     pub events: DynEventBus,
     pub clock: Arc<dyn Clock>,
+    /// Cancelled when the feature is disabled. Background tasks such as event
+    /// subscription loops must stop once it fires.
+    pub shutdown: CancellationToken,
 }
 
 impl FeatureContext {
@@ -16,8 +28,14 @@ impl FeatureContext {
             router,
             events,
             clock,
+            shutdown: CancellationToken::new(),
         }
     }
+
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
 }
 
 pub type FeatureFuture<'a> = Pin<Box<dyn Future<Output = FeatureResult<()>> + Send + 'a>>;
@@ -49,4 +67,11 @@ impl std::error::Error for FeatureInitError {}
 pub trait Feature: Send + Sync {
     fn name(&self) -> &'static str;
     fn init(&self, ctx: FeatureContext) -> FeatureFuture<'_>;
+    /// Release resources not tied to the context's shutdown token.
+    ///
+    /// Called after the feature's handlers are removed and its token is
+    /// cancelled; the default does nothing.
+    fn shutdown(&self) -> FeatureFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
 }
@@ -0,0 +1,370 @@
+//! Runtime lifecycle of features: enabling, disabling and the `admin` RPCs.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    event::DynEventBus,
+    feature::{Feature, FeatureContext, FeatureInitError, FeatureResult},
+    router::{
+        rpc_handler, MethodDescriptor, RouterError, RpcError, RpcFuture, RpcHandler, RpcRegistry,
+        RpcRequest, RpcResponse, RpcStream, StreamingRpcHandler,
+    },
+    types::Clock,
+};
+
+/// Service name of the feature administration methods.
+pub const ADMIN_SERVICE: &str = "admin";
+/// `admin.features`: responds with a JSON array of `{name, enabled}`.
+pub const METHOD_FEATURES: &str = "features";
+/// `admin.enable_feature`: payload is the UTF-8 feature name.
+pub const METHOD_ENABLE_FEATURE: &str = "enable_feature";
+/// `admin.disable_feature`: payload is the UTF-8 feature name.
+pub const METHOD_DISABLE_FEATURE: &str = "disable_feature";
+/// Role a caller's principal must hold to use the `admin` service.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Name and state of a feature known to a [`FeatureRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureStatus {
+    pub name: String,
+    pub enabled: bool,
+}
+
+struct Entry {
+    feature: Arc<dyn Feature>,
+    /// Present while the feature is enabled.
+    shutdown: Option<CancellationToken>,
+}
+
+/// Owns the service's features and switches them on and off at runtime.
+///
+/// Each feature initializes against a router view that tags every method it
+/// registers with the feature name, so disabling a feature removes exactly
+/// its handlers. Disabling also cancels [`FeatureContext::shutdown`], which
+/// stops the feature's event subscriptions and background tasks.
+pub struct FeatureRegistry {
+    router: Arc<dyn RpcRegistry>,
+    events: DynEventBus,
+    clock: Arc<dyn Clock>,
+    features: Mutex<BTreeMap<String, Entry>>,
+    /// Serializes enable/disable so a feature is never initialized twice.
+    lifecycle: tokio::sync::Mutex<()>,
+}
+
+impl FeatureRegistry {
+    pub fn new(router: Arc<dyn RpcRegistry>, events: DynEventBus, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            router,
+            events,
+            clock,
+            features: Mutex::new(BTreeMap::new()),
+            lifecycle: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Add a feature in the disabled state.
+    pub fn add(&self, feature: Arc<dyn Feature>) -> FeatureResult<()> {
+        let name = feature.name();
+        let mut features = self.features.lock().expect("feature mutex poisoned");
+        if features.contains_key(name) {
+            return Err(FeatureInitError::new(format!(
+                "feature '{name}' already added"
+            )));
+        }
+        features.insert(
+            name.to_string(),
+            Entry {
+                feature,
+                shutdown: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Initialize a feature; enabling an enabled feature is a no-op.
+    ///
+    /// If initialization fails, anything the feature registered is removed.
+    pub async fn enable(&self, name: &str) -> FeatureResult<()> {
+        let _lifecycle = self.lifecycle.lock().await;
+        let feature = {
+            let features = self.features.lock().expect("feature mutex poisoned");
+            let entry = features.get(name).ok_or_else(|| unknown_feature(name))?;
+            if entry.shutdown.is_some() {
+                return Ok(());
+            }
+            entry.feature.clone()
+        };
+
+        let shutdown = CancellationToken::new();
+        let scope: Arc<dyn RpcRegistry> = Arc::new(FeatureScope {
+            inner: self.router.clone(),
+            feature: name.to_string(),
+        });
+        let ctx = FeatureContext::new(scope, self.events.clone(), self.clock.clone())
+            .with_shutdown(shutdown.clone());
+        if let Err(err) = feature.init(ctx).await {
+            self.router.unregister_feature(name);
+            shutdown.cancel();
+            return Err(err);
+        }
+
+        let mut features = self.features.lock().expect("feature mutex poisoned");
+        if let Some(entry) = features.get_mut(name) {
+            entry.shutdown = Some(shutdown);
+        }
+        Ok(())
+    }
+
+    /// Remove a feature's handlers and stop its background work; disabling a
+    /// disabled feature is a no-op.
+    ///
+    /// Calls already dispatched to the feature run to completion.
+    pub async fn disable(&self, name: &str) -> FeatureResult<()> {
+        let _lifecycle = self.lifecycle.lock().await;
+        let (feature, shutdown) = {
+            let mut features = self.features.lock().expect("feature mutex poisoned");
+            let entry = features
+                .get_mut(name)
+                .ok_or_else(|| unknown_feature(name))?;
+            match entry.shutdown.take() {
+                Some(shutdown) => (entry.feature.clone(), shutdown),
+                None => return Ok(()),
+            }
+        };
+
+        self.router.unregister_feature(name);
+        shutdown.cancel();
+        feature.shutdown().await
+    }
+
+    /// Enable every added feature, stopping at the first failure.
+    pub async fn enable_all(&self) -> FeatureResult<()> {
+        for status in self.features() {
+            self.enable(&status.name).await?;
+        }
+        Ok(())
+    }
+
+    /// Known features sorted by name.
+    pub fn features(&self) -> Vec<FeatureStatus> {
+        let features = self.features.lock().expect("feature mutex poisoned");
+        features
+            .iter()
+            .map(|(name, entry)| FeatureStatus {
+                name: name.clone(),
+                enabled: entry.shutdown.is_some(),
+            })
+            .collect()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let features = self.features.lock().expect("feature mutex poisoned");
+        features
+            .get(name)
+            .is_some_and(|entry| entry.shutdown.is_some())
+    }
+
+    /// Register the `admin` service on the underlying router.
+    ///
+    /// Only callers authenticated with the [`ADMIN_ROLE`] role may use it;
+    /// anonymous callers get `unauthenticated` and others
+    /// `permission_denied`, on top of any access policy. The handlers hold a
+    /// weak reference, so they fail with `unavailable` once the registry is
+    /// dropped.
+    pub fn register_admin(self: &Arc<Self>) -> Result<(), RouterError> {
+        let registry = Arc::downgrade(self);
+        self.router.register_described(
+            MethodDescriptor::new(ADMIN_SERVICE, METHOD_FEATURES)
+                .description("List features and whether they are enabled")
+                .response_schema("JSON array of `{name, enabled}`"),
+            rpc_handler(move |req: RpcRequest| {
+                let registry = registry.clone();
+                async move {
+                    require_admin(&req)?;
+                    let features = upgrade(&registry)?.features();
+                    serde_json::to_vec(&features)
+                        .map(|payload| RpcResponse { payload })
+                        .map_err(|err| RpcError::Internal(err.to_string()))
+                }
+            }),
+        )?;
+
+        self.router.register_described(
+            MethodDescriptor::new(ADMIN_SERVICE, METHOD_ENABLE_FEATURE)
+                .description("Initialize a feature and register its handlers")
+                .request_schema("UTF-8 feature name")
+                .response_schema("empty"),
+            lifecycle_handler(Arc::downgrade(self), true),
+        )?;
+
+        self.router.register_described(
+            MethodDescriptor::new(ADMIN_SERVICE, METHOD_DISABLE_FEATURE)
+                .description("Remove a feature's handlers and stop its subscriptions")
+                .request_schema("UTF-8 feature name")
+                .response_schema("empty"),
+            lifecycle_handler(Arc::downgrade(self), false),
+        )
+    }
+}
+
+fn lifecycle_handler(registry: Weak<FeatureRegistry>, enable: bool) -> RpcHandler {
+    rpc_handler(move |req: RpcRequest| {
+        let registry = registry.clone();
+        async move {
+            require_admin(&req)?;
+            let registry = upgrade(&registry)?;
+            let name = std::str::from_utf8(&req.payload)
+                .map_err(|err| RpcError::Decode(err.to_string()))?;
+            if !registry
+                .features()
+                .iter()
+                .any(|feature| feature.name == name)
+            {
+                return Err(RpcError::NotFound {
+                    resource: format!("feature '{name}'"),
+                });
+            }
+            let result = if enable {
+                registry.enable(name).await
+            } else {
+                registry.disable(name).await
+            };
+            result
+                .map(|()| RpcResponse {
+                    payload: Vec::new(),
+                })
+                .map_err(|err| RpcError::Internal(err.to_string()))
+        }
+    })
+}
+
+fn require_admin(req: &RpcRequest) -> Result<(), RpcError> {
+    match &req.context.principal {
+        Some(principal) if principal.has_role(ADMIN_ROLE) => Ok(()),
+        Some(principal) => Err(RpcError::PermissionDenied(format!(
+            "{} lacks the {ADMIN_ROLE} role",
+            principal.id
+        ))),
+        None => Err(RpcError::Unauthenticated(format!(
+            "{ADMIN_SERVICE}.{} requires an authenticated caller",
+            req.method
+        ))),
+    }
+}
+
+fn upgrade(registry: &Weak<FeatureRegistry>) -> Result<Arc<FeatureRegistry>, RpcError> {
+    registry
+        .upgrade()
+        .ok_or_else(|| RpcError::Unavailable("feature registry dropped".to_string()))
+}
+
+fn unknown_feature(name: &str) -> FeatureInitError {
+    FeatureInitError::new(format!("unknown feature '{name}'"))
+}
+
+/// Router view handed to a feature; stamps registrations with its name.
+struct FeatureScope {
+    inner: Arc<dyn RpcRegistry>,
+    feature: String,
+}
+
+impl FeatureScope {
+    fn stamp(&self, descriptor: MethodDescriptor) -> MethodDescriptor {
+        descriptor.feature(self.feature.clone())
+    }
+}
+
+impl RpcRegistry for FeatureScope {
+    fn register_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError> {
+        self.inner
+            .register_described(self.stamp(descriptor), handler)
+    }
+
+    fn register_stream_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError> {
+        self.inner
+            .register_stream_described(self.stamp(descriptor), handler)
+    }
+
+    fn replace_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError> {
+        self.inner
+            .replace_described(self.stamp(descriptor), handler)
+    }
+
+    fn replace_stream_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError> {
+        self.inner
+            .replace_stream_described(self.stamp(descriptor), handler)
+    }
+
+    /// Only methods this feature registered may be removed.
+    fn unregister(&self, service: &str, method: &str) -> Result<MethodDescriptor, RouterError> {
+        let owner = self
+            .inner
+            .methods()
+            .into_iter()
+            .find(|descriptor| descriptor.service == service && descriptor.method == method)
+            .map(|descriptor| descriptor.feature);
+        match owner {
+            Some(Some(feature)) if feature == self.feature => {
+                self.inner.unregister(service, method)
+            }
+            Some(_) => Err(RouterError::NotOwned {
+                service: service.to_string(),
+                method: method.to_string(),
+            }),
+            None => Err(RouterError::NotRegistered {
+                service: service.to_string(),
+                method: method.to_string(),
+            }),
+        }
+    }
+
+    /// A feature may only remove its own methods; other names remove nothing.
+    fn unregister_feature(&self, feature: &str) -> Vec<MethodDescriptor> {
+        if feature != self.feature {
+            return Vec::new();
+        }
+        self.inner.unregister_feature(feature)
+    }
+
+    fn methods(&self) -> Vec<MethodDescriptor> {
+        self.inner.methods()
+    }
+
+    fn dispatch(&self, req: RpcRequest) -> RpcFuture {
+        self.inner.dispatch(req)
+    }
+
+    fn dispatch_stream(&self, req: RpcRequest) -> RpcStream {
+        self.inner.dispatch_stream(req)
+    }
+
+    fn cancel(&self, request_id: &str) -> bool {
+        self.inner.cancel(request_id)
+    }
+
+    fn cancel_all(&self) {
+        self.inner.cancel_all();
+    }
+}
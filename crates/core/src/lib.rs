@@ -1,24 +1,42 @@
 //! Core abstractions for the service project.
 
+pub mod acl;
+pub mod auth;
 pub mod config;
 pub mod error;
 pub mod event;
 pub mod feature;
 pub mod manager;
 pub mod router;
+pub mod signing;
 pub mod transport;
 pub mod types;
 
-pub use config::AppConfig;
+pub use acl::{AccessPolicy, AccessRule};
+pub use auth::{
+    AuthConfig, AuthError, Authenticator, DynAuthenticator, HmacTokenAuthenticator,
+    StaticTokenAuthenticator, TokenConfig,
+};
+pub use config::{
+    AppConfig, BlePairingConfig, CorsConfig, EnabledTransports, ProbeConfig, TlsConfig,
+    UnixSocketConfig,
+};
 pub use error::{Error, Result};
 pub use event::{
-    EventBus, EventError, EventPublisher, EventSubscriber, EventSubscription, TransportEvent,
+    ClientEventPublisher, EventBus, EventError, EventPublisher, EventSubscriber, EventSubscription,
+    TopicAllowlist, TransportEvent,
+};
+pub use feature::{
+    Feature, FeatureContext, FeatureFuture, FeatureInitError, FeatureRegistry, FeatureResult,
+    FeatureStatus,
 };
-pub use feature::{Feature, FeatureContext, FeatureFuture, FeatureInitError, FeatureResult};
-pub use manager::{TransportManager, TransportManagerApi};
+pub use manager::{TransportManager, TransportManagerApi, TransportSupervisor, TransportTask};
 pub use router::{
-    rpc_handler, InMemoryRouter, RouterError, RpcError, RpcHandler, RpcRegistry, RpcRequest,
-    RpcResponse,
+    rpc_handler, rpc_stream_handler, CancellationToken, ConcurrencyLimit, IdempotencyConfig,
+    InMemoryRouter, MethodDescriptor, Peer, Principal, RateLimit, RateLimits, RouterError,
+    RpcContext, RpcError, RpcHandler, RpcRegistry, RpcRequest, RpcResponse, RpcStream,
+    RpcStreamSender, StreamingRpcHandler,
 };
+pub use signing::{RequestSigner, RequestVerifier, SigningConfig};
 pub use transport::Transport;
 pub use types::{Clock, FeatureId, SystemClock, TransportId};
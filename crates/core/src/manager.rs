@@ -1,4 +1,17 @@
-use crate::{error::Result, transport::Transport, types::TransportId};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use tokio::task::JoinHandle;
+
+use crate::{
+    error::{Error, Result},
+    transport::Transport,
+    types::TransportId,
+};
 
 /// API for managing transports at runtime.
 pub trait TransportManagerApi {
@@ -7,6 +20,10 @@ pub trait TransportManagerApi {
 }
 
 /// Placeholder manager implementation.
+///
+/// Switching transports at runtime, including cancelling the calls of the
+/// one being stopped, is done by `service_platform::autoselect` on a
+/// [`TransportSupervisor`].
 pub struct TransportManager;
 
 impl TransportManager {
@@ -40,3 +57,144 @@ impl Transport for TransportManager {
         Ok(())
     }
 }
+
+/// Future running one transport until it fails or is stopped.
+pub type TransportTask = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+type Launcher = Arc<dyn Fn() -> TransportTask + Send + Sync>;
+
+type FailureHandler = Arc<dyn Fn(TransportId, Error) + Send + Sync>;
+
+struct Slot {
+    launch: Launcher,
+    task: Option<JoinHandle<()>>,
+}
+
+/// Runs several transports side by side, each in its own task.
+///
+/// Transports are added with a launcher that builds their serving future;
+/// they share whatever registry and event bus the launchers captured.
+/// [`start`](Self::start) and [`stop`](Self::stop) act on one transport and
+/// leave the others running. A transport whose task fails is passed to the
+/// [failure handler](Self::with_failure_handler), if any, and counts as
+/// stopped until started again.
+pub struct TransportSupervisor {
+    transports: Mutex<BTreeMap<TransportId, Slot>>,
+    on_failure: Option<FailureHandler>,
+}
+
+impl TransportSupervisor {
+    pub fn new() -> Self {
+        Self {
+            transports: Mutex::new(BTreeMap::new()),
+            on_failure: None,
+        }
+    }
+
+    /// Call `on_failure` with the id and error of every transport whose task
+    /// ends with an error.
+    pub fn with_failure_handler<F>(mut self, on_failure: F) -> Self
+    where
+        F: Fn(TransportId, Error) + Send + Sync + 'static,
+    {
+        self.on_failure = Some(Arc::new(on_failure));
+        self
+    }
+
+    /// Register a transport under `id` without starting it.
+    pub fn add<F, Fut>(&self, id: impl Into<TransportId>, launch: F) -> Result<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let id = id.into();
+        let mut transports = self.transports.lock().expect("transport lock");
+        if transports.contains_key(&id) {
+            return Err(Error::Transport(format!("transport '{id}' already added")));
+        }
+        let launch: Launcher = Arc::new(move || Box::pin(launch()) as TransportTask);
+        transports.insert(id, Slot { launch, task: None });
+        Ok(())
+    }
+
+    /// Spawn the transport's task; a no-op if it is already running.
+    pub fn start(&self, id: &str) -> Result<()> {
+        let mut transports = self.transports.lock().expect("transport lock");
+        let slot = transports
+            .get_mut(id)
+            .ok_or_else(|| Error::Transport(format!("unknown transport '{id}'")))?;
+        if slot.task.as_ref().is_some_and(|task| !task.is_finished()) {
+            return Ok(());
+        }
+        let task = (slot.launch)();
+        let id = id.to_string();
+        let on_failure = self.on_failure.clone();
+        slot.task = Some(tokio::spawn(async move {
+            if let (Err(err), Some(on_failure)) = (task.await, on_failure) {
+                on_failure(id, err);
+            }
+        }));
+        Ok(())
+    }
+
+    /// Abort the transport's task and wait for it to wind down.
+    ///
+    /// Transports must own their connections from the serving future (as the
+    /// HTTP and BLE transports do) so that aborting it closes them; calls
+    /// they are serving at the time are dropped and thereby cancelled.
+    pub async fn stop(&self, id: &str) -> Result<()> {
+        let task = {
+            let mut transports = self.transports.lock().expect("transport lock");
+            let slot = transports
+                .get_mut(id)
+                .ok_or_else(|| Error::Transport(format!("unknown transport '{id}'")))?;
+            slot.task.take()
+        };
+        if let Some(task) = task {
+            task.abort();
+            let _ = task.await;
+        }
+        Ok(())
+    }
+
+    pub fn start_all(&self) -> Result<()> {
+        for id in self.transports() {
+            self.start(&id)?;
+        }
+        Ok(())
+    }
+
+    pub async fn stop_all(&self) {
+        for id in self.transports() {
+            let _ = self.stop(&id).await;
+        }
+    }
+
+    /// Every added transport, in id order.
+    pub fn transports(&self) -> Vec<TransportId> {
+        let transports = self.transports.lock().expect("transport lock");
+        transports.keys().cloned().collect()
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        let transports = self.transports.lock().expect("transport lock");
+        transports
+            .get(id)
+            .and_then(|slot| slot.task.as_ref())
+            .is_some_and(|task| !task.is_finished())
+    }
+
+    /// Transports whose task is currently running, in id order.
+    pub fn running(&self) -> Vec<TransportId> {
+        self.transports()
+            .into_iter()
+            .filter(|id| self.is_running(id))
+            .collect()
+    }
+}
+
+impl Default for TransportSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
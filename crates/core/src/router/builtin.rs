@@ -0,0 +1,83 @@
+//! Methods of the reserved `rpc` service, handled by the router itself.
+
+use crate::router::{
+    InMemoryRouter, MethodDescriptor, RpcError, RpcFuture, RpcRegistry, RpcRequest, RpcResponse,
+};
+
+/// Service name reserved for router built-ins.
+pub const BUILTIN_SERVICE: &str = "rpc";
+/// `rpc.cancel`: payload is the UTF-8 request id to cancel; responds with
+/// `true` if an in-flight call of the same caller matched, `false` if none
+/// did. Ids of other callers' calls fail with `permission_denied`.
+pub const METHOD_CANCEL: &str = "cancel";
+/// `rpc.list`: responds with a JSON array of every registered method.
+pub const METHOD_LIST: &str = "list";
+/// `rpc.describe`: payload is a UTF-8 `service.method` name; responds with the
+/// JSON descriptor of that method.
+pub const METHOD_DESCRIBE: &str = "describe";
+
+pub(crate) fn dispatch(router: &InMemoryRouter, req: RpcRequest) -> RpcFuture {
+    let result = match req.method.as_str() {
+        METHOD_CANCEL => cancel(router, &req),
+        METHOD_LIST => to_json(&router.methods()),
+        METHOD_DESCRIBE => describe(router, &req.payload),
+        _ => Err(RpcError::UnknownMethod),
+    };
+    Box::pin(async move { result })
+}
+
+/// Descriptors of the built-in methods.
+pub(crate) fn descriptors() -> Vec<MethodDescriptor> {
+    vec![
+        MethodDescriptor::new(BUILTIN_SERVICE, METHOD_CANCEL)
+            .description("Cancel the caller's in-flight calls by request id")
+            .request_schema("UTF-8 request id")
+            .response_schema("`true` if a call was cancelled, `false` otherwise"),
+        MethodDescriptor::new(BUILTIN_SERVICE, METHOD_LIST)
+            .description("List every registered method")
+            .response_schema("JSON array of method descriptors"),
+        MethodDescriptor::new(BUILTIN_SERVICE, METHOD_DESCRIBE)
+            .description("Describe a single method")
+            .request_schema("UTF-8 `service.method` name")
+            .response_schema("JSON method descriptor"),
+    ]
+}
+
+fn cancel(router: &InMemoryRouter, req: &RpcRequest) -> Result<RpcResponse, RpcError> {
+    let request_id = utf8(&req.payload)?;
+    let cancelled = router.in_flight.cancel_own(&req.context, request_id)?;
+    Ok(RpcResponse {
+        payload: cancelled.to_string().into_bytes(),
+    })
+}
+
+fn describe(router: &InMemoryRouter, payload: &[u8]) -> Result<RpcResponse, RpcError> {
+    let name = utf8(payload)?;
+    if !name.contains('.') {
+        return Err(RpcError::Decode(format!(
+            "expected `service.method`, got '{name}'"
+        )));
+    }
+    // Service and method names may themselves contain dots, so match the
+    // whole registered name rather than splitting it.
+    let descriptor = router
+        .methods()
+        .into_iter()
+        .find(|descriptor| {
+            name.strip_prefix(descriptor.service.as_str())
+                .and_then(|rest| rest.strip_prefix('.'))
+                == Some(descriptor.method.as_str())
+        })
+        .ok_or(RpcError::UnknownMethod)?;
+    to_json(&descriptor)
+}
+
+fn utf8(payload: &[u8]) -> Result<&str, RpcError> {
+    std::str::from_utf8(payload).map_err(|err| RpcError::Decode(err.to_string()))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<RpcResponse, RpcError> {
+    serde_json::to_vec(value)
+        .map(|payload| RpcResponse { payload })
+        .map_err(|err| RpcError::Internal(err.to_string()))
+}
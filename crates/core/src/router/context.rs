@@ -0,0 +1,162 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::types::TransportId;
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Remote end of the connection a call arrived on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Peer {
+    /// TCP peer of an HTTP connection.
+    Socket(SocketAddr),
+    /// Device address of a BLE central.
+    Ble(String),
+    /// Local IPC client, identified by a transport-specific label.
+    Local(String),
+}
+
+impl Display for Peer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Peer::Socket(addr) => write!(f, "{addr}"),
+            Peer::Ble(address) => write!(f, "ble:{address}"),
+            Peer::Local(label) => write!(f, "local:{label}"),
+        }
+    }
+}
+
+/// Authenticated identity of the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub id: String,
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            roles: Vec::new(),
+        }
+    }
+
+    pub fn with_roles<I, S>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|candidate| candidate == role)
+    }
+}
+
+/// Per-call context handed to handlers alongside the request payload.
+///
+/// Transports fill in what they know about the caller; fields they cannot
+/// determine stay `None`.
+#[derive(Debug, Clone)]
+pub struct RpcContext {
+    pub request_id: String,
+    pub transport: Option<TransportId>,
+    pub peer: Option<Peer>,
+    pub principal: Option<Principal>,
+    pub deadline: Option<Instant>,
+    /// Free-form string metadata; HTTP maps request headers here using
+    /// lower-case header names.
+    pub metadata: BTreeMap<String, String>,
+    cancellation: CancellationToken,
+}
+
+impl RpcContext {
+    /// Create a context with a process-unique generated request id.
+    pub fn new() -> Self {
+        let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        Self::with_request_id(format!("req-{id}"))
+    }
+
+    pub fn with_request_id(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            transport: None,
+            peer: None,
+            principal: None,
+            deadline: None,
+            metadata: BTreeMap::new(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Set the deadline `timeout_ms` from now; zero means no deadline.
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.deadline =
+            (timeout_ms > 0).then(|| Instant::now() + Duration::from_millis(timeout_ms));
+        self
+    }
+
+    /// Token triggered when the call is cancelled.
+    ///
+    /// Handlers that spawn work or run long loops should watch this token;
+    /// the router also stops awaiting the handler once it fires.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Time left until the deadline, if one is set.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    /// Key telling callers apart for per-caller state: the principal id when
+    /// authenticated, otherwise the peer (an HTTP peer's port is ignored, so
+    /// a client opening new connections is still one caller).
+    pub(crate) fn caller_key(&self) -> String {
+        if let Some(principal) = &self.principal {
+            return format!("principal:{}", principal.id);
+        }
+        match &self.peer {
+            Some(Peer::Socket(addr)) => format!("ip:{}", addr.ip()),
+            Some(peer) => peer.to_string(),
+            None => "anonymous".to_string(),
+        }
+    }
+
+    /// Feed the identity behind [`caller_key`](Self::caller_key) to `state`
+    /// without allocating the key.
+    pub(crate) fn hash_caller<H: Hasher>(&self, state: &mut H) {
+        match (&self.principal, &self.peer) {
+            (Some(principal), _) => (0u8, &principal.id).hash(state),
+            (None, Some(Peer::Socket(addr))) => (1u8, addr.ip()).hash(state),
+            (None, Some(Peer::Ble(address))) => (2u8, address).hash(state),
+            (None, Some(Peer::Local(label))) => (3u8, label).hash(state),
+            (None, None) => 4u8.hash(state),
+        }
+    }
+}
+
+impl Default for RpcContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
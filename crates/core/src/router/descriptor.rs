@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+use crate::router::ConcurrencyLimit;
+
+/// Description of a registered method, returned by `rpc.list` and `rpc.describe`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodDescriptor {
+    pub service: String,
+    pub method: String,
+    /// Name of the feature that registered the method.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-form schema of the request payload (e.g. a JSON Schema document).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_schema: Option<String>,
+    /// Free-form schema of each response payload.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<String>,
+    /// Concurrency bound in effect; config overrides what the feature declared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<ConcurrencyLimit>,
+    pub streaming: bool,
+}
+
+impl MethodDescriptor {
+    pub fn new(service: impl Into<String>, method: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            method: method.into(),
+            feature: None,
+            description: None,
+            request_schema: None,
+            response_schema: None,
+            limit: None,
+            streaming: false,
+        }
+    }
+
+    pub fn feature(mut self, feature: impl Into<String>) -> Self {
+        self.feature = Some(feature.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn request_schema(mut self, schema: impl Into<String>) -> Self {
+        self.request_schema = Some(schema.into());
+        self
+    }
+
+    pub fn response_schema(mut self, schema: impl Into<String>) -> Self {
+        self.response_schema = Some(schema.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: ConcurrencyLimit) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Fully qualified `service.method` name.
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.service, self.method)
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::router::{RpcError, RpcFuture, RpcRequest, RpcResponse};
+
+/// Metadata key (HTTP header `Idempotency-Key`) naming a retryable call.
+pub const IDEMPOTENCY_KEY_METADATA: &str = "idempotency-key";
+
+/// How long results of calls carrying an idempotency key are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotencyConfig {
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// Upper bound on cached results; the oldest are dropped first.
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+impl IdempotencyConfig {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window_secs: window.as_secs(),
+            max_entries: default_max_entries(),
+        }
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: default_window_secs(),
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+fn default_window_secs() -> u64 {
+    300
+}
+
+fn default_max_entries() -> usize {
+    1024
+}
+
+type CallResult = Result<RpcResponse, RpcError>;
+
+struct Entry {
+    created: Instant,
+    /// Digest of the payload, to catch a key reused for a different call.
+    fingerprint: [u8; 32],
+    result: Arc<OnceCell<CallResult>>,
+}
+
+/// `(caller, service.method, idempotency key)`.
+type EntryKey = (String, String, String);
+
+/// Results of recent calls by idempotency key.
+///
+/// The first call with a key runs the handler; retries within the window get
+/// its result, and retries arriving while it runs wait for it. Calls that
+/// never completed (cancelled, timed out, or shed as overloaded) are not
+/// cached, so a retry runs the handler again.
+pub(crate) struct IdempotencyCache {
+    window: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<EntryKey, Entry>>,
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(config: IdempotencyConfig) -> Self {
+        Self {
+            window: Duration::from_secs(config.window_secs),
+            max_entries: config.max_entries.max(1),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `call` for `req` unless a result for its key is cached.
+    pub(crate) fn run(&self, key: &str, req: &RpcRequest, call: RpcFuture) -> RpcFuture {
+        let entry_key = (
+            req.context.caller_key(),
+            format!("{}.{}", req.service, req.method),
+            key.to_string(),
+        );
+        let fingerprint: [u8; 32] = Sha256::digest(&req.payload).into();
+        let now = Instant::now();
+
+        let result = {
+            let mut entries = self.entries.lock().expect("idempotency cache lock");
+            self.evict(&mut entries, now);
+            let entry = entries.entry(entry_key).or_insert_with(|| Entry {
+                created: now,
+                fingerprint,
+                result: Arc::new(OnceCell::new()),
+            });
+            if entry.fingerprint != fingerprint {
+                let message = format!("idempotency key {key} was used for a different request");
+                return Box::pin(async move { Err(RpcError::Conflict(message)) });
+            }
+            entry.result.clone()
+        };
+
+        Box::pin(async move {
+            let cached = result
+                .get_or_try_init(|| async move {
+                    match call.await {
+                        Err(
+                            err @ (RpcError::Cancelled | RpcError::Overloaded | RpcError::Timeout),
+                        ) => Err(err),
+                        completed => Ok(completed),
+                    }
+                })
+                .await?;
+            cached.clone()
+        })
+    }
+
+    fn evict(&self, entries: &mut HashMap<EntryKey, Entry>, now: Instant) {
+        entries.retain(|_, entry| now.duration_since(entry.created) < self.window);
+        while entries.len() >= self.max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+    }
+}
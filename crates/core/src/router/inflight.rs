@@ -0,0 +1,166 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::router::{RpcContext, RpcError};
+
+/// Number of independently locked shards; a call lands on the shard picked
+/// by its sequence number, so concurrent dispatches rarely share a lock.
+const SHARDS: usize = 16;
+
+/// One tracked call.
+///
+/// The request id and caller are stored as keyed hashes so tracking a call
+/// allocates nothing; the per-router random key keeps clients from crafting
+/// colliding ids.
+struct Call {
+    call: u64,
+    request: u64,
+    caller: u64,
+    token: CancellationToken,
+}
+
+type Shard = Mutex<Vec<Call>>;
+
+/// Cancellation tokens of calls currently being handled, looked up by
+/// request id and tagged with the caller that made them.
+#[derive(Default, Clone)]
+pub(crate) struct InFlight {
+    shards: Arc<[Shard; SHARDS]>,
+    next_call: Arc<AtomicU64>,
+    hasher: RandomState,
+}
+
+impl InFlight {
+    /// Track the call described by `context` until the returned guard is
+    /// dropped.
+    ///
+    /// Dropping the guard before [`InFlightGuard::complete`] cancels the call,
+    /// which is how a dropped dispatch future (client disconnect) propagates
+    /// to handler-spawned work.
+    pub(crate) fn track(&self, context: &RpcContext) -> InFlightGuard {
+        let call = self.next_call.fetch_add(1, Ordering::Relaxed);
+        let token = context.cancellation().clone();
+        let entry = Call {
+            call,
+            request: self.hash_request(&context.request_id),
+            caller: self.hash_caller(context),
+            token: token.clone(),
+        };
+        let shard = call as usize % SHARDS;
+        self.shards[shard]
+            .lock()
+            .expect("in-flight mutex poisoned")
+            .push(entry);
+        InFlightGuard {
+            shards: self.shards.clone(),
+            shard,
+            call,
+            token,
+            completed: false,
+        }
+    }
+
+    /// Cancel every call with `request_id`, returning whether any was found.
+    pub(crate) fn cancel(&self, request_id: &str) -> bool {
+        let request = self.hash_request(request_id);
+        let mut found = false;
+        self.for_each(|call| {
+            if call.request == request {
+                call.token.cancel();
+                found = true;
+            }
+        });
+        found
+    }
+
+    /// Cancel the calls with `request_id` made by the same caller as the
+    /// request carrying `caller`.
+    ///
+    /// Fails with [`RpcError::PermissionDenied`] when the id only matches
+    /// calls of other callers, so one client cannot cancel another's work.
+    pub(crate) fn cancel_own(
+        &self,
+        caller: &RpcContext,
+        request_id: &str,
+    ) -> Result<bool, RpcError> {
+        let request = self.hash_request(request_id);
+        let caller = self.hash_caller(caller);
+        let (mut own, mut others) = (false, false);
+        self.for_each(|call| {
+            if call.request != request {
+                return;
+            }
+            if call.caller == caller {
+                call.token.cancel();
+                own = true;
+            } else {
+                others = true;
+            }
+        });
+        if others && !own {
+            return Err(RpcError::PermissionDenied(format!(
+                "request '{request_id}' belongs to another caller"
+            )));
+        }
+        Ok(own)
+    }
+
+    pub(crate) fn cancel_all(&self) {
+        self.for_each(|call| call.token.cancel());
+    }
+
+    fn for_each(&self, mut visit: impl FnMut(&Call)) {
+        for shard in self.shards.iter() {
+            let calls = shard.lock().expect("in-flight mutex poisoned");
+            calls.iter().for_each(&mut visit);
+        }
+    }
+
+    fn hash_request(&self, request_id: &str) -> u64 {
+        self.hasher.hash_one(request_id)
+    }
+
+    fn hash_caller(&self, context: &RpcContext) -> u64 {
+        let mut state = self.hasher.build_hasher();
+        context.hash_caller(&mut state);
+        state.finish()
+    }
+}
+
+/// Registration of one in-flight call; removes itself when dropped.
+pub(crate) struct InFlightGuard {
+    shards: Arc<[Shard; SHARDS]>,
+    shard: usize,
+    call: u64,
+    token: CancellationToken,
+    completed: bool,
+}
+
+impl InFlightGuard {
+    /// Mark the call as finished so dropping the guard does not cancel it.
+    pub(crate) fn complete(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.token.cancel();
+        }
+        let mut calls = self.shards[self.shard]
+            .lock()
+            .expect("in-flight mutex poisoned");
+        if let Some(index) = calls.iter().position(|call| call.call == self.call) {
+            calls.swap_remove(index);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::router::RpcError;
+
+/// Concurrency bound for a single method.
+///
+/// At most `max_concurrency` calls run at once and at most `queue_depth`
+/// more wait for a slot; anything beyond that fails immediately with
+/// [`RpcError::Overloaded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcurrencyLimit {
+    pub max_concurrency: usize,
+    #[serde(default)]
+    pub queue_depth: usize,
+}
+
+impl ConcurrencyLimit {
+    /// Limit without a queue: calls over `max_concurrency` are rejected.
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency,
+            queue_depth: 0,
+        }
+    }
+
+    pub fn with_queue_depth(mut self, queue_depth: usize) -> Self {
+        self.queue_depth = queue_depth;
+        self
+    }
+}
+
+/// Runtime state enforcing a [`ConcurrencyLimit`].
+pub(crate) struct Limiter {
+    permits: Arc<Semaphore>,
+    queue_depth: usize,
+    queued: AtomicUsize,
+}
+
+impl Limiter {
+    pub(crate) fn new(limit: ConcurrencyLimit) -> Arc<Self> {
+        Arc::new(Self {
+            permits: Arc::new(Semaphore::new(limit.max_concurrency)),
+            queue_depth: limit.queue_depth,
+            queued: AtomicUsize::new(0),
+        })
+    }
+
+    /// Take a slot, waiting in the queue if there is room in it.
+    pub(crate) async fn acquire(&self) -> Result<OwnedSemaphorePermit, RpcError> {
+        if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        if self.queued.fetch_add(1, Ordering::AcqRel) >= self.queue_depth {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+            return Err(RpcError::Overloaded);
+        }
+        // Leaves the queue even if the waiting call is cancelled.
+        let _queued = QueueSlot(&self.queued);
+        self.permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| RpcError::Overloaded)
+    }
+}
+
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Acquire a slot from `limiter`, if the method has one.
+pub(crate) async fn acquire(
+    limiter: Option<Arc<Limiter>>,
+) -> Result<Option<OwnedSemaphorePermit>, RpcError> {
+    match limiter {
+        Some(limiter) => limiter.acquire().await.map(Some),
+        None => Ok(None),
+    }
+}
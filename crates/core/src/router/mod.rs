@@ -0,0 +1,730 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+
+mod builtin;
+mod context;
+mod descriptor;
+mod idempotency;
+mod inflight;
+mod limit;
+mod rate;
+mod stream;
+
+pub use builtin::{BUILTIN_SERVICE, METHOD_CANCEL, METHOD_DESCRIBE, METHOD_LIST};
+pub use context::{Peer, Principal, RpcContext};
+pub use descriptor::MethodDescriptor;
+pub use idempotency::{IdempotencyConfig, IDEMPOTENCY_KEY_METADATA};
+pub use limit::ConcurrencyLimit;
+pub use rate::{RateLimit, RateLimits};
+pub use stream::{
+    rpc_stream_handler, RpcStream, RpcStreamFuture, RpcStreamSender, StreamingRpcHandler,
+};
+pub use tokio_util::sync::CancellationToken;
+
+use crate::{acl::AccessPolicy, signing::RequestVerifier, types::TransportId};
+use idempotency::IdempotencyCache;
+use inflight::InFlight;
+use limit::Limiter;
+use rate::RateLimiter;
+
+/// Request envelope for incoming RPC calls.
+#[derive(Debug, Clone)]
+pub struct RpcRequest {
+    pub service: String,
+    pub method: String,
+    pub payload: Vec<u8>,
+    /// Unary calls still running after this many milliseconds fail with
+    /// [`RpcError::Timeout`]; zero means no deadline.
+    pub timeout_ms: u64,
+    pub context: RpcContext,
+}
+
+impl RpcRequest {
+    pub fn new(
+        service: impl Into<String>,
+        method: impl Into<String>,
+        payload: Vec<u8>,
+        timeout_ms: u64,
+    ) -> Self {
+        Self {
+            service: service.into(),
+            method: method.into(),
+            payload,
+            timeout_ms,
+            context: RpcContext::new().with_timeout_ms(timeout_ms),
+        }
+    }
+
+    /// Replace the generated request id, e.g. with one supplied by the client.
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.context.request_id = request_id.into();
+        self
+    }
+
+    pub fn with_transport(mut self, transport: impl Into<TransportId>) -> Self {
+        self.context.transport = Some(transport.into());
+        self
+    }
+
+    pub fn with_peer(mut self, peer: Peer) -> Self {
+        self.context.peer = Some(peer);
+        self
+    }
+
+    pub fn with_principal(mut self, principal: Principal) -> Self {
+        self.context.principal = Some(principal);
+        self
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Successful RPC response envelope.
+#[derive(Debug, Clone)]
+pub struct RpcResponse {
+    pub payload: Vec<u8>,
+}
+
+/// RPC level errors surfaced to transports.
+///
+/// Transports report [`code`](RpcError::code) and
+/// [`details`](RpcError::details) to clients; both are part of the protocol
+/// and stay stable.
+#[derive(Debug, Clone)]
+pub enum RpcError {
+    /// The payload could not be decoded.
+    Decode(String),
+    UnknownMethod,
+    Internal(String),
+    /// The payload decoded but a value in it is unacceptable.
+    InvalidArgument {
+        /// Offending field, when the handler can name one.
+        field: Option<String>,
+        message: String,
+    },
+    /// A resource the call refers to does not exist.
+    NotFound {
+        resource: String,
+    },
+    /// The call did not finish before its deadline.
+    Timeout,
+    /// Something the method depends on is down; a later retry may succeed.
+    Unavailable(String),
+    /// The call was cancelled before the handler finished.
+    Cancelled,
+    /// The method is at its concurrency limit and its queue is full.
+    Overloaded,
+    /// The transport requires credentials and the caller presented none or
+    /// invalid ones.
+    Unauthenticated(String),
+    /// The caller is not allowed to invoke the method.
+    PermissionDenied(String),
+    /// The caller exceeded a rate limit; it may retry after the given delay.
+    RateLimited {
+        retry_after: Duration,
+    },
+    /// The request conflicts with an earlier one, e.g. an idempotency key
+    /// reused for a different payload.
+    Conflict(String),
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Decode(msg) => write!(f, "failed to decode request: {msg}"),
+            RpcError::UnknownMethod => write!(f, "unknown service or method"),
+            RpcError::Internal(msg) => write!(f, "internal error: {msg}"),
+            RpcError::InvalidArgument {
+                field: Some(field),
+                message,
+            } => write!(f, "invalid argument {field}: {message}"),
+            RpcError::InvalidArgument {
+                field: None,
+                message,
+            } => write!(f, "invalid argument: {message}"),
+            RpcError::NotFound { resource } => write!(f, "not found: {resource}"),
+            RpcError::Timeout => write!(f, "deadline exceeded"),
+            RpcError::Unavailable(msg) => write!(f, "unavailable: {msg}"),
+            RpcError::Cancelled => write!(f, "request cancelled"),
+            RpcError::Overloaded => write!(f, "method overloaded, retry later"),
+            RpcError::Unauthenticated(msg) => write!(f, "unauthenticated: {msg}"),
+            RpcError::PermissionDenied(msg) => write!(f, "permission denied: {msg}"),
+            RpcError::RateLimited { retry_after } => write!(
+                f,
+                "rate limited, retry after {} ms",
+                retry_after.as_millis()
+            ),
+            RpcError::Conflict(msg) => write!(f, "conflict: {msg}"),
+        }
+    }
+}
+
+impl RpcError {
+    /// Stable string code identifying the error class.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RpcError::Decode(_) => "decode",
+            RpcError::UnknownMethod => "unknown_method",
+            RpcError::Internal(_) => "internal",
+            RpcError::InvalidArgument { .. } => "invalid_argument",
+            RpcError::NotFound { .. } => "not_found",
+            RpcError::Timeout => "timeout",
+            RpcError::Unavailable(_) => "unavailable",
+            RpcError::Cancelled => "cancelled",
+            RpcError::Overloaded => "overloaded",
+            RpcError::Unauthenticated(_) => "unauthenticated",
+            RpcError::PermissionDenied(_) => "permission_denied",
+            RpcError::RateLimited { .. } => "rate_limited",
+            RpcError::Conflict(_) => "conflict",
+        }
+    }
+
+    /// Machine-readable fields of the error, keyed by stable names.
+    pub fn details(&self) -> BTreeMap<String, String> {
+        let mut details = BTreeMap::new();
+        match self {
+            RpcError::InvalidArgument {
+                field: Some(field), ..
+            } => {
+                details.insert("field".to_string(), field.clone());
+            }
+            RpcError::NotFound { resource } => {
+                details.insert("resource".to_string(), resource.clone());
+            }
+            RpcError::RateLimited { retry_after } => {
+                details.insert(
+                    "retry_after_ms".to_string(),
+                    retry_after.as_millis().to_string(),
+                );
+            }
+            _ => {}
+        }
+        details
+    }
+
+    /// Delay after which a retry may succeed, for throttled calls.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            RpcError::RateLimited { retry_after } => Some(*retry_after),
+            _ => None,
+        }
+    }
+}
+
+impl Error for RpcError {}
+
+/// Errors emitted by the router during registration.
+#[derive(Debug, Clone)]
+pub enum RouterError {
+    DuplicateRegistration {
+        service: String,
+        method: String,
+    },
+    NotRegistered {
+        service: String,
+        method: String,
+    },
+    /// The method belongs to another feature, which alone may replace or
+    /// remove it.
+    NotOwned {
+        service: String,
+        method: String,
+    },
+    InvalidName,
+    /// A concurrency limit allows no calls at all.
+    InvalidLimit,
+}
+
+impl Display for RouterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouterError::DuplicateRegistration { service, method } => {
+                write!(f, "handler already registered for {service}.{method}")
+            }
+            RouterError::NotRegistered { service, method } => {
+                write!(f, "no handler registered for {service}.{method}")
+            }
+            RouterError::NotOwned { service, method } => {
+                write!(f, "{service}.{method} belongs to another feature")
+            }
+            RouterError::InvalidName => write!(f, "invalid service or method name"),
+            RouterError::InvalidLimit => write!(f, "max_concurrency must be at least 1"),
+        }
+    }
+}
+
+impl Error for RouterError {}
+
+pub type RpcFuture = Pin<Box<dyn Future<Output = Result<RpcResponse, RpcError>> + Send>>;
+pub type RpcHandler = Arc<dyn Fn(RpcRequest) -> RpcFuture + Send + Sync>;
+
+/// Abstraction over server-side RPC routing and dispatching.
+pub trait RpcRegistry: Send + Sync {
+    fn register(
+        &self,
+        service: &str,
+        method: &str,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError> {
+        self.register_described(MethodDescriptor::new(service, method), handler)
+    }
+    /// Register a server-streaming handler; service/method names share one
+    /// namespace with unary handlers.
+    fn register_stream(
+        &self,
+        service: &str,
+        method: &str,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError> {
+        self.register_stream_described(MethodDescriptor::new(service, method), handler)
+    }
+    /// Register a unary handler together with its introspection metadata.
+    fn register_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError>;
+    /// Register a streaming handler together with its introspection metadata.
+    fn register_stream_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError>;
+    /// Atomically swap the unary handler for a method, registering it if absent.
+    ///
+    /// Calls already dispatched keep running against the previous handler.
+    /// The descriptor must name the same feature as the method it replaces,
+    /// otherwise this fails with [`RouterError::NotOwned`].
+    fn replace_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError>;
+    /// Streaming counterpart of [`RpcRegistry::replace_described`].
+    fn replace_stream_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError>;
+    /// Remove a handler, returning its descriptor.
+    fn unregister(&self, service: &str, method: &str) -> Result<MethodDescriptor, RouterError>;
+    /// Remove every method whose descriptor names `feature`; returns what was removed.
+    fn unregister_feature(&self, feature: &str) -> Vec<MethodDescriptor>;
+    /// Descriptors of every registered method, including built-ins, sorted by name.
+    fn methods(&self) -> Vec<MethodDescriptor>;
+    fn dispatch(&self, req: RpcRequest) -> RpcFuture;
+    /// Dispatch as a stream; unary methods yield a single chunk.
+    fn dispatch_stream(&self, req: RpcRequest) -> RpcStream;
+    /// Cancel in-flight calls with the given request id, whoever made them;
+    /// returns whether any matched.
+    ///
+    /// Meant for in-process use; `rpc.cancel` only reaches the caller's own
+    /// calls.
+    fn cancel(&self, request_id: &str) -> bool;
+    /// Cancel every in-flight call, e.g. when the active transport is switched.
+    fn cancel_all(&self);
+}
+
+/// Helper to wrap async closures into an [`RpcHandler`].
+pub fn rpc_handler<F, Fut>(func: F) -> RpcHandler
+where
+    F: Fn(RpcRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<RpcResponse, RpcError>> + Send + 'static,
+{
+    Arc::new(move |req| Box::pin(func(req)))
+}
+
+#[derive(Clone)]
+enum RouteHandler {
+    Unary(RpcHandler),
+    Streaming(StreamingRpcHandler),
+}
+
+#[derive(Clone)]
+struct Route {
+    descriptor: MethodDescriptor,
+    handler: RouteHandler,
+    limiter: Option<Arc<Limiter>>,
+}
+
+impl Route {
+    fn new(descriptor: MethodDescriptor, handler: RouteHandler) -> Self {
+        let limiter = descriptor.limit.map(Limiter::new);
+        Self {
+            descriptor,
+            handler,
+            limiter,
+        }
+    }
+}
+
+/// Routes keyed by service, then method, so lookups borrow the request's
+/// names instead of allocating a composite key.
+type RouteTable = HashMap<String, HashMap<String, Route>>;
+
+/// Simple in-memory router implementation that can be embedded in transports.
+///
+/// Dispatch reads an immutable snapshot of the route table without locking.
+/// Registration copies the table, edits the copy and publishes it; writers
+/// are serialized by a mutex so concurrent edits are not lost.
+#[derive(Default, Clone)]
+pub struct InMemoryRouter {
+    routes: Arc<ArcSwap<RouteTable>>,
+    writer: Arc<Mutex<()>>,
+    in_flight: InFlight,
+    /// Configured limits keyed by `service.method`.
+    limits: Arc<HashMap<String, ConcurrencyLimit>>,
+    policy: Option<Arc<AccessPolicy>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    idempotency: Option<Arc<IdempotencyCache>>,
+    verifier: Option<Arc<RequestVerifier>>,
+}
+
+impl InMemoryRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply configured concurrency limits, keyed by `service.method`, to
+    /// methods registered from now on. They take precedence over limits
+    /// declared in a method's descriptor.
+    pub fn with_limits(
+        mut self,
+        limits: impl IntoIterator<Item = (String, ConcurrencyLimit)>,
+    ) -> Self {
+        self.limits = Arc::new(limits.into_iter().collect());
+        self
+    }
+
+    /// Check every call, built-ins included, against `policy` before it is
+    /// dispatched; denied calls fail with [`RpcError::PermissionDenied`].
+    pub fn with_policy(mut self, policy: Arc<AccessPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Throttle every call, built-ins included, with token buckets per
+    /// caller; calls over a limit fail with [`RpcError::RateLimited`].
+    pub fn with_rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limiter = (!limits.is_empty()).then(|| Arc::new(RateLimiter::new(limits)));
+        self
+    }
+
+    /// Remember results of unary calls carrying an
+    /// [`IDEMPOTENCY_KEY_METADATA`] entry and answer retries from the cache.
+    pub fn with_idempotency(mut self, config: IdempotencyConfig) -> Self {
+        self.idempotency = Some(Arc::new(IdempotencyCache::new(config)));
+        self
+    }
+
+    /// Check request signatures and reject replayed nonces before anything
+    /// else; failures surface as [`RpcError::Unauthenticated`].
+    pub fn with_request_verifier(mut self, verifier: Arc<RequestVerifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Signature, access policy, then rate limits.
+    fn admit(&self, req: &RpcRequest) -> Result<(), RpcError> {
+        if let Some(verifier) = &self.verifier {
+            verifier.verify(req)?;
+        }
+        self.authorize(req)?;
+        match &self.rate_limiter {
+            Some(limiter) => limiter.check(req),
+            None => Ok(()),
+        }
+    }
+
+    /// Unary dispatch of a call that already passed [`Self::admit`].
+    fn dispatch_admitted(&self, req: RpcRequest) -> RpcFuture {
+        if req.service == BUILTIN_SERVICE {
+            return builtin::dispatch(self, req);
+        }
+
+        let (handler, limiter) = match self.get(&req.service, &req.method) {
+            Some((RouteHandler::Unary(handler), limiter)) => (handler, limiter),
+            Some((RouteHandler::Streaming(_), _)) => {
+                let message = format!("{}.{} is a streaming method", req.service, req.method);
+                return Box::pin(async move { Err(RpcError::Internal(message)) });
+            }
+            None => return Box::pin(async { Err(RpcError::UnknownMethod) }),
+        };
+
+        let cached = self.idempotency.as_ref().and_then(|cache| {
+            let key = req.context.metadata(IDEMPOTENCY_KEY_METADATA)?;
+            Some((cache.clone(), key.to_string(), req.clone()))
+        });
+        let token = req.context.cancellation().clone();
+        let remaining = req.context.remaining();
+        let guard = self.in_flight.track(&req.context);
+        let call = async move {
+            let _permit = limit::acquire(limiter).await?;
+            handler(req).await
+        };
+        let call: RpcFuture = Box::pin(async move {
+            let expired = async {
+                match remaining {
+                    Some(remaining) => tokio::time::sleep(remaining).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let result = tokio::select! {
+                result = call => result,
+                _ = token.cancelled() => Err(RpcError::Cancelled),
+                _ = expired => Err(RpcError::Timeout),
+            };
+            match &result {
+                // Dropping the guard cancels work the handler spawned.
+                Err(RpcError::Timeout) => drop(guard),
+                _ => guard.complete(),
+            }
+            result
+        });
+        match cached {
+            Some((cache, key, req)) => cache.run(&key, &req, call),
+            None => call,
+        }
+    }
+
+    fn authorize(&self, req: &RpcRequest) -> Result<(), RpcError> {
+        match &self.policy {
+            Some(policy)
+                if !policy.allows_method(
+                    req.context.principal.as_ref(),
+                    &req.service,
+                    &req.method,
+                ) =>
+            {
+                let caller = req
+                    .context
+                    .principal
+                    .as_ref()
+                    .map_or("anonymous caller", |principal| principal.id.as_str());
+                Err(RpcError::PermissionDenied(format!(
+                    "{caller} may not call {}.{}",
+                    req.service, req.method
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a handler is registered for `service.method`; built-ins excluded.
+    pub fn contains(&self, service: &str, method: &str) -> bool {
+        self.routes
+            .load()
+            .get(service)
+            .is_some_and(|methods| methods.contains_key(method))
+    }
+
+    /// Check names and resolve the effective concurrency limit.
+    fn prepare(&self, mut descriptor: MethodDescriptor) -> Result<MethodDescriptor, RouterError> {
+        let (service, method) = (&descriptor.service, &descriptor.method);
+        if service.trim().is_empty() || method.trim().is_empty() || service == BUILTIN_SERVICE {
+            return Err(RouterError::InvalidName);
+        }
+        if let Some(limit) = self.limits.get(&descriptor.qualified_name()) {
+            descriptor.limit = Some(*limit);
+        }
+        if descriptor
+            .limit
+            .is_some_and(|limit| limit.max_concurrency == 0)
+        {
+            return Err(RouterError::InvalidLimit);
+        }
+        Ok(descriptor)
+    }
+
+    /// Apply `edit` to a copy of the route table and publish the copy.
+    fn update<T>(&self, edit: impl FnOnce(&mut RouteTable) -> T) -> T {
+        let _writer = self.writer.lock().expect("router mutex poisoned");
+        let mut routes = RouteTable::clone(&self.routes.load());
+        let result = edit(&mut routes);
+        self.routes.store(Arc::new(routes));
+        result
+    }
+
+    fn insert(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RouteHandler,
+    ) -> Result<(), RouterError> {
+        let descriptor = self.prepare(descriptor)?;
+        self.update(|routes| {
+            let methods = routes.entry(descriptor.service.clone()).or_default();
+            if methods.contains_key(&descriptor.method) {
+                return Err(RouterError::DuplicateRegistration {
+                    service: descriptor.service.clone(),
+                    method: descriptor.method.clone(),
+                });
+            }
+            methods.insert(descriptor.method.clone(), Route::new(descriptor, handler));
+            Ok(())
+        })
+    }
+
+    fn upsert(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RouteHandler,
+    ) -> Result<(), RouterError> {
+        let descriptor = self.prepare(descriptor)?;
+        self.update(|routes| {
+            let methods = routes.entry(descriptor.service.clone()).or_default();
+            if let Some(existing) = methods.get(&descriptor.method) {
+                if existing.descriptor.feature != descriptor.feature {
+                    return Err(RouterError::NotOwned {
+                        service: descriptor.service.clone(),
+                        method: descriptor.method.clone(),
+                    });
+                }
+            }
+            methods.insert(descriptor.method.clone(), Route::new(descriptor, handler));
+            Ok(())
+        })
+    }
+
+    fn get(&self, service: &str, method: &str) -> Option<(RouteHandler, Option<Arc<Limiter>>)> {
+        self.routes
+            .load()
+            .get(service)?
+            .get(method)
+            .map(|route| (route.handler.clone(), route.limiter.clone()))
+    }
+}
+
+impl RpcRegistry for InMemoryRouter {
+    fn register_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError> {
+        let mut descriptor = descriptor;
+        descriptor.streaming = false;
+        self.insert(descriptor, RouteHandler::Unary(handler))
+    }
+
+    fn register_stream_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError> {
+        let mut descriptor = descriptor;
+        descriptor.streaming = true;
+        self.insert(descriptor, RouteHandler::Streaming(handler))
+    }
+
+    fn replace_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: RpcHandler,
+    ) -> Result<(), RouterError> {
+        let mut descriptor = descriptor;
+        descriptor.streaming = false;
+        self.upsert(descriptor, RouteHandler::Unary(handler))
+    }
+
+    fn replace_stream_described(
+        &self,
+        descriptor: MethodDescriptor,
+        handler: StreamingRpcHandler,
+    ) -> Result<(), RouterError> {
+        let mut descriptor = descriptor;
+        descriptor.streaming = true;
+        self.upsert(descriptor, RouteHandler::Streaming(handler))
+    }
+
+    fn unregister(&self, service: &str, method: &str) -> Result<MethodDescriptor, RouterError> {
+        if !self.contains(service, method) {
+            return Err(RouterError::NotRegistered {
+                service: service.to_string(),
+                method: method.to_string(),
+            });
+        }
+        self.update(|routes| {
+            let methods = routes.get_mut(service);
+            let removed = methods.and_then(|methods| methods.remove(method));
+            if routes.get(service).is_some_and(HashMap::is_empty) {
+                routes.remove(service);
+            }
+            removed
+                .map(|route| route.descriptor)
+                .ok_or_else(|| RouterError::NotRegistered {
+                    service: service.to_string(),
+                    method: method.to_string(),
+                })
+        })
+    }
+
+    fn unregister_feature(&self, feature: &str) -> Vec<MethodDescriptor> {
+        self.update(|routes| {
+            let mut removed = Vec::new();
+            for methods in routes.values_mut() {
+                methods.retain(|_, route| {
+                    if route.descriptor.feature.as_deref() == Some(feature) {
+                        removed.push(route.descriptor.clone());
+                        false
+                    } else {
+                        true
+                    }
+                });
+            }
+            routes.retain(|_, methods| !methods.is_empty());
+            removed
+        })
+    }
+
+    fn methods(&self) -> Vec<MethodDescriptor> {
+        let mut methods = builtin::descriptors();
+        let routes = self.routes.load();
+        methods.extend(
+            routes
+                .values()
+                .flat_map(HashMap::values)
+                .map(|route| route.descriptor.clone()),
+        );
+        methods.sort_by(|a, b| (&a.service, &a.method).cmp(&(&b.service, &b.method)));
+        methods
+    }
+
+    fn dispatch(&self, req: RpcRequest) -> RpcFuture {
+        if let Err(err) = self.admit(&req) {
+            return Box::pin(async move { Err(err) });
+        }
+        self.dispatch_admitted(req)
+    }
+
+    fn dispatch_stream(&self, req: RpcRequest) -> RpcStream {
+        if let Err(err) = self.admit(&req) {
+            return RpcStream::terminated(Err(err));
+        }
+        match self.get(&req.service, &req.method) {
+            Some((RouteHandler::Streaming(handler), limiter)) => {
+                let guard = self.in_flight.track(&req.context);
+                RpcStream::spawn(handler, req, guard, limiter)
+            }
+            _ => RpcStream::from_future(self.dispatch_admitted(req)),
+        }
+    }
+
+    fn cancel(&self, request_id: &str) -> bool {
+        self.in_flight.cancel(request_id)
+    }
+
+    fn cancel_all(&self) {
+        self.in_flight.cancel_all();
+    }
+}
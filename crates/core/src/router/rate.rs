@@ -0,0 +1,151 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::router::{RpcError, RpcRequest};
+
+/// Idle buckets are pruned once this many are tracked.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Token bucket: up to `burst` calls at once, refilled at `per_second`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self { per_second, burst }
+    }
+}
+
+/// Rate limits applied to every caller independently.
+///
+/// Callers are told apart by principal id when authenticated, otherwise by
+/// peer (IP address for HTTP, device address for BLE). `per_client` bounds
+/// all calls of a caller; `methods`, keyed by `service.method`, bound a
+/// caller's calls to one method. A call must fit in both.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimits {
+    pub per_client: Option<RateLimit>,
+    pub methods: BTreeMap<String, RateLimit>,
+}
+
+impl RateLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_per_client(mut self, limit: RateLimit) -> Self {
+        self.per_client = Some(limit);
+        self
+    }
+
+    pub fn with_method(mut self, name: impl Into<String>, limit: RateLimit) -> Self {
+        self.methods.insert(name.into(), limit);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.per_client.is_none() && self.methods.is_empty()
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(f64::from(limit.burst));
+        self.updated = now;
+    }
+
+    /// Time until one token is available.
+    fn wait(&self, limit: RateLimit) -> Duration {
+        Duration::try_from_secs_f64((1.0 - self.tokens) / limit.per_second).unwrap_or(Duration::MAX)
+    }
+}
+
+/// `(caller, method)`; the method is `None` for the per-client bucket.
+type BucketKey = (String, Option<String>);
+
+/// Runtime state enforcing [`RateLimits`].
+pub(crate) struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for `req` from each bucket that applies, or fail with
+    /// [`RpcError::RateLimited`] without taking any.
+    pub(crate) fn check(&self, req: &RpcRequest) -> Result<(), RpcError> {
+        let caller = req.context.caller_key();
+        let name = format!("{}.{}", req.service, req.method);
+        let mut applicable = Vec::with_capacity(2);
+        if let Some(limit) = self.limits.per_client {
+            applicable.push(((caller.clone(), None), limit));
+        }
+        if let Some(limit) = self.limits.methods.get(&name) {
+            applicable.push(((caller, Some(name)), *limit));
+        }
+        if applicable.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limiter lock");
+        if buckets.len() >= PRUNE_THRESHOLD {
+            self.prune(&mut buckets, now);
+        }
+        let mut retry_after = Duration::ZERO;
+        for (key, limit) in &applicable {
+            let bucket = buckets.entry(key.clone()).or_insert(Bucket {
+                tokens: f64::from(limit.burst),
+                updated: now,
+            });
+            bucket.refill(*limit, now);
+            if bucket.tokens < 1.0 {
+                retry_after = retry_after.max(bucket.wait(*limit));
+            }
+        }
+        if !retry_after.is_zero() {
+            return Err(RpcError::RateLimited { retry_after });
+        }
+        for (key, _) in &applicable {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget buckets that have refilled completely; they behave the same as
+    /// fresh ones.
+    fn prune(&self, buckets: &mut HashMap<BucketKey, Bucket>, now: Instant) {
+        buckets.retain(|(_, method), bucket| {
+            let limit = match method {
+                Some(name) => self.limits.methods.get(name).copied(),
+                None => self.limits.per_client,
+            };
+            let Some(limit) = limit else { return false };
+            bucket.refill(limit, now);
+            bucket.tokens < f64::from(limit.burst)
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use crate::router::{
+    inflight::InFlightGuard,
+    limit::{self, Limiter},
+    RpcError, RpcFuture, RpcRequest, RpcResponse,
+};
+
+/// Number of chunks buffered between a streaming handler and its consumer.
+const STREAM_BUFFER: usize = 16;
+
+pub type RpcStreamFuture = Pin<Box<dyn Future<Output = Result<(), RpcError>> + Send>>;
+pub type StreamingRpcHandler =
+    Arc<dyn Fn(RpcRequest, RpcStreamSender) -> RpcStreamFuture + Send + Sync>;
+
+/// Helper to wrap async closures into a [`StreamingRpcHandler`].
+///
+/// The closure sends chunks through the provided [`RpcStreamSender`] and its
+/// return value becomes the terminating status of the stream.
+pub fn rpc_stream_handler<F, Fut>(func: F) -> StreamingRpcHandler
+where
+    F: Fn(RpcRequest, RpcStreamSender) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), RpcError>> + Send + 'static,
+{
+    Arc::new(move |req, sender| Box::pin(func(req, sender)))
+}
+
+/// Handler-side half of a response stream.
+#[derive(Clone)]
+pub struct RpcStreamSender {
+    chunks: mpsc::Sender<RpcResponse>,
+}
+
+impl RpcStreamSender {
+    /// Send one response chunk, waiting while the consumer is behind.
+    ///
+    /// Fails once the consumer has gone away, which handlers should treat as
+    /// a signal to stop producing.
+    pub async fn send(&self, chunk: RpcResponse) -> Result<(), RpcError> {
+        self.chunks
+            .send(chunk)
+            .await
+            .map_err(|_| RpcError::Cancelled)
+    }
+
+    /// Whether the consumer has dropped the stream.
+    pub fn is_closed(&self) -> bool {
+        self.chunks.is_closed()
+    }
+}
+
+/// Consumer-side half of a response stream.
+///
+/// Dropping the stream aborts the handler, which is how transports cancel a
+/// stream when their client disconnects.
+pub struct RpcStream {
+    chunks: mpsc::Receiver<RpcResponse>,
+    status: oneshot::Receiver<Result<(), RpcError>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl RpcStream {
+    /// Run `handler` on the current runtime and return the stream of its chunks.
+    ///
+    /// The handler stops being polled once the call's cancellation token fires.
+    /// With a `limiter`, it holds a slot for the lifetime of the stream.
+    pub(crate) fn spawn(
+        handler: StreamingRpcHandler,
+        req: RpcRequest,
+        guard: InFlightGuard,
+        limiter: Option<Arc<Limiter>>,
+    ) -> Self {
+        let (chunk_tx, chunks) = mpsc::channel(STREAM_BUFFER);
+        let (status_tx, status) = oneshot::channel();
+        let sender = RpcStreamSender { chunks: chunk_tx };
+        let token = req.context.cancellation().clone();
+        let task = tokio::spawn(async move {
+            let call = async move {
+                let _permit = limit::acquire(limiter).await?;
+                handler(req, sender).await
+            };
+            let result = tokio::select! {
+                result = call => result,
+                _ = token.cancelled() => Err(RpcError::Cancelled),
+            };
+            guard.complete();
+            let _ = status_tx.send(result);
+        });
+
+        Self {
+            chunks,
+            status,
+            task: Some(task),
+        }
+    }
+
+    /// Run a unary call and stream its single response.
+    pub(crate) fn from_future(call: RpcFuture) -> Self {
+        let (chunk_tx, chunks) = mpsc::channel(1);
+        let (status_tx, status) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let result = match call.await {
+                Ok(response) => chunk_tx
+                    .send(response)
+                    .await
+                    .map_err(|_| RpcError::Cancelled),
+                Err(err) => Err(err),
+            };
+            drop(chunk_tx);
+            let _ = status_tx.send(result);
+        });
+
+        Self {
+            chunks,
+            status,
+            task: Some(task),
+        }
+    }
+
+    /// A stream that yields no chunks and terminates with `status`.
+    pub fn terminated(status: Result<(), RpcError>) -> Self {
+        let (_, chunks) = mpsc::channel(1);
+        let (status_tx, status_rx) = oneshot::channel();
+        let _ = status_tx.send(status);
+        Self {
+            chunks,
+            status: status_rx,
+            task: None,
+        }
+    }
+
+    /// Receive the next chunk, or `None` once the handler has finished.
+    pub async fn next(&mut self) -> Option<RpcResponse> {
+        self.chunks.recv().await
+    }
+
+    /// Wait for the terminating status after all chunks were consumed.
+    pub async fn finish(mut self) -> Result<(), RpcError> {
+        (&mut self.status).await.unwrap_or_else(|_| {
+            Err(RpcError::Internal(
+                "stream handler exited without a status".to_string(),
+            ))
+        })
+    }
+}
+
+impl Drop for RpcStream {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
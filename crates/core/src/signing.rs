@@ -0,0 +1,217 @@
+//! Signed requests with replay protection.
+//!
+//! A client holding the shared key signs each call over its service, method,
+//! timestamp, nonce and payload digest, and sends the three values as
+//! request metadata (HTTP headers of the same names). The router rejects
+//! calls whose signature does not verify, whose timestamp is too far from
+//! the device clock, or whose nonce was already seen within that window.
+//!
+//! A retry must be signed again with a fresh nonce; pair it with an
+//! idempotency key so the retried call is not executed twice.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::router::{RpcError, RpcRequest};
+
+/// Metadata key carrying the base64url HMAC-SHA256 signature.
+pub const SIGNATURE_METADATA: &str = "x-signature";
+/// Metadata key carrying the single-use nonce.
+pub const NONCE_METADATA: &str = "x-nonce";
+/// Metadata key carrying the signing time in Unix seconds.
+pub const TIMESTAMP_METADATA: &str = "x-timestamp";
+
+static NEXT_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// `[signing]` section of the config file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SigningConfig {
+    pub key: String,
+    #[serde(default = "default_max_skew_secs")]
+    pub max_skew_secs: u64,
+    /// Reject unsigned calls instead of letting them through unchecked.
+    #[serde(default)]
+    pub required: bool,
+}
+
+fn default_max_skew_secs() -> u64 {
+    30
+}
+
+/// Client side: produces the signature metadata for a call.
+#[derive(Clone)]
+pub struct RequestSigner {
+    key: Vec<u8>,
+}
+
+impl RequestSigner {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// Metadata entries signing a call made now with a fresh nonce.
+    pub fn sign(&self, service: &str, method: &str, payload: &[u8]) -> BTreeMap<String, String> {
+        let timestamp = unix_now();
+        let nonce = format!(
+            "{:x}-{:x}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos())
+                .unwrap_or(0),
+            NEXT_NONCE.fetch_add(1, Ordering::Relaxed)
+        );
+        self.sign_with(service, method, payload, timestamp, &nonce)
+    }
+
+    /// Metadata entries for an explicit timestamp and nonce.
+    pub fn sign_with(
+        &self,
+        service: &str,
+        method: &str,
+        payload: &[u8],
+        timestamp: u64,
+        nonce: &str,
+    ) -> BTreeMap<String, String> {
+        let signature = mac(&self.key, service, method, timestamp, nonce, payload).finalize();
+        BTreeMap::from([
+            (
+                SIGNATURE_METADATA.to_string(),
+                URL_SAFE_NO_PAD.encode(signature.into_bytes()),
+            ),
+            (NONCE_METADATA.to_string(), nonce.to_string()),
+            (TIMESTAMP_METADATA.to_string(), timestamp.to_string()),
+        ])
+    }
+}
+
+/// Device side: checks signatures and remembers nonces.
+pub struct RequestVerifier {
+    key: Vec<u8>,
+    max_skew: Duration,
+    required: bool,
+    /// Nonce to the Unix time after which it can be forgotten.
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl RequestVerifier {
+    /// Verify signed calls and let unsigned ones through; timestamps may be
+    /// 30 seconds off.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            max_skew: Duration::from_secs(default_max_skew_secs()),
+            required: false,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &SigningConfig) -> Self {
+        let verifier = Self::new(config.key.as_bytes())
+            .with_max_skew(Duration::from_secs(config.max_skew_secs));
+        if config.required {
+            verifier.require_signatures()
+        } else {
+            verifier
+        }
+    }
+
+    /// Accepted distance between the request timestamp and the local clock.
+    pub fn with_max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Reject calls that carry no signature.
+    pub fn require_signatures(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub(crate) fn verify(&self, req: &RpcRequest) -> Result<(), RpcError> {
+        let context = &req.context;
+        let Some(signature) = context.metadata(SIGNATURE_METADATA) else {
+            return match self.required {
+                true => Err(unauthenticated("request signature required")),
+                false => Ok(()),
+            };
+        };
+        let (Some(nonce), Some(timestamp)) = (
+            context.metadata(NONCE_METADATA),
+            context.metadata(TIMESTAMP_METADATA),
+        ) else {
+            return Err(unauthenticated("signed request without nonce or timestamp"));
+        };
+        let timestamp: u64 = timestamp
+            .parse()
+            .map_err(|_| unauthenticated("malformed request timestamp"))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| unauthenticated("invalid request signature"))?;
+        mac(
+            &self.key,
+            &req.service,
+            &req.method,
+            timestamp,
+            nonce,
+            &req.payload,
+        )
+        .verify_slice(&signature)
+        .map_err(|_| unauthenticated("invalid request signature"))?;
+
+        let now = unix_now();
+        let skew = self.max_skew.as_secs();
+        if timestamp.abs_diff(now) > skew {
+            return Err(unauthenticated(
+                "request timestamp outside the allowed window",
+            ));
+        }
+        let mut seen = self.seen.lock().expect("nonce cache lock");
+        // A nonce older than the window would fail the timestamp check anyway.
+        seen.retain(|_, forget_after| *forget_after >= now);
+        if seen.contains_key(nonce) {
+            return Err(unauthenticated("replayed request"));
+        }
+        seen.insert(nonce.to_string(), timestamp.saturating_add(skew));
+        Ok(())
+    }
+}
+
+fn mac(
+    key: &[u8],
+    service: &str,
+    method: &str,
+    timestamp: u64,
+    nonce: &str,
+    payload: &[u8],
+) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    let digest = Sha256::digest(payload);
+    let canonical = format!(
+        "{service}\n{method}\n{timestamp}\n{nonce}\n{}",
+        URL_SAFE_NO_PAD.encode(digest)
+    );
+    mac.update(canonical.as_bytes());
+    mac
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn unauthenticated(reason: &str) -> RpcError {
+    RpcError::Unauthenticated(reason.to_string())
+}
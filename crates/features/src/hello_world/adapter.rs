@@ -2,7 +2,9 @@ use std::sync::Arc;
 
 use service_core::{
     event::{EventBus, TransportEvent},
-    router::{rpc_handler, RouterError, RpcError, RpcRegistry, RpcRequest, RpcResponse},
+    router::{
+        rpc_handler, MethodDescriptor, RouterError, RpcError, RpcRegistry, RpcRequest, RpcResponse,
+    },
     types::Clock,
 };
 
@@ -33,5 +35,10 @@ pub fn register(
         }
     });
 
-    router.register(api::SERVICE, api::METHOD_GET, handler)
+    let descriptor = MethodDescriptor::new(api::SERVICE, api::METHOD_GET)
+        .feature(api::FEATURE)
+        .description("Returns the current time followed by a greeting")
+        .request_schema("empty")
+        .response_schema("UTF-8 string: \"<RFC3339 datetime> hello world\"");
+    router.register_described(descriptor, handler)
 }
@@ -1,3 +1,5 @@
+/// Feature name used when registering Hello World handlers.
+pub const FEATURE: &str = "hello_world";
 /// Service name for Hello World RPCs.
 pub const SERVICE: &str = "hello";
 /// Method name for retrieving a greeting.
@@ -26,9 +26,15 @@ impl HelloWorldFeature {
     }
 }
 
+impl Default for HelloWorldFeature {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Feature for HelloWorldFeature {
     fn name(&self) -> &'static str {
-        "hello_world"
+        api::FEATURE
     }
 
     fn init(&self, ctx: FeatureContext) -> FeatureFuture<'_> {
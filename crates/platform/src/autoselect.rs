@@ -0,0 +1,177 @@
+//! Transport auto-selection: run HTTP while Wi-Fi is usable and BLE
+//! otherwise, switching as the network changes.
+//!
+//! [`follow_wifi`] takes its inputs as parameters, so the service runs it
+//! with a [`WifiDetector`](crate::wifi::WifiDetector) and a netlink watcher
+//! while tests drive it with a [`MockWifiDetector`](crate::wifi::mock::MockWifiDetector).
+//! What it does is reported as [`Selection`] values for the caller to log.
+
+use std::{future::Future, pin::Pin};
+
+use service_core::{Result, RpcRegistry, TransportId, TransportSupervisor};
+use tokio::time::{Duration, Instant, MissedTickBehavior};
+
+use crate::{
+    network::NetworkWatcher,
+    wifi::{
+        detector::{ConnectivityProber, ProbeReport},
+        TransportDetector,
+    },
+};
+
+/// A change made by [`follow_wifi`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// `selected` was picked because of `reason` (`None` for the first
+    /// selection). `running` is the transport now serving: `selected` when
+    /// it is built in, otherwise another one that is, or `None` if the
+    /// supervisor has no transports at all.
+    Switched {
+        reason: Option<String>,
+        selected: TransportId,
+        running: Option<TransportId>,
+    },
+    /// The network watcher ended; only probes re-detect from now on.
+    WatcherStopped,
+}
+
+/// Transport for auto mode, keeping HTTP when the detector cannot tell.
+/// With a prober, HTTP is only picked while its debounced state is online.
+pub fn detected_transport(
+    detector: &dyn TransportDetector,
+    prober: Option<&ConnectivityProber>,
+) -> String {
+    let detected = detector.detect().unwrap_or_else(|| "http".to_string());
+    match prober {
+        Some(prober) if detected == "http" && !prober.is_online() => "ble".to_string(),
+        _ => detected,
+    }
+}
+
+/// Run the detected transport until `shutdown` completes, re-detecting on
+/// every change reported by `watcher`, and on every probe interval when a
+/// prober is given, so HTTP and BLE swap as soon as Wi-Fi comes or goes.
+///
+/// Only the interval feeds the prober's hysteresis; a burst of network
+/// events re-reads the Wi-Fi state against the last debounced result.
+/// Probes run alongside `shutdown`, so a slow one never delays it. Every
+/// switch is passed to `report`.
+pub async fn follow_wifi(
+    supervisor: &TransportSupervisor,
+    registry: &dyn RpcRegistry,
+    detector: &dyn TransportDetector,
+    mut watcher: Option<NetworkWatcher>,
+    mut prober: Option<ConnectivityProber>,
+    shutdown: impl Future<Output = ()>,
+    mut report: impl FnMut(Selection),
+) -> Result<()> {
+    tokio::pin!(shutdown);
+    if let Some(prober) = &mut prober {
+        tokio::select! {
+            _ = &mut shutdown => return Ok(()),
+            _ = prober.probe() => {}
+        }
+    }
+    let mut current = detected_transport(detector, prober.as_ref());
+    let running = switch_transport(supervisor, registry, &current).await?;
+    report(Selection::Switched {
+        reason: None,
+        selected: current.clone(),
+        running,
+    });
+
+    let mut probes = prober.as_ref().map(|prober| {
+        let period = Duration::from_secs(prober.config().interval_secs);
+        let mut probes = tokio::time::interval_at(Instant::now() + period, period);
+        probes.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        probes
+    });
+    let mut probing: Option<Pin<Box<dyn Future<Output = ProbeReport> + Send>>> = None;
+    loop {
+        let idle = probing.is_none();
+        let change = async {
+            match &mut watcher {
+                Some(watcher) => watcher.next().await,
+                None => std::future::pending().await,
+            }
+        };
+        let probe_due = async {
+            match &mut probes {
+                Some(probes) => probes.tick().await,
+                None => std::future::pending().await,
+            }
+        };
+        let probed = async {
+            match &mut probing {
+                Some(probe) => probe.await,
+                None => std::future::pending().await,
+            }
+        };
+        let reason = tokio::select! {
+            _ = &mut shutdown => return Ok(()),
+            change = change => match change {
+                Some(change) => change.to_string(),
+                None => {
+                    report(Selection::WatcherStopped);
+                    watcher = None;
+                    continue;
+                }
+            },
+            _ = probe_due, if idle => {
+                if let Some(prober) = prober.clone() {
+                    probing = Some(Box::pin(async move { prober.check().await }));
+                }
+                continue;
+            }
+            report = probed => {
+                probing = None;
+                if let Some(prober) = &mut prober {
+                    prober.record(report.passed());
+                }
+                "connectivity probe".to_string()
+            }
+        };
+        let selected = detected_transport(detector, prober.as_ref());
+        if selected != current {
+            let running = switch_transport(supervisor, registry, &selected).await?;
+            report(Selection::Switched {
+                reason: Some(reason),
+                selected: selected.clone(),
+                running,
+            });
+            current = selected;
+        }
+    }
+}
+
+/// Run `selected`, or another built-in transport when it was not built in,
+/// and stop every other transport. Calls still in flight on a stopped
+/// transport are cancelled. Returns the transport now running, `None` when
+/// the supervisor has none.
+pub async fn switch_transport(
+    supervisor: &TransportSupervisor,
+    registry: &dyn RpcRegistry,
+    selected: &str,
+) -> Result<Option<TransportId>> {
+    let transports = supervisor.transports();
+    let Some(target) = transports
+        .iter()
+        .find(|id| *id == selected)
+        .or_else(|| transports.first())
+        .cloned()
+    else {
+        return Ok(None);
+    };
+    let mut stopped = false;
+    for id in supervisor.running() {
+        if id != target {
+            supervisor.stop(&id).await?;
+            stopped = true;
+        }
+    }
+    if stopped {
+        registry.cancel_all();
+    }
+    supervisor.start(&target)?;
+    Ok(Some(target))
+}
@@ -1,3 +1,6 @@
-//! Platform integration stubs.
+//! Platform integration: Wi-Fi state, network change notifications and
+//! transport auto-selection.
 
+pub mod autoselect;
+pub mod network;
 pub mod wifi;
@@ -0,0 +1,55 @@
+use std::net::IpAddr;
+
+use tokio::sync::mpsc;
+
+use crate::network::NetworkChanged;
+
+/// Test handle feeding a [`NetworkWatcher::mock`](super::NetworkWatcher::mock).
+///
+/// Events are delivered as sent, duplicates included, so tests see the
+/// watcher's filtering. Dropping every handle ends the watcher's stream.
+#[derive(Clone)]
+pub struct MockNetworkEvents {
+    sender: mpsc::Sender<NetworkChanged>,
+}
+
+impl MockNetworkEvents {
+    pub(crate) fn new(sender: mpsc::Sender<NetworkChanged>) -> Self {
+        Self { sender }
+    }
+
+    /// Deliver a raw event; `false` once the watcher is gone.
+    pub async fn send(&self, change: NetworkChanged) -> bool {
+        self.sender.send(change).await.is_ok()
+    }
+
+    pub async fn link_up(&self, interface: &str) -> bool {
+        self.send(NetworkChanged::LinkUp {
+            interface: interface.to_string(),
+        })
+        .await
+    }
+
+    pub async fn link_down(&self, interface: &str) -> bool {
+        self.send(NetworkChanged::LinkDown {
+            interface: interface.to_string(),
+        })
+        .await
+    }
+
+    pub async fn address_added(&self, interface: &str, address: IpAddr) -> bool {
+        self.send(NetworkChanged::AddressAdded {
+            interface: interface.to_string(),
+            address,
+        })
+        .await
+    }
+
+    pub async fn address_removed(&self, interface: &str, address: IpAddr) -> bool {
+        self.send(NetworkChanged::AddressRemoved {
+            interface: interface.to_string(),
+            address,
+        })
+        .await
+    }
+}
@@ -0,0 +1,147 @@
+//! Notifications about network interfaces coming and going.
+//!
+//! A [`NetworkWatcher`] turns link and address events into a stream of
+//! [`NetworkChanged`] values, so transport selection can react when Wi-Fi
+//! drops instead of polling. On Linux the events come from rtnetlink; other
+//! platforms have no kernel source. Tests push events through a
+//! [`MockNetworkEvents`] handle.
+
+use std::{collections::HashMap, fmt::Display, net::IpAddr};
+
+use tokio::{sync::mpsc, task::JoinHandle};
+
+pub use crate::network::mock::MockNetworkEvents;
+
+pub mod mock;
+#[cfg(target_os = "linux")]
+pub mod netlink;
+
+/// Notifications buffered before the source waits for the watcher.
+const EVENT_BUFFER: usize = 64;
+
+/// A change to an interface's link state or addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkChanged {
+    /// The interface is up and has a carrier.
+    LinkUp {
+        interface: String,
+    },
+    /// The interface lost its carrier, was set down or removed.
+    LinkDown {
+        interface: String,
+    },
+    AddressAdded {
+        interface: String,
+        address: IpAddr,
+    },
+    AddressRemoved {
+        interface: String,
+        address: IpAddr,
+    },
+}
+
+impl NetworkChanged {
+    pub fn interface(&self) -> &str {
+        match self {
+            NetworkChanged::LinkUp { interface }
+            | NetworkChanged::LinkDown { interface }
+            | NetworkChanged::AddressAdded { interface, .. }
+            | NetworkChanged::AddressRemoved { interface, .. } => interface,
+        }
+    }
+}
+
+impl Display for NetworkChanged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkChanged::LinkUp { interface } => write!(f, "{interface} up"),
+            NetworkChanged::LinkDown { interface } => write!(f, "{interface} down"),
+            NetworkChanged::AddressAdded { interface, address } => {
+                write!(f, "{interface} gained {address}")
+            }
+            NetworkChanged::AddressRemoved { interface, address } => {
+                write!(f, "{interface} lost {address}")
+            }
+        }
+    }
+}
+
+/// Stream of [`NetworkChanged`] notifications.
+///
+/// The kernel reports a link again whenever any of its attributes change;
+/// the watcher only passes on transitions, so a `LinkUp` is never followed
+/// by another `LinkUp` for the same interface.
+pub struct NetworkWatcher {
+    events: mpsc::Receiver<NetworkChanged>,
+    links: HashMap<String, bool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl NetworkWatcher {
+    /// Watch rtnetlink's link and IPv4/IPv6 address groups. Must be called
+    /// within a Tokio runtime.
+    #[cfg(target_os = "linux")]
+    pub fn netlink() -> std::io::Result<Self> {
+        let mut socket = netlink::NetlinkSocket::open()?;
+        let (sender, events) = mpsc::channel(EVENT_BUFFER);
+        let reader = tokio::spawn(async move {
+            while let Ok(changes) = socket.recv().await {
+                for change in changes {
+                    if sender.send(change).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            events,
+            links: HashMap::new(),
+            reader: Some(reader),
+        })
+    }
+
+    /// Network change notifications are only implemented on Linux; elsewhere
+    /// this fails with [`ErrorKind::Unsupported`](std::io::ErrorKind::Unsupported).
+    #[cfg(not(target_os = "linux"))]
+    pub fn netlink() -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "network change notifications need rtnetlink (Linux only)",
+        ))
+    }
+
+    /// A watcher fed by the returned handle instead of the kernel.
+    pub fn mock() -> (Self, MockNetworkEvents) {
+        let (sender, events) = mpsc::channel(EVENT_BUFFER);
+        let watcher = Self {
+            events,
+            links: HashMap::new(),
+            reader: None,
+        };
+        (watcher, MockNetworkEvents::new(sender))
+    }
+
+    /// Wait for the next change; `None` once the source has gone away.
+    pub async fn next(&mut self) -> Option<NetworkChanged> {
+        loop {
+            let change = self.events.recv().await?;
+            let up = match &change {
+                NetworkChanged::LinkUp { .. } => true,
+                NetworkChanged::LinkDown { .. } => false,
+                _ => return Some(change),
+            };
+            let previous = self.links.insert(change.interface().to_string(), up);
+            if previous != Some(up) {
+                return Some(change);
+            }
+        }
+    }
+}
+
+impl Drop for NetworkWatcher {
+    fn drop(&mut self) {
+        if let Some(reader) = &self.reader {
+            reader.abort();
+        }
+    }
+}
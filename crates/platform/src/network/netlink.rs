@@ -0,0 +1,239 @@
+//! rtnetlink link and address notifications.
+//!
+//! The socket joins the `RTMGRP_LINK`, `RTMGRP_IPV4_IFADDR` and
+//! `RTMGRP_IPV6_IFADDR` multicast groups and decodes `RTM_NEWLINK`,
+//! `RTM_DELLINK`, `RTM_NEWADDR` and `RTM_DELADDR` messages; everything else
+//! is ignored.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd},
+};
+
+use tokio::io::unix::AsyncFd;
+
+use crate::network::NetworkChanged;
+
+const NLMSG_HEADER_LEN: usize = 16;
+/// `struct ifinfomsg`.
+const IFINFO_LEN: usize = 16;
+/// `struct ifaddrmsg`.
+const IFADDR_LEN: usize = 8;
+const ATTR_HEADER_LEN: usize = 4;
+/// Strips the nested/byte-order flag bits from an attribute type.
+const ATTR_TYPE_MASK: u16 = 0x3fff;
+const RECEIVE_BUFFER_LEN: usize = 32 * 1024;
+
+/// Non-blocking `NETLINK_ROUTE` socket subscribed to link and address
+/// changes.
+pub(crate) struct NetlinkSocket {
+    fd: AsyncFd<OwnedFd>,
+    buffer: Vec<u8>,
+}
+
+impl NetlinkSocket {
+    pub(crate) fn open() -> io::Result<Self> {
+        // SAFETY: plain socket(2) call; the result is checked below.
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `raw` is a freshly created descriptor nothing else owns.
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        // SAFETY: `sockaddr_nl` is plain old data; all-zero is a valid value.
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups =
+            (libc::RTMGRP_LINK | libc::RTMGRP_IPV4_IFADDR | libc::RTMGRP_IPV6_IFADDR) as u32;
+        // SAFETY: `address` outlives the call and the length matches its type.
+        let bound = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                (&address as *const libc::sockaddr_nl).cast(),
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if bound < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            fd: AsyncFd::new(fd)?,
+            buffer: vec![0; RECEIVE_BUFFER_LEN],
+        })
+    }
+
+    /// Wait for the next datagram and decode it. A datagram may carry no
+    /// change of interest, giving an empty batch.
+    pub(crate) async fn recv(&mut self) -> io::Result<Vec<NetworkChanged>> {
+        let Self { fd, buffer } = self;
+        loop {
+            let mut guard = fd.readable().await?;
+            let received = guard.try_io(|fd| {
+                // SAFETY: the buffer is valid for writes of its whole length.
+                let len = unsafe {
+                    libc::recv(fd.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0)
+                };
+                if len < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(len as usize)
+                }
+            });
+            match received {
+                Ok(Ok(len)) => return Ok(decode(&buffer[..len])),
+                // The kernel dropped notifications because we fell behind;
+                // later ones still arrive.
+                Ok(Err(err)) if err.raw_os_error() == Some(libc::ENOBUFS) => continue,
+                Ok(Err(err)) => return Err(err),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+/// Decode one datagram of rtnetlink messages into the changes it reports.
+///
+/// Links count as up while `IFF_RUNNING` is set. Interfaces are named by the
+/// message's name attribute, else looked up by index.
+pub fn decode(datagram: &[u8]) -> Vec<NetworkChanged> {
+    let mut changes = Vec::new();
+    let mut rest = datagram;
+    while rest.len() >= NLMSG_HEADER_LEN {
+        let len = u32_at(rest, 0) as usize;
+        if len < NLMSG_HEADER_LEN || len > rest.len() {
+            break;
+        }
+        let body = &rest[NLMSG_HEADER_LEN..len];
+        let change = match u16_at(rest, 4) {
+            kind @ (libc::RTM_NEWLINK | libc::RTM_DELLINK) => decode_link(kind, body),
+            kind @ (libc::RTM_NEWADDR | libc::RTM_DELADDR) => decode_address(kind, body),
+            _ => None,
+        };
+        changes.extend(change);
+        rest = &rest[align(len).min(rest.len())..];
+    }
+    changes
+}
+
+fn decode_link(kind: u16, body: &[u8]) -> Option<NetworkChanged> {
+    if body.len() < IFINFO_LEN {
+        return None;
+    }
+    let index = u32_at(body, 4);
+    let flags = u32_at(body, 8);
+    let interface = attributes(&body[IFINFO_LEN..])
+        .find(|(kind, _)| *kind == libc::IFLA_IFNAME)
+        .map(|(_, value)| c_string(value))
+        .unwrap_or_else(|| interface_name(index));
+    let running = flags & libc::IFF_RUNNING as u32 != 0;
+    Some(if kind == libc::RTM_NEWLINK && running {
+        NetworkChanged::LinkUp { interface }
+    } else {
+        NetworkChanged::LinkDown { interface }
+    })
+}
+
+fn decode_address(kind: u16, body: &[u8]) -> Option<NetworkChanged> {
+    if body.len() < IFADDR_LEN {
+        return None;
+    }
+    let family = i32::from(body[0]);
+    let index = u32_at(body, 4);
+    let (mut local, mut address, mut label) = (None, None, None);
+    for (kind, value) in attributes(&body[IFADDR_LEN..]) {
+        match kind {
+            libc::IFA_LOCAL => local = ip_address(family, value),
+            libc::IFA_ADDRESS => address = ip_address(family, value),
+            libc::IFA_LABEL => label = Some(c_string(value)),
+            _ => {}
+        }
+    }
+    // On point-to-point links IFA_ADDRESS is the peer; IFA_LOCAL is ours.
+    let address = local.or(address)?;
+    let interface = label.unwrap_or_else(|| interface_name(index));
+    Some(if kind == libc::RTM_NEWADDR {
+        NetworkChanged::AddressAdded { interface, address }
+    } else {
+        NetworkChanged::AddressRemoved { interface, address }
+    })
+}
+
+/// Iterate `(type, value)` over a run of route attributes.
+fn attributes(mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        if data.len() < ATTR_HEADER_LEN {
+            return None;
+        }
+        let len = usize::from(u16_at(data, 0));
+        if len < ATTR_HEADER_LEN || len > data.len() {
+            return None;
+        }
+        let attribute = (
+            u16_at(data, 2) & ATTR_TYPE_MASK,
+            &data[ATTR_HEADER_LEN..len],
+        );
+        data = &data[align(len).min(data.len())..];
+        Some(attribute)
+    })
+}
+
+fn ip_address(family: i32, value: &[u8]) -> Option<IpAddr> {
+    match family {
+        libc::AF_INET => <[u8; 4]>::try_from(value)
+            .ok()
+            .map(|octets| IpAddr::V4(Ipv4Addr::from(octets))),
+        libc::AF_INET6 => <[u8; 16]>::try_from(value)
+            .ok()
+            .map(|octets| IpAddr::V6(Ipv6Addr::from(octets))),
+        _ => None,
+    }
+}
+
+/// Name of the interface with `index`, or `if{index}` once it is gone.
+fn interface_name(index: u32) -> String {
+    let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+    // SAFETY: `name` holds IF_NAMESIZE bytes, as if_indextoname requires.
+    let found = unsafe { libc::if_indextoname(index, name.as_mut_ptr()) };
+    if found.is_null() {
+        return format!("if{index}");
+    }
+    let bytes: Vec<u8> = name
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| byte as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn c_string(value: &[u8]) -> String {
+    let end = value
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(value.len());
+    String::from_utf8_lossy(&value[..end]).into_owned()
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
@@ -1,4 +1,180 @@
-/// Shared Wi-Fi detection helpers.
+//! Connectivity checks beyond the Wi-Fi link state.
+//!
+//! Being associated to an access point does not mean clients can reach us
+//! over HTTP. A [`ConnectivityProber`] additionally checks for a default
+//! route (`/proc/net/route` and `/proc/net/ipv6_route`), DNS resolution of
+//! the configured host and, optionally, a TCP connection to it.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use service_core::ProbeConfig;
+use tokio::net::TcpStream;
+
+use crate::wifi::linux::LinuxWifi;
+
+/// `RTF_UP` in the route flags.
+const ROUTE_UP: u32 = 0x1;
+/// `RTF_REJECT`: the kernel's unreachable default route on `lo`.
+const ROUTE_REJECT: u32 = 0x200;
+
+/// Whether a wireless interface of this system is associated to a network.
 pub fn has_connectivity() -> bool {
-    false
+    LinuxWifi::new()
+        .connected_interface()
+        .is_ok_and(|interface| interface.is_some())
+}
+
+/// Debounced result of the probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Online,
+    Offline,
+}
+
+/// Findings of a single probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeReport {
+    pub default_route: bool,
+    pub dns: bool,
+    /// `None` when no port is configured.
+    pub tcp: Option<bool>,
+}
+
+impl ProbeReport {
+    pub fn passed(&self) -> bool {
+        self.default_route && self.dns && self.tcp != Some(false)
+    }
+}
+
+/// Probes reachability and smooths the results with hysteresis.
+///
+/// The first probe sets the state directly; after that it only changes
+/// once `online_after` probes in a row pass or `offline_after` in a row
+/// fail.
+#[derive(Debug, Clone)]
+pub struct ConnectivityProber {
+    config: ProbeConfig,
+    root: PathBuf,
+    state: Option<Connectivity>,
+    /// Consecutive results disagreeing with `state`.
+    streak: u32,
+}
+
+impl ConnectivityProber {
+    pub fn new(config: ProbeConfig) -> Self {
+        Self {
+            config,
+            root: PathBuf::from("/"),
+            state: None,
+            streak: 0,
+        }
+    }
+
+    /// Read `proc/net/route` and `proc/net/ipv6_route` below `root`.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    pub fn config(&self) -> &ProbeConfig {
+        &self.config
+    }
+
+    /// `None` until the first probe.
+    pub fn state(&self) -> Option<Connectivity> {
+        self.state
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.state == Some(Connectivity::Online)
+    }
+
+    /// Run the checks once and feed the result into the state.
+    pub async fn probe(&mut self) -> ProbeReport {
+        let report = self.check().await;
+        self.record(report.passed());
+        report
+    }
+
+    /// Run the checks once without touching the state. DNS and TCP are
+    /// skipped when there is no default route, since they cannot succeed.
+    pub async fn check(&self) -> ProbeReport {
+        let mut report = ProbeReport {
+            default_route: self.has_default_route(),
+            dns: false,
+            tcp: self.config.port.map(|_| false),
+        };
+        if !report.default_route {
+            return report;
+        }
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let host = self.config.host.as_str();
+        report.dns = tokio::time::timeout(timeout, tokio::net::lookup_host((host, 0)))
+            .await
+            .is_ok_and(|addrs| addrs.is_ok_and(|mut addrs| addrs.next().is_some()));
+        if let (Some(port), true) = (self.config.port, report.dns) {
+            let connected = tokio::time::timeout(timeout, TcpStream::connect((host, port))).await;
+            report.tcp = Some(connected.is_ok_and(|stream| stream.is_ok()));
+        }
+        report
+    }
+
+    /// Apply one probe result and return the resulting state.
+    pub fn record(&mut self, passed: bool) -> Connectivity {
+        let observed = if passed {
+            Connectivity::Online
+        } else {
+            Connectivity::Offline
+        };
+        let Some(state) = self.state else {
+            self.state = Some(observed);
+            return observed;
+        };
+        if observed == state {
+            self.streak = 0;
+            return state;
+        }
+        self.streak += 1;
+        let needed = match observed {
+            Connectivity::Online => self.config.online_after,
+            Connectivity::Offline => self.config.offline_after,
+        };
+        if self.streak < needed {
+            return state;
+        }
+        self.state = Some(observed);
+        self.streak = 0;
+        observed
+    }
+
+    /// Whether an IPv4 or IPv6 default route is up. Unreadable tables
+    /// count as none.
+    pub fn has_default_route(&self) -> bool {
+        let ipv4 = fs::read_to_string(self.root.join("proc/net/route")).is_ok_and(|table| {
+            // Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+            table.lines().skip(1).any(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                fields.len() > 7
+                    && fields[1] == "00000000"
+                    && fields[7] == "00000000"
+                    && is_usable(fields[3])
+            })
+        });
+        let ipv6 = fs::read_to_string(self.root.join("proc/net/ipv6_route")).is_ok_and(|table| {
+            // Destination PrefixLen Source PrefixLen NextHop Metric RefCnt Use Flags Iface
+            table.lines().any(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                fields.len() > 9
+                    && fields[0].bytes().all(|digit| digit == b'0')
+                    && fields[1] == "00"
+                    && is_usable(fields[8])
+            })
+        });
+        ipv4 || ipv6
+    }
+}
+
+fn is_usable(flags: &str) -> bool {
+    u32::from_str_radix(flags, 16)
+        .is_ok_and(|flags| flags & ROUTE_UP != 0 && flags & ROUTE_REJECT == 0)
 }
@@ -1,4 +1,245 @@
-/// Linux-specific Wi-Fi detection placeholder.
-pub fn current_ssid() -> Option<String> {
-    None
+//! Linux Wi-Fi detection from sysfs and procfs.
+//!
+//! Wireless interfaces are the entries of `/sys/class/net` that have a
+//! `wireless` directory; their `operstate` and `carrier` files give the link
+//! state and `/proc/net/wireless` the link quality. All paths are resolved
+//! under an injectable root so tests can point at fixture directories.
+//!
+//! What network an interface is on is not in sysfs; [`NetworkInfo`] asks
+//! NetworkManager and falls back to wpa_supplicant.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+#[cfg(target_os = "linux")]
+use crate::wifi::{networkmanager::NetworkManager, wpa_supplicant::WpaSupplicant};
+
+/// RFC 2863 operational state, as reported in `/sys/class/net/*/operstate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperState {
+    Up,
+    Down,
+    /// Associated but not yet usable, e.g. during WPA authentication.
+    Dormant,
+    LowerLayerDown,
+    NotPresent,
+    Testing,
+    Unknown,
+}
+
+impl OperState {
+    fn parse(text: &str) -> Self {
+        match text.trim() {
+            "up" => OperState::Up,
+            "down" => OperState::Down,
+            "dormant" => OperState::Dormant,
+            "lowerlayerdown" => OperState::LowerLayerDown,
+            "notpresent" => OperState::NotPresent,
+            "testing" => OperState::Testing,
+            _ => OperState::Unknown,
+        }
+    }
+}
+
+/// One row of `/proc/net/wireless`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkQuality {
+    /// Driver-specific link quality, typically out of 70.
+    pub link: f32,
+    /// Signal level in dBm.
+    pub level_dbm: f32,
+    /// Noise level in dBm; many drivers report `-256` for unknown.
+    pub noise_dbm: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WirelessInterface {
+    pub name: String,
+    pub operstate: OperState,
+    pub carrier: bool,
+    /// `None` when the interface has no row in `/proc/net/wireless`, which
+    /// is usual while it is not associated.
+    pub quality: Option<LinkQuality>,
+}
+
+impl WirelessInterface {
+    /// Whether the interface is up with a carrier, i.e. associated to a
+    /// network.
+    pub fn is_connected(&self) -> bool {
+        self.operstate == OperState::Up && self.carrier
+    }
+}
+
+/// Reader for the wireless interfaces of a Linux system.
+#[derive(Debug, Clone)]
+pub struct LinuxWifi {
+    root: PathBuf,
+}
+
+impl LinuxWifi {
+    /// Read the running system's `/sys` and `/proc`.
+    pub fn new() -> Self {
+        Self::with_root("/")
+    }
+
+    /// Read `sys/class/net` and `proc/net/wireless` below `root` instead.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Every wireless interface, sorted by name. A system without
+    /// `/sys/class/net` has none.
+    pub fn interfaces(&self) -> io::Result<Vec<WirelessInterface>> {
+        let net = self.root.join("sys/class/net");
+        let entries = match fs::read_dir(&net) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut qualities = self.link_qualities()?;
+        let mut interfaces = Vec::new();
+        for entry in entries {
+            let dir = entry?.path();
+            if !dir.join("wireless").is_dir() {
+                continue;
+            }
+            let Some(name) = dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let operstate = read_trimmed(&dir.join("operstate"))
+                .map(|state| OperState::parse(&state))
+                .unwrap_or(OperState::Unknown);
+            // Reading `carrier` fails with EINVAL while the interface is down.
+            let carrier = read_trimmed(&dir.join("carrier")).is_ok_and(|carrier| carrier == "1");
+            interfaces.push(WirelessInterface {
+                name: name.to_string(),
+                operstate,
+                carrier,
+                quality: qualities.remove(name),
+            });
+        }
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(interfaces)
+    }
+
+    /// The first connected wireless interface, if any.
+    pub fn connected_interface(&self) -> io::Result<Option<WirelessInterface>> {
+        Ok(self
+            .interfaces()?
+            .into_iter()
+            .find(WirelessInterface::is_connected))
+    }
+
+    /// Parse `/proc/net/wireless`; a missing file means no rows.
+    fn link_qualities(&self) -> io::Result<BTreeMap<String, LinkQuality>> {
+        let text = match fs::read_to_string(self.root.join("proc/net/wireless")) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(err) => return Err(err),
+        };
+        Ok(parse_proc_net_wireless(&text))
+    }
+}
+
+impl Default for LinuxWifi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rows look like `wlan0: 0000   70.  -40.  -256  0 0 0 0 0  0` after two
+/// header lines; malformed rows are skipped.
+fn parse_proc_net_wireless(text: &str) -> BTreeMap<String, LinkQuality> {
+    let number = |field: &str| field.trim_end_matches('.').parse::<f32>().ok();
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, fields) = line.split_once(':')?;
+            let mut fields = fields.split_whitespace().skip(1);
+            let quality = LinkQuality {
+                link: number(fields.next()?)?,
+                level_dbm: number(fields.next()?)?,
+                noise_dbm: number(fields.next()?)?,
+            };
+            Some((name.trim().to_string(), quality))
+        })
+        .collect()
+}
+
+fn read_trimmed(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path).map(|text| text.trim().to_string())
+}
+
+/// Security of the associated network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiSecurity {
+    Open,
+    Wep,
+    /// WPA or WPA2 with a pre-shared key.
+    WpaPersonal,
+    /// WPA3 SAE, including WPA2/WPA3 transition networks.
+    Wpa3Personal,
+    /// WPA or WPA2 with 802.1X authentication.
+    WpaEnterprise,
+    Unknown,
+}
+
+/// The network a wireless interface is associated to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub interface: String,
+    pub ssid: String,
+    /// MAC address of the access point, e.g. `aa:bb:cc:dd:ee:ff`.
+    pub bssid: Option<String>,
+    /// Signal strength from 0 to 100.
+    pub strength: Option<u8>,
+    /// Signal level in dBm; NetworkManager only reports [`Self::strength`].
+    pub signal_dbm: Option<i32>,
+    pub security: WifiSecurity,
+}
+
+/// Reads the current network from NetworkManager, falling back to
+/// wpa_supplicant when NetworkManager is not running or reports no network.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone)]
+pub struct NetworkInfo {
+    networkmanager: Option<NetworkManager>,
+    wpa_supplicant: WpaSupplicant,
+}
+
+#[cfg(target_os = "linux")]
+impl NetworkInfo {
+    /// NetworkManager on the system bus, if reachable, and wpa_supplicant's
+    /// default control directory.
+    pub async fn system() -> Self {
+        Self::new(NetworkManager::system().await.ok(), WpaSupplicant::new())
+    }
+
+    pub fn new(networkmanager: Option<NetworkManager>, wpa_supplicant: WpaSupplicant) -> Self {
+        Self {
+            networkmanager,
+            wpa_supplicant,
+        }
+    }
+
+    /// The associated network, or `None` when neither source knows one.
+    pub async fn current_network(&self) -> Option<WifiNetwork> {
+        if let Some(networkmanager) = &self.networkmanager {
+            if let Ok(Some(network)) = networkmanager.current_network().await {
+                return Some(network);
+            }
+        }
+        self.wpa_supplicant.current_network().await.ok().flatten()
+    }
+}
+
+/// SSID of the network this system is associated to.
+#[cfg(target_os = "linux")]
+pub async fn current_ssid() -> Option<String> {
+    let network = NetworkInfo::system().await.current_network().await?;
+    Some(network.ssid)
 }
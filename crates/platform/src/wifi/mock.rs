@@ -1,4 +1,221 @@
+//! Controllable Wi-Fi state for tests and local development.
+//!
+//! A [`MockWifiDetector`] answers [`detect`](MockWifiDetector::detect) like
+//! [`WifiDetector`](crate::wifi::WifiDetector), from state that tests change
+//! directly or through a [`WifiTimeline`]. Timelines run on Tokio's clock,
+//! so under `tokio::time::pause` every step lands at an exact instant.
+
+use std::time::Duration;
+
+use service_core::TransportId;
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time::{self, Instant},
+};
+
+use crate::{
+    network::NetworkWatcher,
+    wifi::{
+        linux::{WifiNetwork, WifiSecurity},
+        TransportDetector,
+    },
+};
+
+/// Interface name the mock reports networks on.
+const MOCK_INTERFACE: &str = "wlan0";
+
 /// Mock Wi-Fi detection for local development.
 pub fn mock_ssid() -> Option<String> {
     Some("development-network".to_string())
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockWifiState {
+    /// No wireless interface at all, as on a board without Wi-Fi.
+    NoInterface,
+    Disconnected,
+    Connected {
+        ssid: String,
+        signal_dbm: i32,
+    },
+}
+
+/// A change applied to a [`MockWifiDetector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WifiEvent {
+    Connect {
+        ssid: String,
+        signal_dbm: i32,
+    },
+    Disconnect,
+    /// New signal level for the current network; ignored while
+    /// disconnected.
+    Signal {
+        dbm: i32,
+    },
+    RemoveInterface,
+}
+
+/// Events to replay at offsets from the moment playback starts.
+#[derive(Debug, Clone, Default)]
+pub struct WifiTimeline {
+    steps: Vec<(Duration, WifiEvent)>,
+}
+
+impl WifiTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `event` at `offset`; events sharing an offset keep their order.
+    pub fn at(mut self, offset: Duration, event: WifiEvent) -> Self {
+        self.steps.push((offset, event));
+        self
+    }
+
+    pub fn steps(&self) -> &[(Duration, WifiEvent)] {
+        &self.steps
+    }
+}
+
+/// Wi-Fi detector whose state is set by tests.
+///
+/// Clones share the state. Every applied event notifies the receivers from
+/// [`subscribe`](Self::subscribe), even when it leaves the state unchanged.
+#[derive(Debug, Clone)]
+pub struct MockWifiDetector {
+    state: watch::Sender<MockWifiState>,
+}
+
+impl MockWifiDetector {
+    /// Start with a wireless interface that is not connected.
+    pub fn new() -> Self {
+        Self::with_state(MockWifiState::Disconnected)
+    }
+
+    pub fn with_state(state: MockWifiState) -> Self {
+        Self {
+            state: watch::Sender::new(state),
+        }
+    }
+
+    pub fn state(&self) -> MockWifiState {
+        self.state.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<MockWifiState> {
+        self.state.subscribe()
+    }
+
+    pub fn apply(&self, event: WifiEvent) {
+        self.state.send_modify(|state| match event {
+            WifiEvent::Connect { ssid, signal_dbm } => {
+                *state = MockWifiState::Connected { ssid, signal_dbm };
+            }
+            WifiEvent::Disconnect => *state = MockWifiState::Disconnected,
+            WifiEvent::Signal { dbm } => {
+                if let MockWifiState::Connected { signal_dbm, .. } = state {
+                    *signal_dbm = dbm;
+                }
+            }
+            WifiEvent::RemoveInterface => *state = MockWifiState::NoInterface,
+        });
+    }
+
+    pub fn connect(&self, ssid: &str, signal_dbm: i32) {
+        self.apply(WifiEvent::Connect {
+            ssid: ssid.to_string(),
+            signal_dbm,
+        });
+    }
+
+    pub fn disconnect(&self) {
+        self.apply(WifiEvent::Disconnect);
+    }
+
+    pub fn set_signal(&self, dbm: i32) {
+        self.apply(WifiEvent::Signal { dbm });
+    }
+
+    pub fn remove_interface(&self) {
+        self.apply(WifiEvent::RemoveInterface);
+    }
+
+    /// Same contract as [`WifiDetector::detect`](crate::wifi::WifiDetector::detect).
+    pub fn detect(&self) -> Option<TransportId> {
+        match *self.state.borrow() {
+            MockWifiState::NoInterface => None,
+            MockWifiState::Disconnected => Some("ble".to_string()),
+            MockWifiState::Connected { .. } => Some("http".to_string()),
+        }
+    }
+
+    /// The network while connected, reported on `wlan0` as WPA2 personal.
+    pub fn current_network(&self) -> Option<WifiNetwork> {
+        let MockWifiState::Connected { ssid, signal_dbm } = &*self.state.borrow() else {
+            return None;
+        };
+        Some(WifiNetwork {
+            interface: MOCK_INTERFACE.to_string(),
+            ssid: ssid.clone(),
+            bssid: None,
+            strength: None,
+            signal_dbm: Some(*signal_dbm),
+            security: WifiSecurity::WpaPersonal,
+        })
+    }
+
+    /// A watcher reporting `wlan0` up while connected and down otherwise,
+    /// as the kernel would for the real interface. Must be called within a
+    /// Tokio runtime; the watcher ends when every clone of the detector is
+    /// dropped.
+    pub fn watcher(&self) -> NetworkWatcher {
+        let (watcher, events) = NetworkWatcher::mock();
+        let mut changes = self.subscribe();
+        tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                let connected = matches!(
+                    *changes.borrow_and_update(),
+                    MockWifiState::Connected { .. }
+                );
+                let delivered = if connected {
+                    events.link_up(MOCK_INTERFACE).await
+                } else {
+                    events.link_down(MOCK_INTERFACE).await
+                };
+                if !delivered {
+                    return;
+                }
+            }
+        });
+        watcher
+    }
+
+    /// Replay `timeline` from now on a spawned task. Abort the handle to
+    /// stop early.
+    pub fn play(&self, timeline: WifiTimeline) -> JoinHandle<()> {
+        let detector = self.clone();
+        let start = Instant::now();
+        let mut steps = timeline.steps;
+        steps.sort_by_key(|(offset, _)| *offset);
+        tokio::spawn(async move {
+            for (offset, event) in steps {
+                time::sleep_until(start + offset).await;
+                detector.apply(event);
+            }
+        })
+    }
+}
+
+impl TransportDetector for MockWifiDetector {
+    fn detect(&self) -> Option<TransportId> {
+        MockWifiDetector::detect(self)
+    }
+}
+
+impl Default for MockWifiDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
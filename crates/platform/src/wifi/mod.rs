@@ -1,17 +1,64 @@
+use std::path::PathBuf;
+
 use service_core::TransportId;
 
-pub struct WifiDetector;
+use crate::wifi::linux::LinuxWifi;
+
+/// Source of the transport auto-select mode should run, implemented by
+/// [`WifiDetector`] and [`MockWifiDetector`](mock::MockWifiDetector).
+pub trait TransportDetector: Send + Sync {
+    /// Same contract as [`WifiDetector::detect`].
+    fn detect(&self) -> Option<TransportId>;
+}
+
+/// Picks the transport for auto-select mode from the Wi-Fi link state.
+pub struct WifiDetector {
+    linux: LinuxWifi,
+}
 
 impl WifiDetector {
     pub fn new() -> Self {
-        Self
+        Self {
+            linux: LinuxWifi::new(),
+        }
+    }
+
+    /// Read sysfs and procfs below `root`; see [`LinuxWifi::with_root`].
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            linux: LinuxWifi::with_root(root),
+        }
     }
 
+    /// `"http"` while a wireless interface is connected, `"ble"` when there
+    /// are wireless interfaces but none is, and `None` when the system has
+    /// no readable wireless interface to judge by.
     pub fn detect(&self) -> Option<TransportId> {
-        None
+        let interfaces = self.linux.interfaces().ok()?;
+        if interfaces.is_empty() {
+            return None;
+        }
+        let connected = interfaces.iter().any(|interface| interface.is_connected());
+        Some(if connected { "http" } else { "ble" }.to_string())
+    }
+}
+
+impl TransportDetector for WifiDetector {
+    fn detect(&self) -> Option<TransportId> {
+        WifiDetector::detect(self)
+    }
+}
+
+impl Default for WifiDetector {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 pub mod detector;
 pub mod linux;
 pub mod mock;
+#[cfg(target_os = "linux")]
+pub mod networkmanager;
+#[cfg(target_os = "linux")]
+pub mod wpa_supplicant;
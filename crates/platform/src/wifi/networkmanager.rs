@@ -0,0 +1,117 @@
+//! Wi-Fi details from NetworkManager's D-Bus API.
+//!
+//! The manager's `Devices` are walked for Wi-Fi devices; the first whose
+//! `ActiveAccessPoint` is set describes the current network.
+
+use zbus::{proxy::CacheProperties, zvariant::OwnedObjectPath, Connection, Proxy};
+
+use crate::wifi::linux::{WifiNetwork, WifiSecurity};
+
+const SERVICE: &str = "org.freedesktop.NetworkManager";
+const MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+const DEVICE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const ACCESS_POINT: &str = "org.freedesktop.NetworkManager.AccessPoint";
+/// `NM_DEVICE_TYPE_WIFI`.
+const DEVICE_TYPE_WIFI: u32 = 2;
+/// Object path NetworkManager uses for "no access point".
+const NO_ACCESS_POINT: &str = "/";
+
+// `NM80211ApFlags` and `NM80211ApSecurityFlags`.
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+const KEY_MGMT_PSK: u32 = 0x100;
+const KEY_MGMT_802_1X: u32 = 0x200;
+const KEY_MGMT_SAE: u32 = 0x400;
+
+/// Client for the NetworkManager daemon.
+#[derive(Debug, Clone)]
+pub struct NetworkManager {
+    connection: Connection,
+}
+
+impl NetworkManager {
+    /// Talk to NetworkManager on the system bus.
+    pub async fn system() -> zbus::Result<Self> {
+        Ok(Self::with_connection(Connection::system().await?))
+    }
+
+    /// Talk to NetworkManager over `connection`, e.g. a peer-to-peer one to
+    /// a mock service.
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    /// The network the first associated Wi-Fi device is on, or `None` when
+    /// no Wi-Fi device has an active access point.
+    pub async fn current_network(&self) -> zbus::Result<Option<WifiNetwork>> {
+        let devices: Vec<OwnedObjectPath> = self
+            .proxy(MANAGER_PATH, SERVICE)
+            .await?
+            .get_property("Devices")
+            .await?;
+        for device in &devices {
+            let properties = self.proxy(device, DEVICE).await?;
+            if properties.get_property::<u32>("DeviceType").await? != DEVICE_TYPE_WIFI {
+                continue;
+            }
+            let access_point: OwnedObjectPath = self
+                .proxy(device, WIRELESS)
+                .await?
+                .get_property("ActiveAccessPoint")
+                .await?;
+            if access_point.as_str() == NO_ACCESS_POINT {
+                continue;
+            }
+            let interface = properties.get_property("Interface").await?;
+            return self.access_point(interface, &access_point).await.map(Some);
+        }
+        Ok(None)
+    }
+
+    async fn access_point(&self, interface: String, path: &str) -> zbus::Result<WifiNetwork> {
+        let access_point = self.proxy(path, ACCESS_POINT).await?;
+        let ssid: Vec<u8> = access_point.get_property("Ssid").await?;
+        let bssid: String = access_point.get_property("HwAddress").await?;
+        let strength: u8 = access_point.get_property("Strength").await?;
+        let flags: u32 = access_point.get_property("Flags").await?;
+        let wpa_flags: u32 = access_point.get_property("WpaFlags").await?;
+        let rsn_flags: u32 = access_point.get_property("RsnFlags").await?;
+        Ok(WifiNetwork {
+            interface,
+            ssid: String::from_utf8_lossy(&ssid).into_owned(),
+            bssid: Some(bssid).filter(|bssid| !bssid.is_empty()),
+            strength: Some(strength.min(100)),
+            signal_dbm: None,
+            security: security(flags, wpa_flags, rsn_flags),
+        })
+    }
+
+    /// Properties are read on demand; a property cache would subscribe to
+    /// change signals through the bus daemon, which peer-to-peer
+    /// connections do not have.
+    async fn proxy<'a>(&self, path: &'a str, interface: &'a str) -> zbus::Result<Proxy<'a>> {
+        zbus::proxy::Builder::new(&self.connection)
+            .destination(SERVICE)?
+            .path(path)?
+            .interface(interface)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+    }
+}
+
+/// WPA3 wins on transition networks that offer both SAE and PSK.
+fn security(flags: u32, wpa_flags: u32, rsn_flags: u32) -> WifiSecurity {
+    let key_management = wpa_flags | rsn_flags;
+    if key_management & KEY_MGMT_SAE != 0 {
+        WifiSecurity::Wpa3Personal
+    } else if key_management & KEY_MGMT_802_1X != 0 {
+        WifiSecurity::WpaEnterprise
+    } else if key_management & KEY_MGMT_PSK != 0 {
+        WifiSecurity::WpaPersonal
+    } else if flags & AP_FLAGS_PRIVACY != 0 {
+        WifiSecurity::Wep
+    } else {
+        WifiSecurity::Open
+    }
+}
@@ -0,0 +1,219 @@
+//! Wi-Fi details from wpa_supplicant's control interface.
+//!
+//! wpa_supplicant creates a datagram socket per interface in its control
+//! directory, named after the interface. Commands are plain text; `STATUS`
+//! and `SIGNAL_POLL` answer with `key=value` lines. Replies are sent back to
+//! the requesting socket's address, so every request binds a fresh socket
+//! in the abstract namespace.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    os::{
+        linux::net::SocketAddrExt as _,
+        unix::{fs::FileTypeExt as _, net},
+    },
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use tokio::net::UnixDatagram;
+
+use crate::wifi::linux::{WifiNetwork, WifiSecurity};
+
+/// Control directory of a stock wpa_supplicant install.
+pub const DEFAULT_CONTROL_DIR: &str = "/var/run/wpa_supplicant";
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// wpa_cli's reply buffer size.
+const MAX_REPLY_LEN: usize = 4096;
+
+/// Client for the per-interface control sockets of wpa_supplicant.
+#[derive(Debug, Clone)]
+pub struct WpaSupplicant {
+    control_dir: PathBuf,
+}
+
+impl WpaSupplicant {
+    /// Use [`DEFAULT_CONTROL_DIR`].
+    pub fn new() -> Self {
+        Self::with_control_dir(DEFAULT_CONTROL_DIR)
+    }
+
+    pub fn with_control_dir(control_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            control_dir: control_dir.into(),
+        }
+    }
+
+    /// The network of the first interface, by name, whose `wpa_state` is
+    /// `COMPLETED`. Sockets that do not answer are skipped; the error is
+    /// only returned when none did. A missing control directory means
+    /// wpa_supplicant is not running.
+    pub async fn current_network(&self) -> io::Result<Option<WifiNetwork>> {
+        let entries = match fs::read_dir(&self.control_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut sockets = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_socket() {
+                sockets.push(entry.path());
+            }
+        }
+        sockets.sort();
+
+        let mut failure = None;
+        let mut answered = false;
+        for socket in &sockets {
+            let status = match request(socket, "STATUS").await {
+                Ok(status) => status,
+                Err(err) => {
+                    failure = Some(err);
+                    continue;
+                }
+            };
+            answered = true;
+            if status.get("wpa_state").map(String::as_str) != Some("COMPLETED") {
+                continue;
+            }
+            let Some(ssid) = status.get("ssid") else {
+                continue;
+            };
+            // Drivers without signal reporting answer `FAIL`.
+            let signal_dbm = request(socket, "SIGNAL_POLL")
+                .await
+                .ok()
+                .and_then(|poll| poll.get("RSSI")?.parse::<i32>().ok());
+            let interface = socket
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            return Ok(Some(WifiNetwork {
+                interface,
+                ssid: unescape(ssid),
+                bssid: status.get("bssid").cloned(),
+                strength: signal_dbm.map(strength),
+                signal_dbm,
+                security: security(
+                    status.get("key_mgmt").map_or("", String::as_str),
+                    status.get("group_cipher").map_or("", String::as_str),
+                ),
+            }));
+        }
+        match failure {
+            Some(err) if !answered => Err(err),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Default for WpaSupplicant {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send `command` to the control socket at `path` and parse the reply's
+/// `key=value` lines.
+async fn request(path: &Path, command: &str) -> io::Result<HashMap<String, String>> {
+    static NEXT_CLIENT: AtomicU64 = AtomicU64::new(0);
+    let name = format!(
+        "service-platform/wpa_ctrl/{}/{}",
+        process::id(),
+        NEXT_CLIENT.fetch_add(1, Ordering::Relaxed)
+    );
+    let client = net::UnixDatagram::bind_addr(&net::SocketAddr::from_abstract_name(name)?)?;
+    client.set_nonblocking(true)?;
+    let client = UnixDatagram::from_std(client)?;
+    client.connect(path)?;
+    client.send(command.as_bytes()).await?;
+
+    let mut reply = vec![0; MAX_REPLY_LEN];
+    let len = tokio::time::timeout(REPLY_TIMEOUT, client.recv(&mut reply))
+        .await
+        .map_err(|_| {
+            io::Error::new(
+                ErrorKind::TimedOut,
+                format!("{} did not answer {command}", path.display()),
+            )
+        })??;
+    let reply = String::from_utf8_lossy(&reply[..len]);
+    if reply.trim_end() == "FAIL" {
+        return Err(io::Error::other(format!(
+            "{} rejected {command}",
+            path.display()
+        )));
+    }
+    Ok(reply
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+/// `key_mgmt` names as printed by `wpa_key_mgmt_txt`; WEP networks report
+/// `NONE` with a WEP group cipher.
+fn security(key_mgmt: &str, group_cipher: &str) -> WifiSecurity {
+    if key_mgmt.contains("SAE") {
+        WifiSecurity::Wpa3Personal
+    } else if key_mgmt.contains("EAP") || key_mgmt.contains("802.1X") {
+        WifiSecurity::WpaEnterprise
+    } else if key_mgmt.contains("PSK") {
+        WifiSecurity::WpaPersonal
+    } else if key_mgmt == "NONE" && group_cipher.starts_with("WEP") {
+        WifiSecurity::Wep
+    } else if key_mgmt == "NONE" || key_mgmt == "OWE" {
+        WifiSecurity::Open
+    } else {
+        WifiSecurity::Unknown
+    }
+}
+
+/// NetworkManager's mapping from RSSI to a 0-100 strength.
+fn strength(dbm: i32) -> u8 {
+    ((dbm + 100) * 2).clamp(0, 100) as u8
+}
+
+/// Undo `printf_encode`, which `STATUS` applies to the SSID.
+fn unescape(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let Some((&escaped, tail)) = rest.split_first() else {
+            bytes.push(byte);
+            break;
+        };
+        rest = tail;
+        match escaped {
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'e' => bytes.push(0x1b),
+            b'x' => {
+                let hex = rest
+                    .get(..2)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(value) => {
+                        bytes.push(value);
+                        rest = &rest[2..];
+                    }
+                    None => bytes.extend_from_slice(b"\\x"),
+                }
+            }
+            other => bytes.push(other),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
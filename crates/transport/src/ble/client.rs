@@ -0,0 +1,266 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::ble::{
+    framing::{split_message, BleFrame, BleMessage, FrameKind, FramingError, Reassembler},
+    link::SimulatedCentral,
+    protocol::{
+        BleEvent, BleHandshake, BlePairAccept, BlePairRequest, BleRpcRequest, BleRpcResponse,
+    },
+    secure::{KeyExchange, Opener, PairingKey, Role, Sealer},
+};
+
+/// Largest message accepted from the device before reassembly is aborted.
+const MAX_MESSAGE_LEN: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub enum BleClientError {
+    Disconnected,
+    Framing(FramingError),
+    Decode(String),
+    /// The secure session could not be opened.
+    Pairing(String),
+}
+
+impl Display for BleClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BleClientError::Disconnected => write!(f, "link disconnected"),
+            BleClientError::Framing(err) => write!(f, "framing error: {err}"),
+            BleClientError::Decode(msg) => write!(f, "failed to decode message: {msg}"),
+            BleClientError::Pairing(msg) => write!(f, "pairing failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for BleClientError {}
+
+/// Message-level client speaking the RPC protocol over a simulated link.
+pub struct BleClient {
+    central: SimulatedCentral,
+    reassembler: Reassembler,
+    next_msg_id: u32,
+    responses: HashMap<u32, VecDeque<BleRpcResponse>>,
+    events: VecDeque<BleEvent>,
+    sealer: Option<Sealer>,
+    opener: Option<Opener>,
+}
+
+impl BleClient {
+    pub fn new(central: SimulatedCentral) -> Self {
+        Self {
+            central,
+            reassembler: Reassembler::new(MAX_MESSAGE_LEN, Duration::from_secs(30)),
+            next_msg_id: 1,
+            responses: HashMap::new(),
+            events: VecDeque::new(),
+            sealer: None,
+            opener: None,
+        }
+    }
+
+    /// Pair with the device using its pairing code and open an encrypted
+    /// session. Returns the pairing key to keep for [`Self::resume`].
+    pub async fn pair(
+        &mut self,
+        client_id: &str,
+        code: &str,
+    ) -> Result<PairingKey, BleClientError> {
+        self.open_session(client_id, code.as_bytes(), false).await
+    }
+
+    /// Open an encrypted session as a central paired earlier.
+    pub async fn resume(
+        &mut self,
+        client_id: &str,
+        pairing_key: &PairingKey,
+    ) -> Result<(), BleClientError> {
+        self.open_session(client_id, pairing_key, true)
+            .await
+            .map(|_| ())
+    }
+
+    async fn open_session(
+        &mut self,
+        client_id: &str,
+        psk: &[u8],
+        resume: bool,
+    ) -> Result<PairingKey, BleClientError> {
+        self.sealer = None;
+        self.opener = None;
+        let exchange = KeyExchange::new(psk, client_id);
+        let request = BlePairRequest {
+            client_id: client_id.to_string(),
+            public_key_b64: exchange.public_b64(),
+            resume,
+        };
+        let msg_id = self.send(FrameKind::Pair, &request).await?;
+        let response = self.next_response(msg_id).await?;
+        if let Some(error) = response.error {
+            return Err(BleClientError::Pairing(format!(
+                "{}: {}",
+                error.code, error.message
+            )));
+        }
+        let payload = general_purpose::STANDARD
+            .decode(response.payload_b64)
+            .map_err(|err| BleClientError::Decode(err.to_string()))?;
+        let accept: BlePairAccept = decode_body(&payload)?;
+        let mut keys = exchange
+            .finish(Role::Central, &accept.public_key_b64, client_id)
+            .map_err(|err| BleClientError::Pairing(err.to_string()))?;
+        let confirm = general_purpose::STANDARD
+            .decode(accept.confirm_b64)
+            .map_err(|err| BleClientError::Decode(err.to_string()))?;
+        keys.opener
+            .open(FrameKind::Response, msg_id, &confirm)
+            .map_err(|_| {
+                BleClientError::Pairing("device did not prove the shared secret".to_string())
+            })?;
+        self.sealer = Some(keys.sealer);
+        self.opener = Some(keys.opener);
+        Ok(keys.pairing_key)
+    }
+
+    /// Present a bearer token and wait for the device's verdict.
+    pub async fn authenticate(&mut self, token: &str) -> Result<BleRpcResponse, BleClientError> {
+        let handshake = BleHandshake {
+            token: token.to_string(),
+        };
+        let msg_id = self.send(FrameKind::Handshake, &handshake).await?;
+        self.next_response(msg_id).await
+    }
+
+    /// Issue an RPC and wait for its response.
+    pub async fn call(
+        &mut self,
+        request: &BleRpcRequest,
+    ) -> Result<BleRpcResponse, BleClientError> {
+        let msg_id = self.send(FrameKind::Request, request).await?;
+        self.next_response(msg_id).await
+    }
+
+    /// Issue a streaming RPC and collect every response up to and including
+    /// the terminating one.
+    pub async fn call_stream(
+        &mut self,
+        request: &BleRpcRequest,
+    ) -> Result<Vec<BleRpcResponse>, BleClientError> {
+        let msg_id = self.send(FrameKind::Request, request).await?;
+        let mut responses = Vec::new();
+        loop {
+            let response = self.next_response(msg_id).await?;
+            let more = response.more;
+            responses.push(response);
+            if !more {
+                return Ok(responses);
+            }
+        }
+    }
+
+    /// Wait for the next response carrying `msg_id`.
+    pub async fn next_response(&mut self, msg_id: u32) -> Result<BleRpcResponse, BleClientError> {
+        loop {
+            if let Some(response) = self
+                .responses
+                .get_mut(&msg_id)
+                .and_then(|queue| queue.pop_front())
+            {
+                return Ok(response);
+            }
+            self.pump().await?;
+        }
+    }
+
+    /// Publish an event onto the device bus.
+    pub async fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<(), BleClientError> {
+        let event = BleEvent {
+            topic: topic.to_string(),
+            payload_b64: general_purpose::STANDARD.encode(payload),
+        };
+        self.send(FrameKind::Event, &event).await.map(|_| ())
+    }
+
+    /// Wait for the next event notified on `events_tx`.
+    pub async fn next_event(&mut self) -> Result<BleEvent, BleClientError> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+            self.pump().await?;
+        }
+    }
+
+    /// Serialize and send a message, returning its message id.
+    pub async fn send<T: Serialize>(
+        &mut self,
+        kind: FrameKind,
+        body: &T,
+    ) -> Result<u32, BleClientError> {
+        let msg_id = self.next_msg_id;
+        self.next_msg_id = self.next_msg_id.wrapping_add(1);
+        let mut bytes =
+            serde_json::to_vec(body).map_err(|err| BleClientError::Decode(err.to_string()))?;
+        if let (Some(sealer), false) = (self.sealer.as_mut(), kind == FrameKind::Pair) {
+            bytes = sealer.seal(kind, msg_id, &bytes);
+        }
+        for frame in split_message(msg_id, kind, &bytes, self.central.mtu()) {
+            if !self.central.write(frame.encode()).await {
+                return Err(BleClientError::Disconnected);
+            }
+        }
+        Ok(msg_id)
+    }
+
+    /// Receive the next complete message from either notify characteristic,
+    /// opened if a secure session is established.
+    pub async fn recv_message(&mut self) -> Result<BleMessage, BleClientError> {
+        loop {
+            let (_, bytes) = self
+                .central
+                .recv_notification()
+                .await
+                .ok_or(BleClientError::Disconnected)?;
+            let frame = BleFrame::decode(&bytes).map_err(BleClientError::Framing)?;
+            if let Some(message) = self
+                .reassembler
+                .push(frame, Instant::now())
+                .map_err(BleClientError::Framing)?
+            {
+                let Some(opener) = self.opener.as_mut() else {
+                    return Ok(message);
+                };
+                let payload = opener
+                    .open(message.kind, message.msg_id, &message.payload)
+                    .map_err(|err| BleClientError::Decode(err.to_string()))?;
+                return Ok(BleMessage { payload, ..message });
+            }
+        }
+    }
+
+    async fn pump(&mut self) -> Result<(), BleClientError> {
+        let message = self.recv_message().await?;
+        match message.kind {
+            FrameKind::Response => {
+                let response = decode_body(&message.payload)?;
+                self.responses
+                    .entry(message.msg_id)
+                    .or_default()
+                    .push_back(response);
+            }
+            FrameKind::Event => self.events.push_back(decode_body(&message.payload)?),
+            FrameKind::Request | FrameKind::Handshake | FrameKind::Pair => {}
+        }
+        Ok(())
+    }
+}
+
+fn decode_body<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, BleClientError> {
+    serde_json::from_slice(bytes).map_err(|err| BleClientError::Decode(err.to_string()))
+}
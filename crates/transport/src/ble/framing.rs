@@ -1,8 +1,324 @@
-/// Placeholder for BLE framing utilities.
-pub struct BleFrame;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// Size of the fixed frame header in bytes.
+pub const HEADER_LEN: usize = 14;
+/// Flag set on the first chunk of a message.
+pub const FLAG_FIRST: u8 = 0b01;
+/// Flag set on the last chunk of a message.
+pub const FLAG_LAST: u8 = 0b10;
+/// Messages a peer may have in reassembly at once.
+pub const MAX_PARTIAL_MESSAGES: usize = 8;
+
+/// Message kind carried in every frame header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameKind {
+    Request,
+    Response,
+    Event,
+    /// Credentials presented by the central before its first request.
+    Handshake,
+    /// Key exchange opening an encrypted session.
+    Pair,
+}
+
+impl FrameKind {
+    pub fn as_u8(self) -> u8 {
+        match self {
+            FrameKind::Request => 0,
+            FrameKind::Response => 1,
+            FrameKind::Event => 2,
+            FrameKind::Handshake => 3,
+            FrameKind::Pair => 4,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(FrameKind::Request),
+            1 => Some(FrameKind::Response),
+            2 => Some(FrameKind::Event),
+            3 => Some(FrameKind::Handshake),
+            4 => Some(FrameKind::Pair),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramingError {
+    Truncated,
+    UnknownKind(u8),
+    OutOfOrder {
+        msg_id: u32,
+        expected: u16,
+        got: u16,
+    },
+    UnexpectedChunk {
+        msg_id: u32,
+    },
+    LengthMismatch {
+        msg_id: u32,
+    },
+    TooLarge {
+        msg_id: u32,
+        len: usize,
+    },
+    /// The peer already has [`MAX_PARTIAL_MESSAGES`] messages in reassembly.
+    TooManyPartial {
+        msg_id: u32,
+    },
+}
+
+impl Display for FramingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FramingError::Truncated => write!(f, "frame shorter than its header"),
+            FramingError::UnknownKind(kind) => write!(f, "unknown frame kind {kind}"),
+            FramingError::OutOfOrder {
+                msg_id,
+                expected,
+                got,
+            } => write!(
+                f,
+                "message {msg_id}: expected chunk {expected}, received {got}"
+            ),
+            FramingError::UnexpectedChunk { msg_id } => {
+                write!(f, "message {msg_id}: chunk received without a first chunk")
+            }
+            FramingError::LengthMismatch { msg_id } => {
+                write!(f, "message {msg_id}: reassembled length does not match")
+            }
+            FramingError::TooLarge { msg_id, len } => {
+                write!(f, "message {msg_id}: {len} bytes exceeds the size limit")
+            }
+            FramingError::TooManyPartial { msg_id } => write!(
+                f,
+                "message {msg_id}: too many messages in reassembly at once"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FramingError {}
+
+/// A single BLE chunk as written to or notified from a characteristic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BleFrame {
+    pub msg_id: u32,
+    pub kind: FrameKind,
+    pub flags: u8,
+    pub seq: u16,
+    pub total_len: u32,
+    pub chunk: Vec<u8>,
+}
 
 impl BleFrame {
-    pub fn new() -> Self {
-        Self
+    pub fn is_first(&self) -> bool {
+        self.flags & FLAG_FIRST != 0
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.flags & FLAG_LAST != 0
+    }
+
+    /// Encode the frame using the big-endian header layout from `docs/protocol.md`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.chunk.len());
+        out.extend_from_slice(&self.msg_id.to_be_bytes());
+        out.push(self.kind.as_u8());
+        out.push(self.flags);
+        out.extend_from_slice(&self.seq.to_be_bytes());
+        out.extend_from_slice(&self.total_len.to_be_bytes());
+        out.extend_from_slice(&(self.chunk.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.chunk);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, FramingError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(FramingError::Truncated);
+        }
+        let msg_id = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let kind = FrameKind::from_u8(bytes[4]).ok_or(FramingError::UnknownKind(bytes[4]))?;
+        let flags = bytes[5];
+        let seq = u16::from_be_bytes([bytes[6], bytes[7]]);
+        let total_len = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        let chunk_len = u16::from_be_bytes([bytes[12], bytes[13]]) as usize;
+        let chunk = bytes
+            .get(HEADER_LEN..HEADER_LEN + chunk_len)
+            .ok_or(FramingError::Truncated)?
+            .to_vec();
+
+        Ok(Self {
+            msg_id,
+            kind,
+            flags,
+            seq,
+            total_len,
+            chunk,
+        })
+    }
+}
+
+/// Split a message into frames no larger than `mtu` bytes including the header.
+pub fn split_message(msg_id: u32, kind: FrameKind, payload: &[u8], mtu: usize) -> Vec<BleFrame> {
+    let chunk_size = mtu.saturating_sub(HEADER_LEN).clamp(1, u16::MAX as usize);
+    let mut chunks: Vec<&[u8]> = payload.chunks(chunk_size).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+
+    let count = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut flags = 0;
+            if index == 0 {
+                flags |= FLAG_FIRST;
+            }
+            if index + 1 == count {
+                flags |= FLAG_LAST;
+            }
+            BleFrame {
+                msg_id,
+                kind,
+                flags,
+                seq: index as u16,
+                total_len: if index == 0 { payload.len() as u32 } else { 0 },
+                chunk: chunk.to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// A fully reassembled message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BleMessage {
+    pub msg_id: u32,
+    pub kind: FrameKind,
+    pub payload: Vec<u8>,
+}
+
+struct PartialMessage {
+    total_len: usize,
+    next_seq: u16,
+    buffer: Vec<u8>,
+    started: Instant,
+}
+
+/// Reassembles frames into messages following the strict ordering rules of
+/// the MVP protocol.
+pub struct Reassembler {
+    max_message_len: usize,
+    timeout: Duration,
+    partial: HashMap<(u32, FrameKind), PartialMessage>,
+}
+
+impl Reassembler {
+    pub fn new(max_message_len: usize, timeout: Duration) -> Self {
+        Self {
+            max_message_len,
+            timeout,
+            partial: HashMap::new(),
+        }
+    }
+
+    /// Feed one frame, returning the message once its last chunk arrives.
+    ///
+    /// Any error drops the partially reassembled message. A first chunk is
+    /// refused while [`MAX_PARTIAL_MESSAGES`] other messages are in
+    /// reassembly, and buffers grow with the chunks received rather than the
+    /// length the peer announced, so a peer cannot make the receiver hold
+    /// more than it actually sent.
+    pub fn push(
+        &mut self,
+        frame: BleFrame,
+        now: Instant,
+    ) -> Result<Option<BleMessage>, FramingError> {
+        let key = (frame.msg_id, frame.kind);
+        if frame.is_first() {
+            let total_len = frame.total_len as usize;
+            if total_len > self.max_message_len {
+                self.partial.remove(&key);
+                return Err(FramingError::TooLarge {
+                    msg_id: frame.msg_id,
+                    len: total_len,
+                });
+            }
+            if !self.partial.contains_key(&key) && self.partial.len() >= MAX_PARTIAL_MESSAGES {
+                return Err(FramingError::TooManyPartial {
+                    msg_id: frame.msg_id,
+                });
+            }
+            self.partial.insert(
+                key,
+                PartialMessage {
+                    total_len,
+                    next_seq: 0,
+                    buffer: Vec::new(),
+                    started: now,
+                },
+            );
+        }
+
+        let partial = self
+            .partial
+            .get_mut(&key)
+            .ok_or(FramingError::UnexpectedChunk {
+                msg_id: frame.msg_id,
+            })?;
+        if frame.seq != partial.next_seq {
+            let expected = partial.next_seq;
+            self.partial.remove(&key);
+            return Err(FramingError::OutOfOrder {
+                msg_id: frame.msg_id,
+                expected,
+                got: frame.seq,
+            });
+        }
+        if partial.buffer.len() + frame.chunk.len() > partial.total_len {
+            self.partial.remove(&key);
+            return Err(FramingError::LengthMismatch {
+                msg_id: frame.msg_id,
+            });
+        }
+        partial.buffer.extend_from_slice(&frame.chunk);
+        partial.next_seq = partial.next_seq.wrapping_add(1);
+
+        if !frame.is_last() {
+            return Ok(None);
+        }
+
+        let partial = self.partial.remove(&key).expect("partial message present");
+        if partial.buffer.len() != partial.total_len {
+            return Err(FramingError::LengthMismatch {
+                msg_id: frame.msg_id,
+            });
+        }
+        Ok(Some(BleMessage {
+            msg_id: frame.msg_id,
+            kind: frame.kind,
+            payload: partial.buffer,
+        }))
+    }
+
+    /// Drop messages whose reassembly timed out, returning their ids.
+    pub fn expire(&mut self, now: Instant) -> Vec<(u32, FrameKind)> {
+        let timeout = self.timeout;
+        let expired: Vec<_> = self
+            .partial
+            .iter()
+            .filter(|(_, partial)| now.duration_since(partial.started) >= timeout)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &expired {
+            self.partial.remove(key);
+        }
+        expired
     }
 }
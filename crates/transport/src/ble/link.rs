@@ -0,0 +1,116 @@
+use tokio::sync::mpsc;
+
+/// Default ATT payload size assumed when the link does not report one.
+pub const DEFAULT_MTU: usize = 185;
+
+/// GATT characteristics exposed by the RPC service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Characteristic {
+    /// Client writes request and event chunks.
+    RpcRx,
+    /// Device notifies response chunks.
+    RpcTx,
+    /// Device notifies event chunks.
+    EventsTx,
+}
+
+/// Device side of a connected BLE central.
+///
+/// Backends (BlueZ, the simulator) pump characteristic writes into the link
+/// and drain notifications from it; the link closes when the central
+/// disconnects.
+pub struct BleLink {
+    writes: mpsc::Receiver<Vec<u8>>,
+    notifications: mpsc::Sender<(Characteristic, Vec<u8>)>,
+    peer: String,
+    mtu: usize,
+}
+
+impl BleLink {
+    pub fn new(
+        writes: mpsc::Receiver<Vec<u8>>,
+        notifications: mpsc::Sender<(Characteristic, Vec<u8>)>,
+        peer: impl Into<String>,
+        mtu: usize,
+    ) -> Self {
+        Self {
+            writes,
+            notifications,
+            peer: peer.into(),
+            mtu,
+        }
+    }
+
+    /// Create a link connected to an in-memory central for tests and local development.
+    pub fn simulated(peer: impl Into<String>, mtu: usize) -> (Self, SimulatedCentral) {
+        let (write_tx, write_rx) = mpsc::channel(64);
+        let (notify_tx, notify_rx) = mpsc::channel(64);
+        let link = Self::new(write_rx, notify_tx, peer, mtu);
+        let central = SimulatedCentral {
+            writes: write_tx,
+            notifications: notify_rx,
+            mtu,
+        };
+        (link, central)
+    }
+
+    /// Address of the connected central.
+    pub fn peer(&self) -> &str {
+        &self.peer
+    }
+
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Receive the next raw write to `rpc_rx`, or `None` once disconnected.
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        self.writes.recv().await
+    }
+
+    /// Handle for sending notifications from spawned tasks.
+    pub fn notifier(&self) -> BleNotifier {
+        BleNotifier {
+            notifications: self.notifications.clone(),
+        }
+    }
+}
+
+/// Cloneable sender for characteristic notifications.
+#[derive(Clone)]
+pub struct BleNotifier {
+    notifications: mpsc::Sender<(Characteristic, Vec<u8>)>,
+}
+
+impl BleNotifier {
+    /// Notify raw bytes; returns `false` if the central has disconnected.
+    pub async fn notify(&self, characteristic: Characteristic, bytes: Vec<u8>) -> bool {
+        self.notifications
+            .send((characteristic, bytes))
+            .await
+            .is_ok()
+    }
+}
+
+/// Central side of a simulated link.
+pub struct SimulatedCentral {
+    writes: mpsc::Sender<Vec<u8>>,
+    notifications: mpsc::Receiver<(Characteristic, Vec<u8>)>,
+    mtu: usize,
+}
+
+impl SimulatedCentral {
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Write raw bytes to `rpc_rx`; returns `false` if the device side is gone.
+    pub async fn write(&self, bytes: Vec<u8>) -> bool {
+        self.writes.send(bytes).await.is_ok()
+    }
+
+    /// Receive the next raw notification, or `None` once the device side is gone.
+    pub async fn recv_notification(&mut self) -> Option<(Characteristic, Vec<u8>)> {
+        self.notifications.recv().await
+    }
+}
@@ -1,20 +1,736 @@
-use service_core::{Result, Transport, TransportId};
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-#[allow(dead_code)]
-pub struct BleTransport;
+use base64::{engine::general_purpose, Engine as _};
+use service_core::{
+    acl::AccessPolicy,
+    auth::DynAuthenticator,
+    event::{
+        ClientEventPublisher, DynEventBus, EventError, EventSubscription, TopicAllowlist,
+        TransportEvent,
+    },
+    router::{Peer, Principal, RpcError, RpcRegistry, RpcRequest},
+    Result, Transport, TransportId,
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::JoinSet,
+};
+
+use crate::ble::{
+    framing::{split_message, BleFrame, BleMessage, FrameKind, Reassembler},
+    link::{BleLink, BleNotifier, Characteristic},
+    protocol::{
+        BleEvent, BleHandshake, BlePairAccept, BlePairRequest, BleRpcError, BleRpcRequest,
+        BleRpcResponse, BleTransportError, TRANSPORT_ERROR_TOPIC,
+    },
+    secure::{KeyExchange, Opener, PairingKey, Role, Sealer, SecurePairing, SessionKeys},
+};
+
+pub mod bluez;
+pub mod client;
+pub mod framing;
+pub mod link;
+pub mod protocol;
+pub mod secure;
+
+/// Transport id reported by [`BleTransport`] and set on request contexts.
+pub const BLE_TRANSPORT_ID: &str = "ble";
+/// Largest message accepted from a central before reassembly is aborted.
+const DEFAULT_MAX_MESSAGE_LEN: usize = 32 * 1024;
+/// Time allowed between the first and last chunk of a message.
+const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Sealed messages that may fail to open before the link is dropped. Guesses
+/// of the pairing code are also bounded across links by [`SecurePairing`].
+const MAX_SECURE_FAILURES: u32 = 3;
+
+/// GATT transport carrying RPCs on `rpc_rx`/`rpc_tx` and events on `events_tx`.
+#[derive(Clone)]
+pub struct BleTransport {
+    registry: Arc<dyn RpcRegistry>,
+    events: Option<ClientEventPublisher>,
+    authenticator: Option<DynAuthenticator>,
+    policy: Option<Arc<AccessPolicy>>,
+    pairing: Option<SecurePairing>,
+    max_message_len: usize,
+    reassembly_timeout: Duration,
+}
+
+/// Per-link authentication state.
+enum Session {
+    /// No authenticator configured; everything is allowed.
+    Open,
+    /// Waiting for a successful handshake.
+    Pending,
+    Authenticated(Principal),
+}
+
+impl Session {
+    fn is_authorized(&self) -> bool {
+        !matches!(self, Session::Pending)
+    }
+
+    fn principal(&self) -> Option<Principal> {
+        match self {
+            Session::Authenticated(principal) => Some(principal.clone()),
+            Session::Open | Session::Pending => None,
+        }
+    }
+}
+
+/// Per-link encryption state.
+enum Encryption {
+    /// Secure pairing is not configured; messages travel in plaintext.
+    Disabled,
+    /// Waiting for a `pair` message.
+    Required,
+    Established {
+        opener: Opener,
+        /// Pairing key to store once the central proves it holds the keys.
+        unconfirmed: Option<(String, PairingKey)>,
+    },
+}
+
+impl Encryption {
+    fn is_ready(&self) -> bool {
+        !matches!(self, Encryption::Required)
+    }
+}
 
 impl BleTransport {
-    pub fn new() -> Self {
-        Self
+    pub fn new(registry: Arc<dyn RpcRegistry>) -> Self {
+        Self {
+            registry,
+            events: None,
+            authenticator: None,
+            policy: None,
+            pairing: None,
+            max_message_len: DEFAULT_MAX_MESSAGE_LEN,
+            reassembly_timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+        }
+    }
+
+    /// Require a `handshake` message with a valid token on each link.
+    ///
+    /// Until it succeeds, requests fail with `unauthenticated`, client events
+    /// are rejected and no bus events are notified.
+    pub fn with_authenticator(mut self, authenticator: DynAuthenticator) -> Self {
+        self.authenticator = Some(authenticator);
+        self
+    }
+
+    /// Require an encrypted session, opened with a `pair` message, on each
+    /// link.
+    ///
+    /// Until then requests fail with `unauthenticated`, client events are
+    /// rejected and no bus events are notified. A link is dropped after
+    /// three sealed messages fail to open, and pairing with the code locks
+    /// after five unconfirmed attempts across all links.
+    pub fn with_secure_pairing(mut self, pairing: SecurePairing) -> Self {
+        self.pairing = Some(pairing);
+        self
+    }
+
+    /// Notify bus events on `events_tx` and accept client events written to
+    /// `rpc_rx` for topics matched by `allowlist`.
+    pub fn with_events(mut self, events: DynEventBus, allowlist: TopicAllowlist) -> Self {
+        self.events =
+            Some(ClientEventPublisher::new(events, allowlist).with_policy(self.policy.clone()));
+        self
+    }
+
+    /// Apply the topic rules of `policy` to the event channel: a central
+    /// only receives and publishes events on topics it allows the session's
+    /// principal. Method rules are enforced by the router.
+    pub fn with_access_policy(mut self, policy: Arc<AccessPolicy>) -> Self {
+        self.events = self
+            .events
+            .take()
+            .map(|events| events.with_policy(Some(policy.clone())));
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Serve every link the adapter backend hands over on `links`, each on
+    /// its own task, until the sender side closes and the links drop.
+    ///
+    /// Dropping the returned future disconnects every link it serves, so a
+    /// stopped transport can be restarted on the same receiver.
+    pub async fn serve_links(&self, links: &mut mpsc::Receiver<BleLink>) {
+        let mut served = JoinSet::new();
+        loop {
+            tokio::select! {
+                link = links.recv() => {
+                    let Some(link) = link else { break };
+                    let transport = self.clone();
+                    served.spawn(async move { transport.serve(link).await });
+                }
+                Some(_) = served.join_next(), if !served.is_empty() => {}
+            }
+        }
+        while served.join_next().await.is_some() {}
+    }
+
+    /// Serve one connected central until the link drops.
+    ///
+    /// In-flight RPCs are aborted when the link disconnects.
+    pub async fn serve(&self, mut link: BleLink) {
+        let outbound = Outbound::new(link.notifier(), link.mtu());
+        let peer = link.peer().to_string();
+        let mut reassembler = Reassembler::new(self.max_message_len, self.reassembly_timeout);
+        let mut subscription = self
+            .events
+            .as_ref()
+            .map(|events| events.events().subscribe());
+        let mut in_flight = JoinSet::new();
+        let mut expiry = tokio::time::interval(self.reassembly_timeout);
+        let mut session = self.new_session();
+        let mut encryption = match self.pairing {
+            Some(_) => Encryption::Required,
+            None => Encryption::Disabled,
+        };
+        let mut secure_failures = 0;
+
+        loop {
+            tokio::select! {
+                write = link.recv() => {
+                    let Some(bytes) = write else { break };
+                    match BleFrame::decode(&bytes)
+                        .and_then(|frame| reassembler.push(frame, Instant::now()))
+                    {
+                        Ok(Some(message)) => match self.unseal(&mut encryption, message) {
+                            Ok(message) if message.kind == FrameKind::Pair => {
+                                self.handle_pair(message, &mut session, &mut encryption, &outbound)
+                                    .await;
+                            }
+                            Ok(message) => self.handle_message(
+                                message,
+                                &peer,
+                                &mut session,
+                                &mut encryption,
+                                &outbound,
+                                &mut in_flight,
+                            ),
+                            Err(error) => {
+                                outbound.send_transport_error(&error).await;
+                                secure_failures += 1;
+                                if secure_failures >= MAX_SECURE_FAILURES {
+                                    break;
+                                }
+                            }
+                        },
+                        Ok(None) => {}
+                        Err(err) => {
+                            let error = BleTransportError {
+                                kind: "framing".to_string(),
+                                msg_id: 0,
+                                reason: err.to_string(),
+                            };
+                            outbound.send_transport_error(&error).await;
+                        }
+                    }
+                }
+                event = recv_event(&mut subscription) => {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(EventError::Lagged(_)) => continue,
+                        Err(_) => {
+                            subscription = None;
+                            continue;
+                        }
+                    };
+                    let allowed = self.events.as_ref().is_some_and(|events| {
+                        events.may_use(session.principal().as_ref(), &event.topic)
+                    });
+                    if !session.is_authorized() || !encryption.is_ready() || !allowed {
+                        continue;
+                    }
+                    let body = BleEvent {
+                        topic: event.topic,
+                        payload_b64: encode_payload(&event.payload),
+                    };
+                    if !outbound.send_event(&body).await {
+                        break;
+                    }
+                }
+                _ = expiry.tick() => {
+                    for (msg_id, kind) in reassembler.expire(Instant::now()) {
+                        let error = BleTransportError {
+                            kind: kind_name(kind).to_string(),
+                            msg_id,
+                            reason: "reassembly timed out".to_string(),
+                        };
+                        outbound.send_transport_error(&error).await;
+                    }
+                }
+                Some(_) = in_flight.join_next(), if !in_flight.is_empty() => {}
+            }
+        }
+
+        in_flight.abort_all();
+    }
+
+    fn handle_message(
+        &self,
+        message: BleMessage,
+        peer: &str,
+        session: &mut Session,
+        encryption: &mut Encryption,
+        outbound: &Outbound,
+        in_flight: &mut JoinSet<()>,
+    ) {
+        match message.kind {
+            _ if !encryption.is_ready() => {
+                self.reject_insecure(message, outbound, in_flight);
+            }
+            FrameKind::Handshake => {
+                let response = match self.handshake(&message.payload) {
+                    Ok(principal) => {
+                        let response = BleRpcResponse {
+                            payload_b64: encode_payload(principal.id.as_bytes()),
+                            error: None,
+                            more: false,
+                        };
+                        *session = Session::Authenticated(principal);
+                        response
+                    }
+                    Err(err) => error_response(&err),
+                };
+                let outbound = outbound.clone();
+                in_flight.spawn(async move {
+                    outbound
+                        .send(message.msg_id, FrameKind::Response, &response)
+                        .await;
+                });
+            }
+            FrameKind::Request => {
+                let registry = self.registry.clone();
+                let outbound = outbound.clone();
+                let peer = Peer::Ble(peer.to_string());
+                let principal = session.principal();
+                let authorized = session.is_authorized();
+                in_flight.spawn(async move {
+                    let request = if authorized {
+                        decode_request(&message.payload, peer, principal)
+                    } else {
+                        Err(unauthenticated())
+                    };
+                    serve_request(registry, outbound, message.msg_id, request).await;
+                });
+            }
+            FrameKind::Event => {
+                let rejection = match &self.events {
+                    _ if !session.is_authorized() => Some(unauthenticated().to_string()),
+                    Some(events) => {
+                        publish_client_event(events, session.principal(), &message.payload).err()
+                    }
+                    None => Some("events are not enabled on this transport".to_string()),
+                };
+                if let Some(reason) = rejection {
+                    let outbound = outbound.clone();
+                    let error = BleTransportError {
+                        kind: kind_name(FrameKind::Event).to_string(),
+                        msg_id: message.msg_id,
+                        reason,
+                    };
+                    in_flight.spawn(async move {
+                        outbound.send_transport_error(&error).await;
+                    });
+                }
+            }
+            FrameKind::Response | FrameKind::Pair => {}
+        }
+    }
+
+    /// Run the device side of a `pair` message and switch the link to the
+    /// new keys.
+    ///
+    /// The accept is notified before the link counts as encrypted, so no
+    /// event can slip out in plaintext between the two. A new session also
+    /// needs a new handshake; the previous one does not carry over.
+    async fn handle_pair(
+        &self,
+        message: BleMessage,
+        session: &mut Session,
+        encryption: &mut Encryption,
+        outbound: &Outbound,
+    ) {
+        let (keys, request, device_public_b64) = match self.pair(&message.payload) {
+            Ok(paired) => paired,
+            Err(err) => {
+                outbound
+                    .send(message.msg_id, FrameKind::Response, &error_response(&err))
+                    .await;
+                return;
+            }
+        };
+        let SessionKeys {
+            mut sealer,
+            opener,
+            pairing_key,
+        } = keys;
+        let accept = BlePairAccept {
+            public_key_b64: device_public_b64,
+            confirm_b64: encode_payload(&sealer.seal(FrameKind::Response, message.msg_id, &[])),
+        };
+        let response = BleRpcResponse {
+            payload_b64: encode_payload(
+                &serde_json::to_vec(&accept).expect("pair accept serializes"),
+            ),
+            error: None,
+            more: false,
+        };
+        outbound.establish(message.msg_id, &response, sealer).await;
+        *session = self.new_session();
+        *encryption = Encryption::Established {
+            opener,
+            unconfirmed: (!request.resume).then_some((request.client_id, pairing_key)),
+        };
+    }
+
+    /// Open the body of a sealed message once a secure session is
+    /// established; `pair` messages and links without one pass through.
+    fn unseal(
+        &self,
+        encryption: &mut Encryption,
+        message: BleMessage,
+    ) -> std::result::Result<BleMessage, BleTransportError> {
+        let Encryption::Established {
+            opener,
+            unconfirmed,
+        } = encryption
+        else {
+            return Ok(message);
+        };
+        if message.kind == FrameKind::Pair {
+            return Ok(message);
+        }
+        let payload = opener
+            .open(message.kind, message.msg_id, &message.payload)
+            .map_err(|err| BleTransportError {
+                kind: kind_name(message.kind).to_string(),
+                msg_id: message.msg_id,
+                reason: err.to_string(),
+            })?;
+        // The central opened the session with the pairing code and has now
+        // proven it holds the keys: remember it for reconnects.
+        if let (Some((client_id, key)), Some(pairing)) = (unconfirmed.take(), &self.pairing) {
+            let _ = pairing.confirm(&client_id, key);
+        }
+        Ok(BleMessage { payload, ..message })
+    }
+
+    /// Answer a message that arrived before the secure session was opened.
+    fn reject_insecure(
+        &self,
+        message: BleMessage,
+        outbound: &Outbound,
+        in_flight: &mut JoinSet<()>,
+    ) {
+        let outbound = outbound.clone();
+        let err = RpcError::Unauthenticated("secure session required".to_string());
+        in_flight.spawn(async move {
+            match message.kind {
+                FrameKind::Request | FrameKind::Handshake => {
+                    outbound
+                        .send(message.msg_id, FrameKind::Response, &error_response(&err))
+                        .await;
+                }
+                FrameKind::Event => {
+                    let error = BleTransportError {
+                        kind: kind_name(FrameKind::Event).to_string(),
+                        msg_id: message.msg_id,
+                        reason: err.to_string(),
+                    };
+                    outbound.send_transport_error(&error).await;
+                }
+                FrameKind::Response | FrameKind::Pair => {}
+            }
+        });
+    }
+
+    /// Run the device side of the key exchange for a `pair` body.
+    fn pair(
+        &self,
+        body: &[u8],
+    ) -> std::result::Result<(SessionKeys, BlePairRequest, String), RpcError> {
+        let request: BlePairRequest =
+            serde_json::from_slice(body).map_err(|err| RpcError::Decode(err.to_string()))?;
+        let Some(pairing) = &self.pairing else {
+            return Err(RpcError::Internal(
+                "secure pairing is not enabled on this transport".to_string(),
+            ));
+        };
+        let unauthenticated = |err: secure::SecureError| RpcError::Unauthenticated(err.to_string());
+        let psk = pairing
+            .psk(&request.client_id, request.resume)
+            .map_err(unauthenticated)?;
+        let exchange = KeyExchange::new(&psk, &request.client_id);
+        let device_public_b64 = exchange.public_b64();
+        let keys = exchange
+            .finish(Role::Device, &request.public_key_b64, &request.client_id)
+            .map_err(unauthenticated)?;
+        Ok((keys, request, device_public_b64))
+    }
+
+    /// Session state of a link before any handshake.
+    fn new_session(&self) -> Session {
+        match self.authenticator {
+            Some(_) => Session::Pending,
+            None => Session::Open,
+        }
+    }
+
+    fn handshake(&self, body: &[u8]) -> std::result::Result<Principal, RpcError> {
+        let handshake: BleHandshake =
+            serde_json::from_slice(body).map_err(|err| RpcError::Decode(err.to_string()))?;
+        match &self.authenticator {
+            Some(authenticator) => authenticator
+                .authenticate(&handshake.token)
+                .map_err(|err| RpcError::Unauthenticated(err.to_string())),
+            None => Err(RpcError::Internal(
+                "authentication is not enabled on this transport".to_string(),
+            )),
+        }
     }
 }
 
+fn unauthenticated() -> RpcError {
+    RpcError::Unauthenticated("handshake required".to_string())
+}
+
 impl Transport for BleTransport {
     fn id(&self) -> TransportId {
-        "ble".to_string()
+        BLE_TRANSPORT_ID.to_string()
     }
 
     fn start(&self) -> Result<()> {
         Ok(())
     }
 }
+
+async fn recv_event(
+    subscription: &mut Option<EventSubscription>,
+) -> std::result::Result<TransportEvent, EventError> {
+    match subscription {
+        Some(subscription) => subscription.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Dispatch a decoded request and notify the response message(s).
+///
+/// Streaming requests produce one `more = true` response per chunk followed
+/// by a terminating response carrying the final status.
+async fn serve_request(
+    registry: Arc<dyn RpcRegistry>,
+    outbound: Outbound,
+    msg_id: u32,
+    request: std::result::Result<(RpcRequest, bool), RpcError>,
+) {
+    let (request, stream) = match request {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            let response = error_response(&err);
+            outbound.send(msg_id, FrameKind::Response, &response).await;
+            return;
+        }
+    };
+
+    if !stream {
+        let response = match registry.dispatch(request).await {
+            Ok(response) => BleRpcResponse {
+                payload_b64: encode_payload(&response.payload),
+                error: None,
+                more: false,
+            },
+            Err(err) => error_response(&err),
+        };
+        outbound.send(msg_id, FrameKind::Response, &response).await;
+        return;
+    }
+
+    let mut stream = registry.dispatch_stream(request);
+    while let Some(chunk) = stream.next().await {
+        let response = BleRpcResponse {
+            payload_b64: encode_payload(&chunk.payload),
+            error: None,
+            more: true,
+        };
+        if !outbound.send(msg_id, FrameKind::Response, &response).await {
+            return;
+        }
+    }
+    let response = match stream.finish().await {
+        Ok(()) => BleRpcResponse {
+            payload_b64: String::new(),
+            error: None,
+            more: false,
+        },
+        Err(err) => error_response(&err),
+    };
+    outbound.send(msg_id, FrameKind::Response, &response).await;
+}
+
+/// Decode a request body into the RPC request and its streaming flag.
+fn decode_request(
+    body: &[u8],
+    peer: Peer,
+    principal: Option<Principal>,
+) -> std::result::Result<(RpcRequest, bool), RpcError> {
+    let request: BleRpcRequest =
+        serde_json::from_slice(body).map_err(|err| RpcError::Decode(err.to_string()))?;
+    let payload = decode_payload(&request.payload_b64)?;
+    let mut rpc_request =
+        RpcRequest::new(request.service, request.method, payload, request.timeout_ms)
+            .with_transport(BLE_TRANSPORT_ID)
+            .with_peer(peer);
+    if let Some(request_id) = request.request_id {
+        rpc_request = rpc_request.with_request_id(request_id);
+    }
+    if let Some(principal) = principal {
+        rpc_request = rpc_request.with_principal(principal);
+    }
+    rpc_request.context.metadata.extend(request.metadata);
+    Ok((rpc_request, request.stream))
+}
+
+fn error_response(err: &RpcError) -> BleRpcResponse {
+    BleRpcResponse {
+        payload_b64: String::new(),
+        error: Some(map_rpc_error(err)),
+        more: false,
+    }
+}
+
+fn publish_client_event(
+    events: &ClientEventPublisher,
+    principal: Option<Principal>,
+    body: &[u8],
+) -> std::result::Result<(), String> {
+    let event: BleEvent = serde_json::from_slice(body).map_err(|err| err.to_string())?;
+    let payload = decode_payload(&event.payload_b64).map_err(|err| err.to_string())?;
+    events
+        .publish_as(
+            principal.as_ref(),
+            TransportEvent {
+                topic: event.topic,
+                payload,
+            },
+        )
+        .map_err(|err| err.to_string())
+}
+
+/// Sends messages to the central, sealing them once a secure session is
+/// established.
+///
+/// The sealer lock is held until every frame of a message is notified so
+/// messages reach the central in counter order. Responses reuse the
+/// request's `msg_id`; every `event` message, bus event or transport error,
+/// takes the next id from one per-link counter so no two share
+/// `(msg_id, kind)`.
+#[derive(Clone)]
+struct Outbound {
+    notifier: BleNotifier,
+    mtu: usize,
+    sealer: Arc<Mutex<Option<Sealer>>>,
+    next_event_id: Arc<AtomicU32>,
+}
+
+impl Outbound {
+    fn new(notifier: BleNotifier, mtu: usize) -> Self {
+        Self {
+            notifier,
+            mtu,
+            sealer: Arc::new(Mutex::new(None)),
+            next_event_id: Arc::new(AtomicU32::new(1)),
+        }
+    }
+
+    /// Notify an `event` message under a fresh message id.
+    async fn send_event(&self, body: &BleEvent) -> bool {
+        let msg_id = self.next_event_id.fetch_add(1, Ordering::Relaxed);
+        self.send(msg_id, FrameKind::Event, body).await
+    }
+
+    /// Serialize and notify a message; returns `false` once the central has
+    /// disconnected.
+    async fn send<T: serde::Serialize>(&self, msg_id: u32, kind: FrameKind, body: &T) -> bool {
+        let bytes = serde_json::to_vec(body).expect("ble message serializes");
+        let mut sealer = self.sealer.lock().await;
+        let bytes = match sealer.as_mut() {
+            Some(sealer) => sealer.seal(kind, msg_id, &bytes),
+            None => bytes,
+        };
+        self.notify(msg_id, kind, &bytes).await
+    }
+
+    /// Send the plaintext answer to a `pair` message, then seal everything
+    /// after it with `sealer`.
+    async fn establish(&self, msg_id: u32, response: &BleRpcResponse, sealer: Sealer) -> bool {
+        let bytes = serde_json::to_vec(response).expect("ble message serializes");
+        let mut current = self.sealer.lock().await;
+        let sent = self.notify(msg_id, FrameKind::Response, &bytes).await;
+        *current = Some(sealer);
+        sent
+    }
+
+    /// Report a dropped client message; the client's `msg_id` travels in the
+    /// body, not the frame header.
+    async fn send_transport_error(&self, error: &BleTransportError) {
+        let body = BleEvent {
+            topic: TRANSPORT_ERROR_TOPIC.to_string(),
+            payload_b64: encode_payload(&serde_json::to_vec(error).expect("error serializes")),
+        };
+        self.send_event(&body).await;
+    }
+
+    async fn notify(&self, msg_id: u32, kind: FrameKind, bytes: &[u8]) -> bool {
+        let characteristic = match kind {
+            FrameKind::Event => Characteristic::EventsTx,
+            FrameKind::Request | FrameKind::Response | FrameKind::Handshake | FrameKind::Pair => {
+                Characteristic::RpcTx
+            }
+        };
+        for frame in split_message(msg_id, kind, bytes, self.mtu) {
+            if !self.notifier.notify(characteristic, frame.encode()).await {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn kind_name(kind: FrameKind) -> &'static str {
+    match kind {
+        FrameKind::Request => "request",
+        FrameKind::Response => "response",
+        FrameKind::Event => "event",
+        FrameKind::Handshake => "handshake",
+        FrameKind::Pair => "pair",
+    }
+}
+
+fn decode_payload(encoded: &str) -> std::result::Result<Vec<u8>, RpcError> {
+    general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|err| RpcError::Decode(err.to_string()))
+}
+
+fn encode_payload(payload: &[u8]) -> String {
+    general_purpose::STANDARD.encode(payload)
+}
+
+fn map_rpc_error(err: &RpcError) -> BleRpcError {
+    BleRpcError {
+        code: err.code().to_string(),
+        message: err.to_string(),
+        retry_after_ms: err.retry_after().map(|delay| delay.as_millis() as u64),
+        details: err.details(),
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// RPC request body carried in `request` frames on `rpc_rx`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleRpcRequest {
+    pub service: String,
+    pub method: String,
+    /// Base64-encoded payload (empty string represents an empty payload).
+    pub payload_b64: String,
+    pub timeout_ms: u64,
+    /// Request a server-streaming response.
+    #[serde(default)]
+    pub stream: bool,
+    /// Client-chosen request id, usable with `rpc.cancel`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Free-form metadata exposed to handlers through the request context.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// RPC response body carried in `response` frames on `rpc_tx`.
+///
+/// Streaming calls send one response per chunk with `more = true`, followed
+/// by a terminating response with `more = false` and the final status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleRpcResponse {
+    pub payload_b64: String,
+    pub error: Option<BleRpcError>,
+    #[serde(default)]
+    pub more: bool,
+}
+
+/// Error payload returned for RPC-level failures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleRpcError {
+    pub code: String,
+    pub message: String,
+    /// Suggested delay before retrying, set with `rate_limited`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    /// Structured fields of the error, e.g. `field` for `invalid_argument`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+/// Event body carried in `event` frames, written by clients to `rpc_rx` or
+/// notified by the device on `events_tx`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleEvent {
+    pub topic: String,
+    pub payload_b64: String,
+}
+
+/// Body of a `handshake` frame written to `rpc_rx`.
+///
+/// The device answers with a `response` frame with the same message id whose
+/// payload is the authenticated principal id, or an `unauthenticated` error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleHandshake {
+    pub token: String,
+}
+
+/// Body of a `pair` frame written to `rpc_rx`, opening an encrypted session.
+///
+/// A new central pairs with the device's pairing code; a paired central sets
+/// `resume` and uses the pairing key from its first session instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlePairRequest {
+    pub client_id: String,
+    /// Ephemeral CPace public key (compressed Ristretto255 point).
+    pub public_key_b64: String,
+    #[serde(default)]
+    pub resume: bool,
+}
+
+/// Payload of the response to a `pair` frame.
+///
+/// `confirm_b64` is an empty body sealed with the new session keys; a central
+/// that cannot open it does not share the device's secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlePairAccept {
+    /// Ephemeral CPace public key of the device.
+    pub public_key_b64: String,
+    pub confirm_b64: String,
+}
+
+/// Payload of the `transport/error` event emitted when a client message is dropped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleTransportError {
+    pub kind: String,
+    /// `msg_id` of the dropped client message; `0` when the frame could not
+    /// be decoded. The event itself carries a device-assigned `msg_id`.
+    pub msg_id: u32,
+    pub reason: String,
+}
+
+/// Topic used to report dropped or rejected client messages.
+pub const TRANSPORT_ERROR_TOPIC: &str = "transport/error";
@@ -0,0 +1,490 @@
+//! Application-layer encryption for BLE links.
+//!
+//! A central opens a secure session with a `pair` message carrying an
+//! ephemeral key for a CPace exchange over Ristretto255. The group generator
+//! is derived from a pre-shared secret: the device's pairing code on first
+//! contact, or the pairing key derived during that first session when a
+//! known central reconnects. Both sides derive per-direction
+//! ChaCha20-Poly1305 keys with HKDF-SHA256 from the shared point. A party
+//! without the right secret derives different keys, and nothing sent on the
+//! link lets it test more than the one guess it made per exchange, so the
+//! pairing code cannot be brute-forced offline.
+//!
+//! Once established, every message body is sealed as
+//! `counter (8 bytes, big-endian) || ciphertext || tag`, with the frame kind
+//! and message id as associated data. Counters start at 1 and must strictly
+//! increase, which rejects replayed and reordered messages.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::IsIdentity,
+};
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::ble::framing::FrameKind;
+
+/// Long-term secret shared with a paired central, used to resume sessions.
+pub type PairingKey = [u8; 32];
+
+const KDF_INFO: &[u8] = b"service-ble-session-v2";
+const GENERATOR_DSI: &[u8] = b"service-ble-cpace-ristretto255-v1";
+/// Pairings with the code that may go unconfirmed, across all links, before
+/// pairing with the code is locked.
+const MAX_PAIRING_ATTEMPTS: u32 = 5;
+const DEFAULT_PAIRING_LOCKOUT: Duration = Duration::from_secs(60);
+const COUNTER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecureError {
+    /// A public key or sealed body is malformed.
+    Malformed,
+    /// Authentication failed: wrong keys or tampered data.
+    Decrypt,
+    /// The message counter did not increase.
+    Replay,
+    /// A resume was requested for a central that is not paired.
+    UnknownClient(String),
+    /// Too many pairings with the code went unconfirmed; retry later.
+    Locked,
+}
+
+impl Display for SecureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecureError::Malformed => write!(f, "malformed secure message"),
+            SecureError::Decrypt => write!(f, "message failed authentication"),
+            SecureError::Replay => write!(f, "replayed or reordered message"),
+            SecureError::UnknownClient(id) => write!(f, "central {id} is not paired"),
+            SecureError::Locked => write!(f, "too many pairing attempts, try again later"),
+        }
+    }
+}
+
+impl std::error::Error for SecureError {}
+
+/// Persistence for pairing keys, keyed by the central's client id.
+pub trait PairingStore: Send + Sync {
+    fn get(&self, client_id: &str) -> Option<PairingKey>;
+    fn insert(&self, client_id: &str, key: PairingKey) -> io::Result<()>;
+    fn remove(&self, client_id: &str) -> io::Result<bool>;
+}
+
+/// Pairing keys kept in memory; pairings are lost on restart.
+#[derive(Default)]
+pub struct MemoryPairingStore {
+    keys: Mutex<HashMap<String, PairingKey>>,
+}
+
+impl MemoryPairingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PairingStore for MemoryPairingStore {
+    fn get(&self, client_id: &str) -> Option<PairingKey> {
+        self.keys
+            .lock()
+            .expect("pairing store lock")
+            .get(client_id)
+            .copied()
+    }
+
+    fn insert(&self, client_id: &str, key: PairingKey) -> io::Result<()> {
+        self.keys
+            .lock()
+            .expect("pairing store lock")
+            .insert(client_id.to_string(), key);
+        Ok(())
+    }
+
+    fn remove(&self, client_id: &str) -> io::Result<bool> {
+        Ok(self
+            .keys
+            .lock()
+            .expect("pairing store lock")
+            .remove(client_id)
+            .is_some())
+    }
+}
+
+/// Pairing keys persisted as a JSON object of base64 keys by client id.
+///
+/// The file is rewritten on every change, created readable by the service's
+/// user only (`0600`) and synced before it replaces the previous one.
+pub struct FilePairingStore {
+    path: PathBuf,
+    keys: Mutex<BTreeMap<String, PairingKey>>,
+}
+
+impl FilePairingStore {
+    /// Open the store at `path`; a missing file is an empty store.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let keys = match fs::read(&path) {
+            Ok(bytes) => {
+                let encoded: BTreeMap<String, String> = serde_json::from_slice(&bytes)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                encoded
+                    .into_iter()
+                    .map(|(client_id, key)| {
+                        decode_key(&key).map(|key| (client_id, key)).ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidData, "invalid pairing key")
+                        })
+                    })
+                    .collect::<io::Result<_>>()?
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            path,
+            keys: Mutex::new(keys),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&self, keys: &BTreeMap<String, PairingKey>) -> io::Result<()> {
+        let encoded: BTreeMap<&str, String> = keys
+            .iter()
+            .map(|(client_id, key)| (client_id.as_str(), general_purpose::STANDARD.encode(key)))
+            .collect();
+        let json = serde_json::to_vec_pretty(&encoded).expect("pairing keys serialize");
+        let staging = self.path.with_extension("tmp");
+        // A staging file left by a crash may have other permissions; start
+        // afresh so `mode` applies.
+        match fs::remove_file(&staging) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&staging)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        fs::rename(staging, &self.path)
+    }
+}
+
+impl PairingStore for FilePairingStore {
+    fn get(&self, client_id: &str) -> Option<PairingKey> {
+        self.keys
+            .lock()
+            .expect("pairing store lock")
+            .get(client_id)
+            .copied()
+    }
+
+    fn insert(&self, client_id: &str, key: PairingKey) -> io::Result<()> {
+        let mut keys = self.keys.lock().expect("pairing store lock");
+        keys.insert(client_id.to_string(), key);
+        self.save(&keys)
+    }
+
+    fn remove(&self, client_id: &str) -> io::Result<bool> {
+        let mut keys = self.keys.lock().expect("pairing store lock");
+        if keys.remove(client_id).is_none() {
+            return Ok(false);
+        }
+        self.save(&keys).map(|()| true)
+    }
+}
+
+/// Device-side pairing settings: the code a new central must know, and where
+/// pairing keys of known centrals are kept.
+///
+/// Clones share one count of pairing attempts: after five pairings with the
+/// code go unconfirmed, on any link, pairing with the code is refused until
+/// the lockout passes. Resuming with a pairing key is not limited.
+#[derive(Clone)]
+pub struct SecurePairing {
+    code: String,
+    store: Arc<dyn PairingStore>,
+    lockout: Duration,
+    attempts: Arc<Mutex<PairingAttempts>>,
+}
+
+/// Pairings with the code not yet confirmed by a sealed message.
+#[derive(Default)]
+struct PairingAttempts {
+    unconfirmed: u32,
+    locked_until: Option<Instant>,
+}
+
+impl SecurePairing {
+    pub fn new(code: impl Into<String>, store: Arc<dyn PairingStore>) -> Self {
+        Self {
+            code: code.into(),
+            store,
+            lockout: DEFAULT_PAIRING_LOCKOUT,
+            attempts: Arc::default(),
+        }
+    }
+
+    /// Use a random six-digit code; show it to the user with [`Self::code`].
+    pub fn with_random_code(store: Arc<dyn PairingStore>) -> Self {
+        let code = format!("{:06}", OsRng.next_u32() % 1_000_000);
+        Self::new(code, store)
+    }
+
+    /// How long pairing with the code stays locked after too many
+    /// unconfirmed attempts. Defaults to one minute.
+    pub fn with_lockout(mut self, lockout: Duration) -> Self {
+        self.lockout = lockout;
+        self
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn store(&self) -> &Arc<dyn PairingStore> {
+        &self.store
+    }
+
+    /// Secret the key exchange for `client_id` is bound to: the pairing
+    /// code, or the stored pairing key when resuming.
+    ///
+    /// Each request for the code counts as a pairing attempt until
+    /// [`Self::confirm`] is called for it.
+    pub(crate) fn psk(&self, client_id: &str, resume: bool) -> Result<Vec<u8>, SecureError> {
+        if resume {
+            return self
+                .store
+                .get(client_id)
+                .map(|key| key.to_vec())
+                .ok_or_else(|| SecureError::UnknownClient(client_id.to_string()));
+        }
+        let mut attempts = self.attempts.lock().expect("pairing attempts lock");
+        let now = Instant::now();
+        match attempts.locked_until {
+            Some(until) if now < until => return Err(SecureError::Locked),
+            Some(_) => *attempts = PairingAttempts::default(),
+            None => {}
+        }
+        attempts.unconfirmed += 1;
+        if attempts.unconfirmed >= MAX_PAIRING_ATTEMPTS {
+            attempts.locked_until = Some(now + self.lockout);
+        }
+        Ok(self.code.as_bytes().to_vec())
+    }
+
+    /// Record that a central paired with the code proved it knew it, and
+    /// keep its pairing key.
+    pub(crate) fn confirm(&self, client_id: &str, key: PairingKey) -> io::Result<()> {
+        *self.attempts.lock().expect("pairing attempts lock") = PairingAttempts::default();
+        self.store.insert(client_id, key)
+    }
+}
+
+/// Which end of the link derives the keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    Central,
+    Device,
+}
+
+/// Ephemeral CPace key pair for one session setup, on a generator derived
+/// from the pre-shared secret.
+pub(crate) struct KeyExchange {
+    secret: Scalar,
+    public: CompressedRistretto,
+}
+
+impl KeyExchange {
+    pub(crate) fn new(psk: &[u8], client_id: &str) -> Self {
+        let mut wide = [0u8; 64];
+        OsRng.fill_bytes(&mut wide);
+        let secret = Scalar::from_bytes_mod_order_wide(&wide);
+        let public = (secret * generator(psk, client_id)).compress();
+        Self { secret, public }
+    }
+
+    pub(crate) fn public_b64(&self) -> String {
+        general_purpose::STANDARD.encode(self.public.as_bytes())
+    }
+
+    /// Combine with the peer's public key into the session keys.
+    pub(crate) fn finish(
+        self,
+        role: Role,
+        peer_public_b64: &str,
+        client_id: &str,
+    ) -> Result<SessionKeys, SecureError> {
+        let peer_public =
+            CompressedRistretto(decode_key(peer_public_b64).ok_or(SecureError::Malformed)?);
+        let peer_point = peer_public
+            .decompress()
+            .filter(|point| !point.is_identity())
+            .ok_or(SecureError::Malformed)?;
+        let shared = (self.secret * peer_point).compress();
+        let (central_public, device_public) = match role {
+            Role::Central => (self.public, peer_public),
+            Role::Device => (peer_public, self.public),
+        };
+
+        let mut info = KDF_INFO.to_vec();
+        info.extend_from_slice(central_public.as_bytes());
+        info.extend_from_slice(device_public.as_bytes());
+        info.extend_from_slice(client_id.as_bytes());
+        let mut okm = [0u8; 96];
+        Hkdf::<Sha256>::new(None, shared.as_bytes())
+            .expand(&info, &mut okm)
+            .expect("96 bytes is a valid HKDF-SHA256 output length");
+
+        let central_to_device = Key::clone_from_slice(&okm[..32]);
+        let device_to_central = Key::clone_from_slice(&okm[32..64]);
+        let mut pairing_key = [0u8; 32];
+        pairing_key.copy_from_slice(&okm[64..]);
+        let (seal_key, open_key) = match role {
+            Role::Central => (central_to_device, device_to_central),
+            Role::Device => (device_to_central, central_to_device),
+        };
+        Ok(SessionKeys {
+            sealer: Sealer::new(&seal_key),
+            opener: Opener::new(&open_key),
+            pairing_key,
+        })
+    }
+}
+
+/// Group generator bound to the secret and the central's client id; public
+/// keys on it reveal nothing about the secret.
+fn generator(psk: &[u8], client_id: &str) -> RistrettoPoint {
+    let mut hash = Sha512::new();
+    for part in [GENERATOR_DSI, psk, client_id.as_bytes()] {
+        hash.update((part.len() as u64).to_be_bytes());
+        hash.update(part);
+    }
+    RistrettoPoint::from_uniform_bytes(&hash.finalize().into())
+}
+
+/// Keys of an established session.
+pub(crate) struct SessionKeys {
+    pub(crate) sealer: Sealer,
+    pub(crate) opener: Opener,
+    /// Secret to store for resuming after a session set up with the code.
+    pub(crate) pairing_key: PairingKey,
+}
+
+/// Encrypts outgoing message bodies.
+pub(crate) struct Sealer {
+    cipher: ChaCha20Poly1305,
+    counter: u64,
+}
+
+impl Sealer {
+    fn new(key: &Key) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(key),
+            counter: 0,
+        }
+    }
+
+    pub(crate) fn seal(&mut self, kind: FrameKind, msg_id: u32, plaintext: &[u8]) -> Vec<u8> {
+        self.counter += 1;
+        let aad = associated_data(kind, msg_id);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce(self.counter),
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .expect("ChaCha20-Poly1305 encryption does not fail");
+        let mut sealed = Vec::with_capacity(COUNTER_LEN + ciphertext.len());
+        sealed.extend_from_slice(&self.counter.to_be_bytes());
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+}
+
+/// Decrypts and authenticates incoming message bodies.
+pub(crate) struct Opener {
+    cipher: ChaCha20Poly1305,
+    last: u64,
+}
+
+impl Opener {
+    fn new(key: &Key) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(key),
+            last: 0,
+        }
+    }
+
+    pub(crate) fn open(
+        &mut self,
+        kind: FrameKind,
+        msg_id: u32,
+        sealed: &[u8],
+    ) -> Result<Vec<u8>, SecureError> {
+        if sealed.len() < COUNTER_LEN {
+            return Err(SecureError::Malformed);
+        }
+        let (counter, ciphertext) = sealed.split_at(COUNTER_LEN);
+        let counter = u64::from_be_bytes(counter.try_into().expect("counter is 8 bytes"));
+        if counter <= self.last {
+            return Err(SecureError::Replay);
+        }
+        let aad = associated_data(kind, msg_id);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                &nonce(counter),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| SecureError::Decrypt)?;
+        self.last = counter;
+        Ok(plaintext)
+    }
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::from(nonce)
+}
+
+fn associated_data(kind: FrameKind, msg_id: u32) -> [u8; 5] {
+    let mut aad = [0u8; 5];
+    aad[0] = kind.as_u8();
+    aad[1..].copy_from_slice(&msg_id.to_be_bytes());
+    aad
+}
+
+fn decode_key(encoded: &str) -> Option<[u8; 32]> {
+    general_purpose::STANDARD
+        .decode(encoded)
+        .ok()?
+        .try_into()
+        .ok()
+}
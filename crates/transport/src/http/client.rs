@@ -1,8 +1,171 @@
-/// Placeholder HTTP client for transport interactions.
-pub struct HttpClient;
+use std::{fmt::Display, net::SocketAddr};
+
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use axum::{
+    body::Bytes,
+    http::{header, Method, Request, StatusCode},
+};
+use http_body_util::{BodyExt as _, Full};
+use hyper_util::rt::TokioIo;
+use serde::de::DeserializeOwned;
+use service_core::router::MethodDescriptor;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::http::protocol::{HttpRpcRequest, HttpRpcResponse};
+
+/// Where an [`HttpClient`] connects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpEndpoint {
+    Tcp(SocketAddr),
+    /// Socket file served by
+    /// [`HttpServerTransport::with_unix_socket`](super::HttpServerTransport::with_unix_socket).
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+#[derive(Debug)]
+pub enum HttpClientError {
+    Io(std::io::Error),
+    Http(String),
+    /// The server answered with a body that is not a protocol message, such
+    /// as the `413` of an oversized request.
+    Status(StatusCode, String),
+    Decode(String),
+}
+
+impl Display for HttpClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpClientError::Io(err) => write!(f, "connection failed: {err}"),
+            HttpClientError::Http(msg) => write!(f, "http error: {msg}"),
+            HttpClientError::Status(status, body) => write!(f, "unexpected {status}: {body}"),
+            HttpClientError::Decode(msg) => write!(f, "failed to decode response: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for HttpClientError {}
+
+impl From<std::io::Error> for HttpClientError {
+    fn from(err: std::io::Error) -> Self {
+        HttpClientError::Io(err)
+    }
+}
+
+impl From<hyper::Error> for HttpClientError {
+    fn from(err: hyper::Error) -> Self {
+        HttpClientError::Http(err.to_string())
+    }
+}
+
+/// Client speaking the `/rpc` envelope protocol over TCP or a Unix socket.
+///
+/// Every call opens its own HTTP/1.1 connection.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    endpoint: HttpEndpoint,
+    bearer_token: Option<String>,
+}
 
 impl HttpClient {
-    pub fn new() -> Self {
-        Self
+    pub fn new(endpoint: HttpEndpoint) -> Self {
+        Self {
+            endpoint,
+            bearer_token: None,
+        }
+    }
+
+    pub fn tcp(addr: SocketAddr) -> Self {
+        Self::new(HttpEndpoint::Tcp(addr))
+    }
+
+    #[cfg(unix)]
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        Self::new(HttpEndpoint::Unix(path.into()))
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request.
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    pub fn endpoint(&self) -> &HttpEndpoint {
+        &self.endpoint
     }
+
+    /// Issue an RPC through `POST /rpc` and return its response envelope.
+    ///
+    /// RPC failures come back in [`HttpRpcResponse::error`] whatever status
+    /// the server chose for them.
+    pub async fn call(&self, request: &HttpRpcRequest) -> Result<HttpRpcResponse, HttpClientError> {
+        let body =
+            serde_json::to_vec(request).map_err(|err| HttpClientError::Decode(err.to_string()))?;
+        self.send(Method::POST, "/rpc", body).await
+    }
+
+    /// List the registered methods through `GET /rpc/methods`.
+    pub async fn methods(&self) -> Result<Vec<MethodDescriptor>, HttpClientError> {
+        self.send(Method::GET, "/rpc/methods", Vec::new()).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Vec<u8>,
+    ) -> Result<T, HttpClientError> {
+        let host = match &self.endpoint {
+            HttpEndpoint::Tcp(addr) => addr.to_string(),
+            #[cfg(unix)]
+            HttpEndpoint::Unix(_) => "localhost".to_string(),
+        };
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::HOST, host)
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = &self.bearer_token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = builder
+            .body(Full::new(Bytes::from(body)))
+            .map_err(|err| HttpClientError::Http(err.to_string()))?;
+
+        let (status, body) = match &self.endpoint {
+            HttpEndpoint::Tcp(addr) => {
+                exchange(tokio::net::TcpStream::connect(addr).await?, request).await?
+            }
+            #[cfg(unix)]
+            HttpEndpoint::Unix(path) => {
+                exchange(tokio::net::UnixStream::connect(path).await?, request).await?
+            }
+        };
+        serde_json::from_slice(&body).map_err(|err| {
+            if status.is_success() {
+                HttpClientError::Decode(err.to_string())
+            } else {
+                HttpClientError::Status(status, String::from_utf8_lossy(&body).into_owned())
+            }
+        })
+    }
+}
+
+/// Send one request over a fresh connection and read the whole response.
+async fn exchange<S>(
+    stream: S,
+    request: Request<Full<Bytes>>,
+) -> Result<(StatusCode, Bytes), HttpClientError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(connection);
+    let response = sender.send_request(request).await?;
+    let status = response.status();
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok((status, body))
 }
@@ -0,0 +1,260 @@
+//! JSON-RPC 2.0 over `POST /jsonrpc`, dispatched through the same registry
+//! as `/rpc`.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use futures_util::future::join_all;
+use serde_json::Value;
+use service_core::{
+    router::{Peer, Principal, RpcError, RpcRegistry, RpcRequest, IDEMPOTENCY_KEY_METADATA},
+    signing::{NONCE_METADATA, SIGNATURE_METADATA, TIMESTAMP_METADATA},
+};
+
+use crate::http::{
+    map_rpc_error,
+    protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse, JSONRPC_VERSION},
+    with_http_context, CallerPeer, HttpServerState, PeerIdentity,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// Start of the implementation-defined server error range.
+const SERVER_ERROR: i64 = -32000;
+/// Headers describing a single request; every item of a batch would share
+/// them, so batches carrying any of them are rejected.
+const SINGLE_REQUEST_HEADERS: [&str; 4] = [
+    IDEMPOTENCY_KEY_METADATA,
+    SIGNATURE_METADATA,
+    NONCE_METADATA,
+    TIMESTAMP_METADATA,
+];
+
+pub(super) async fn handle_jsonrpc(
+    State(state): State<HttpServerState>,
+    CallerPeer(peer): CallerPeer,
+    identity: Option<Extension<PeerIdentity>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let principal = match state.authenticate(identity, &headers) {
+        Ok(principal) => principal,
+        Err(err) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(failure(Value::Null, rpc_error(&err))),
+            )
+                .into_response()
+        }
+    };
+    let run = |item: Value| {
+        let state = state.clone();
+        let peer = peer.clone();
+        let principal = principal.clone();
+        let headers = &headers;
+        async move { call(&state, peer, principal, headers, item).await }
+    };
+
+    let body: Value = match serde_json::from_slice(&body) {
+        Ok(body) => body,
+        Err(err) => {
+            let error = error(PARSE_ERROR, format!("parse error: {err}"));
+            return Json(failure(Value::Null, error)).into_response();
+        }
+    };
+    match body {
+        Value::Array(items) if items.is_empty() => {
+            let error = error(INVALID_REQUEST, "empty batch");
+            Json(failure(Value::Null, error)).into_response()
+        }
+        Value::Array(_)
+            if SINGLE_REQUEST_HEADERS
+                .iter()
+                .any(|h| headers.contains_key(*h)) =>
+        {
+            let error = error(
+                INVALID_REQUEST,
+                "idempotency and signature headers apply to a single request, not a batch",
+            );
+            Json(failure(Value::Null, error)).into_response()
+        }
+        Value::Array(items) => {
+            let responses: Vec<_> = join_all(items.into_iter().map(run))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        item => match run(item).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Run one request of a (possibly batched) body; `None` for notifications.
+async fn call(
+    state: &HttpServerState,
+    peer: Option<Peer>,
+    principal: Option<Principal>,
+    headers: &HeaderMap,
+    item: Value,
+) -> Option<JsonRpcResponse> {
+    let request: JsonRpcRequest = match serde_json::from_value(item) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = error(INVALID_REQUEST, format!("invalid request: {err}"));
+            return Some(failure(Value::Null, error));
+        }
+    };
+    let id = request.id;
+    let result = decode(
+        state.registry.as_ref(),
+        request.jsonrpc,
+        &request.method,
+        request.params,
+    );
+    let result = match result {
+        Ok((service, method, payload)) => {
+            let rpc_request = with_http_context(
+                RpcRequest::new(service, method, payload, 0),
+                peer,
+                principal,
+                headers,
+            );
+            state
+                .registry
+                .dispatch(rpc_request)
+                .await
+                .map_err(|err| rpc_error(&err))
+                .and_then(|response| encode(&response.payload))
+        }
+        Err(error) => Err(error),
+    };
+
+    let id = id?;
+    Some(match result {
+        Ok(result) => JsonRpcResponse {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            result: Some(result),
+            error: None,
+            id,
+        },
+        Err(error) => failure(id, error),
+    })
+}
+
+/// Resolve `service.method` and serialize `params` into the RPC payload.
+fn decode(
+    registry: &dyn RpcRegistry,
+    version: String,
+    name: &str,
+    params: Option<Value>,
+) -> Result<(String, String, Vec<u8>), JsonRpcError> {
+    if version != JSONRPC_VERSION {
+        return Err(error(
+            INVALID_REQUEST,
+            format!("unsupported jsonrpc version {version:?}"),
+        ));
+    }
+    let Some((service, method)) = split_method(registry, name) else {
+        return Err(error(
+            METHOD_NOT_FOUND,
+            format!("method {name:?} is not of the form service.method"),
+        ));
+    };
+    let payload = match params {
+        None => Vec::new(),
+        Some(params @ (Value::Object(_) | Value::Array(_))) => {
+            serde_json::to_vec(&params).expect("JSON value serializes")
+        }
+        Some(_) => {
+            return Err(error(
+                INVALID_REQUEST,
+                "params must be an object or an array",
+            ))
+        }
+    };
+    Ok((service, method, payload))
+}
+
+/// Service and method of `name`. Service and method names may themselves
+/// contain dots, so the whole name is matched against the registered
+/// methods; names matching none are split at the first dot and left to the
+/// router to reject.
+fn split_method(registry: &dyn RpcRegistry, name: &str) -> Option<(String, String)> {
+    let registered = registry.methods().into_iter().find(|descriptor| {
+        name.strip_prefix(descriptor.service.as_str())
+            .and_then(|rest| rest.strip_prefix('.'))
+            == Some(descriptor.method.as_str())
+    });
+    match registered {
+        Some(descriptor) => Some((descriptor.service, descriptor.method)),
+        None => name
+            .split_once('.')
+            .map(|(service, method)| (service.to_string(), method.to_string())),
+    }
+}
+
+/// The response payload as the JSON `result`; an empty payload is `null`.
+fn encode(payload: &[u8]) -> Result<Value, JsonRpcError> {
+    if payload.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(payload)
+        .map_err(|err| error(INTERNAL_ERROR, format!("response is not JSON: {err}")))
+}
+
+/// Map an RPC error to a JSON-RPC error object; `data` keeps the `/rpc`
+/// error code.
+fn rpc_error(err: &RpcError) -> JsonRpcError {
+    let code = match err {
+        RpcError::UnknownMethod => METHOD_NOT_FOUND,
+        RpcError::Decode(_) | RpcError::InvalidArgument { .. } => INVALID_PARAMS,
+        RpcError::Internal(_) => INTERNAL_ERROR,
+        RpcError::Unauthenticated(_) => SERVER_ERROR - 1,
+        RpcError::PermissionDenied(_) => SERVER_ERROR - 2,
+        RpcError::Overloaded => SERVER_ERROR - 3,
+        RpcError::RateLimited { .. } => SERVER_ERROR - 4,
+        RpcError::Cancelled => SERVER_ERROR - 5,
+        RpcError::Conflict(_) => SERVER_ERROR - 6,
+        RpcError::NotFound { .. } => SERVER_ERROR - 7,
+        RpcError::Timeout => SERVER_ERROR - 8,
+        RpcError::Unavailable(_) => SERVER_ERROR - 9,
+    };
+    JsonRpcError {
+        code,
+        message: err.to_string(),
+        data: Some(map_rpc_error(err)),
+    }
+}
+
+fn error(code: i64, message: impl Into<String>) -> JsonRpcError {
+    JsonRpcError {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+fn failure(id: Value, error: JsonRpcError) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: JSONRPC_VERSION.to_string(),
+        result: None,
+        error: Some(error),
+        id,
+    }
+}
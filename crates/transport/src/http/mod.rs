@@ -1,55 +1,284 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{
-    extract::State,
-    http::StatusCode,
+    async_trait,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, DefaultBodyLimit, FromRequestParts, State,
+    },
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
     response::{IntoResponse, Response},
-    routing::post,
-    Json, Router,
+    routing::{get, post},
+    Extension, Json, Router,
 };
 use base64::{engine::general_purpose, Engine as _};
+use hyper::{body::Incoming, service::service_fn, Request};
+use hyper_util::rt::TokioIo;
 use service_core::{
-    router::{RpcError, RpcRegistry, RpcRequest},
-    Transport,
+    acl::AccessPolicy,
+    auth::{bearer_token, AuthError, DynAuthenticator},
+    config::{CorsConfig, UnixSocketConfig, DEFAULT_HTTP_MAX_BODY_BYTES},
+    event::{ClientEventPublisher, DynEventBus, EventError, TopicAllowlist, TransportEvent},
+    router::{
+        Peer, Principal, RpcError, RpcRegistry, RpcRequest, BUILTIN_SERVICE,
+        IDEMPOTENCY_KEY_METADATA, METHOD_LIST,
+    },
+    signing::{NONCE_METADATA, SIGNATURE_METADATA, TIMESTAMP_METADATA},
+    CancellationToken, Transport,
+};
+use tokio::{net::TcpListener, task::JoinSet};
+use tower::ServiceExt as _;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+    limit::RequestBodyLimitLayer,
 };
 
-use crate::http::protocol::{HttpRpcError, HttpRpcRequest, HttpRpcResponse};
+use crate::http::protocol::{
+    HttpEventMessage, HttpRpcError, HttpRpcRequest, HttpRpcResponse, HttpStreamMessage,
+};
 
 pub mod client;
+mod jsonrpc;
 pub mod protocol;
+mod rest;
+#[cfg(feature = "transport_http_tls")]
+pub mod tls;
+#[cfg(unix)]
+mod unix;
+
+/// Transport id reported by [`HttpServerTransport`] and set on request contexts.
+pub const HTTP_TRANSPORT_ID: &str = "http";
+/// Header carrying a client-chosen request id, usable with `rpc.cancel`.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Header carrying the call timeout in milliseconds on `POST /rpc/{service}/{method}`.
+pub const TIMEOUT_HEADER: &str = "x-timeout-ms";
 
 #[derive(Clone)]
 pub struct HttpServerTransport {
     registry: Arc<dyn RpcRegistry>,
+    events: Option<ClientEventPublisher>,
+    authenticator: Option<DynAuthenticator>,
+    policy: Option<Arc<AccessPolicy>>,
+    error_status: bool,
+    max_body_bytes: usize,
+    compression: bool,
+    cors: Option<CorsConfig>,
+    unix: Option<UnixSocketConfig>,
+    #[cfg(feature = "transport_http_tls")]
+    tls: Option<tls::HttpTls>,
 }
 
 impl HttpServerTransport {
     pub fn new(registry: Arc<dyn RpcRegistry>) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            events: None,
+            authenticator: None,
+            policy: None,
+            error_status: false,
+            max_body_bytes: DEFAULT_HTTP_MAX_BODY_BYTES,
+            compression: false,
+            cors: None,
+            unix: None,
+            #[cfg(feature = "transport_http_tls")]
+            tls: None,
+        }
+    }
+
+    /// Serve HTTPS instead of plain HTTP.
+    ///
+    /// When the TLS config names a client CA, the principal of a request is
+    /// taken from the verified client certificate and no bearer token is
+    /// needed; see [`with_authenticator`](Self::with_authenticator).
+    #[cfg(feature = "transport_http_tls")]
+    pub fn with_tls(mut self, tls: tls::HttpTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Apply the topic rules of `policy` to `GET /events`: callers only
+    /// receive and publish events on topics it allows them. Method rules are
+    /// enforced by the router.
+    pub fn with_access_policy(mut self, policy: Arc<AccessPolicy>) -> Self {
+        self.events = self
+            .events
+            .take()
+            .map(|events| events.with_policy(Some(policy.clone())));
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Answer every failed `POST /rpc` call with a status reflecting the
+    /// error class (`404` for `unknown_method`, `504` for `timeout`, ...)
+    /// instead of `200` with the error in the body.
+    pub fn with_error_status(mut self, enabled: bool) -> Self {
+        self.error_status = enabled;
+        self
+    }
+
+    /// Reject request bodies larger than `max_body_bytes` with `413`.
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// Compress responses with gzip or deflate when the client's
+    /// `Accept-Encoding` allows it.
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Answer CORS preflights and tag responses for the allowed origins.
+    pub fn with_cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Also serve the router on a Unix domain socket while
+    /// [`serve`](Self::serve) or [`serve_with_listener`](Self::serve_with_listener)
+    /// runs. The socket always speaks plain HTTP, even with TLS enabled.
+    pub fn with_unix_socket(mut self, unix: UnixSocketConfig) -> Self {
+        self.unix = Some(unix);
+        self
+    }
+
+    /// Require an `Authorization: Bearer <token>` header on every endpoint.
+    ///
+    /// Requests without a valid token get `401` with the `unauthenticated`
+    /// error code; accepted tokens set the request's principal. Requests over
+    /// mutual TLS are identified by their client certificate instead.
+    pub fn with_authenticator(mut self, authenticator: DynAuthenticator) -> Self {
+        self.authenticator = Some(authenticator);
+        self
+    }
+
+    /// Expose the event bus over the `GET /events` WebSocket.
+    ///
+    /// Clients receive every bus event and may publish events to topics
+    /// matched by `allowlist`.
+    pub fn with_events(mut self, events: DynEventBus, allowlist: TopicAllowlist) -> Self {
+        self.events =
+            Some(ClientEventPublisher::new(events, allowlist).with_policy(self.policy.clone()));
+        self
     }
 
     /// Build the Axum router handling HTTP RPC requests.
     pub fn router(&self) -> Router {
+        self.router_until(CancellationToken::new())
+    }
+
+    /// Router whose WebSocket sessions end once `shutdown` is cancelled.
+    fn router_until(&self, shutdown: CancellationToken) -> Router {
         let state = HttpServerState {
             registry: self.registry.clone(),
+            events: self.events.clone(),
+            authenticator: self.authenticator.clone(),
+            error_status: self.error_status,
+            shutdown,
         };
-        Router::new()
+        let mut router = Router::new()
             .route("/rpc", post(handle_rpc))
+            .route("/rpc/methods", get(handle_list_methods))
+            .route("/rpc/stream", get(handle_rpc_stream))
+            .route("/rpc/:service/:method", post(rest::handle_rest))
+            .route("/jsonrpc", post(jsonrpc::handle_jsonrpc));
+        if self.events.is_some() {
+            router = router.route("/events", get(handle_events));
+        }
+        let mut router = router
             .with_state(state)
+            .layer(DefaultBodyLimit::disable())
+            .layer(RequestBodyLimitLayer::new(self.max_body_bytes));
+        if self.compression {
+            router = router.layer(CompressionLayer::new().gzip(true).deflate(true));
+        }
+        if let Some(cors) = &self.cors {
+            router = router.layer(cors_layer(cors));
+        }
+        router
     }
 
     /// Start serving HTTP RPC requests on the provided socket address.
     pub async fn serve(self, addr: SocketAddr) -> anyhow::Result<()> {
-        let router = self.router();
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, router).await?;
-        Ok(())
+        let listener = TcpListener::bind(addr).await?;
+        self.serve_with_listener(listener).await
+    }
+
+    /// Start serving HTTP RPC requests on an already bound listener.
+    ///
+    /// Connections, including upgraded WebSockets, are owned by the returned
+    /// future: dropping it (e.g. aborting its task) closes all of them and
+    /// cancels the calls they were serving.
+    pub async fn serve_with_listener(self, listener: TcpListener) -> anyhow::Result<()> {
+        let shutdown = CancellationToken::new();
+        let _shutdown = shutdown.clone().drop_guard();
+        let router = self.router_until(shutdown);
+        #[cfg(unix)]
+        if let Some(config) = &self.unix {
+            let socket = unix::bind(config)?;
+            tokio::try_join!(
+                self.serve_tcp(listener, router.clone()),
+                unix::serve(socket, router)
+            )?;
+            return Ok(());
+        }
+        self.serve_tcp(listener, router).await
+    }
+
+    /// Serve HTTP RPC requests only on the Unix domain socket described by
+    /// `config`, replacing a stale socket file left by a previous run.
+    ///
+    /// As with [`serve_with_listener`](Self::serve_with_listener), dropping
+    /// the future closes every connection.
+    #[cfg(unix)]
+    pub async fn serve_unix(self, config: &UnixSocketConfig) -> anyhow::Result<()> {
+        let socket = unix::bind(config)?;
+        let shutdown = CancellationToken::new();
+        let _shutdown = shutdown.clone().drop_guard();
+        unix::serve(socket, self.router_until(shutdown)).await
+    }
+
+    async fn serve_tcp(&self, listener: TcpListener, router: Router) -> anyhow::Result<()> {
+        #[cfg(feature = "transport_http_tls")]
+        if let Some(tls) = self.tls.clone() {
+            return tls::serve(listener, router, tls).await;
+        }
+        let mut connections = JoinSet::new();
+        loop {
+            let (stream, addr) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(_) => {
+                        // Usually out of file descriptors; back off instead of spinning.
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        continue;
+                    }
+                },
+                // Reap finished connections so the set does not grow.
+                Some(_) = connections.join_next() => continue,
+            };
+            let router = router.clone();
+            connections.spawn(async move {
+                let service = service_fn(move |mut request: Request<Incoming>| {
+                    request
+                        .extensions_mut()
+                        .insert(ConnectInfo::<SocketAddr>(addr));
+                    router.clone().oneshot(request)
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .with_upgrades()
+                    .await;
+            });
+        }
     }
 }
 
 impl Transport for HttpServerTransport {
     fn id(&self) -> service_core::types::TransportId {
-        "http".to_string()
+        HTTP_TRANSPORT_ID.to_string()
     }
 
     fn start(&self) -> service_core::Result<()> {
@@ -57,19 +286,143 @@ impl Transport for HttpServerTransport {
     }
 }
 
+/// Headers the protocol defines; CORS always allows them.
+const PROTOCOL_HEADERS: [&str; 8] = [
+    "authorization",
+    "content-type",
+    REQUEST_ID_HEADER,
+    TIMEOUT_HEADER,
+    IDEMPOTENCY_KEY_METADATA,
+    SIGNATURE_METADATA,
+    NONCE_METADATA,
+    TIMESTAMP_METADATA,
+];
+
+fn cors_layer(cors: &CorsConfig) -> CorsLayer {
+    let origins = if cors.allows_any_origin() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            cors.allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    let headers = PROTOCOL_HEADERS
+        .iter()
+        .copied()
+        .chain(cors.allowed_headers.iter().map(String::as_str))
+        .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+        .collect::<Vec<_>>();
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers(headers)
+        .expose_headers([header::RETRY_AFTER])
+        .max_age(Duration::from_secs(cors.max_age_secs))
+}
+
+/// Caller identity established by the connection itself (a verified TLS
+/// client certificate), inserted into request extensions by the server.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "transport_http_tls"), allow(dead_code))]
+struct PeerIdentity(Principal);
+
+/// Peer of a connection that has no socket address (a Unix socket client),
+/// inserted into request extensions by the server.
+#[derive(Clone)]
+#[cfg_attr(not(unix), allow(dead_code))]
+struct ConnectionPeer(Peer);
+
+/// The peer a request arrived from: a [`ConnectionPeer`] if the server set
+/// one, otherwise the TCP address from `ConnectInfo`.
+struct CallerPeer(Option<Peer>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CallerPeer {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(ConnectionPeer(peer)) = parts.extensions.get::<ConnectionPeer>() {
+            return Ok(Self(Some(peer.clone())));
+        }
+        let addr = parts.extensions.get::<ConnectInfo<SocketAddr>>();
+        Ok(Self(addr.map(|ConnectInfo(addr)| Peer::Socket(*addr))))
+    }
+}
+
 #[derive(Clone)]
 struct HttpServerState {
     registry: Arc<dyn RpcRegistry>,
+    events: Option<ClientEventPublisher>,
+    authenticator: Option<DynAuthenticator>,
+    error_status: bool,
+    /// Cancelled when the server stops; ends upgraded WebSocket sessions,
+    /// which run outside the connection tasks.
+    shutdown: CancellationToken,
+}
+
+impl HttpServerState {
+    /// Resolve the caller from the connection's client certificate or the
+    /// `Authorization` header; `None` when the server does not require
+    /// authentication.
+    fn authenticate(
+        &self,
+        identity: Option<Extension<PeerIdentity>>,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, RpcError> {
+        if let Some(Extension(PeerIdentity(principal))) = identity {
+            return Ok(Some(principal));
+        }
+        let Some(authenticator) = &self.authenticator else {
+            return Ok(None);
+        };
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_token)
+            .ok_or(AuthError::Missing);
+        token
+            .and_then(|token| authenticator.authenticate(token))
+            .map(Some)
+            .map_err(|err| RpcError::Unauthenticated(err.to_string()))
+    }
 }
 
 async fn handle_rpc(
     State(state): State<HttpServerState>,
+    CallerPeer(peer): CallerPeer,
+    identity: Option<Extension<PeerIdentity>>,
+    headers: HeaderMap,
     Json(request): Json<HttpRpcRequest>,
-) -> Result<Json<HttpRpcResponse>, HttpHandlerError> {
-    let payload = decode_payload(&request.payload_b64)?;
-    let rpc_request = RpcRequest::new(request.service, request.method, payload, request.timeout_ms);
+) -> Result<Response, HttpHandlerError> {
+    let principal = state
+        .authenticate(identity, &headers)
+        .map_err(HttpHandlerError::Unauthenticated)?;
+    let payload = decode_payload(&request.payload_b64).map_err(HttpHandlerError::InvalidBase64)?;
+    let rpc_request = with_http_context(
+        RpcRequest::new(request.service, request.method, payload, request.timeout_ms),
+        peer,
+        principal,
+        &headers,
+    );
     let rpc_result = state.registry.dispatch(rpc_request).await;
 
+    // By default, shed load with 503 and throttle with 429 so proxies and
+    // clients back off, reject bad request signatures with 401, refuse with
+    // 403 and report a reused idempotency key with 409; every other RPC
+    // error is reported in the body of a 200.
+    let status = match &rpc_result {
+        Ok(_) => StatusCode::OK,
+        Err(err) if state.error_status => error_status(err),
+        Err(RpcError::Overloaded) => StatusCode::SERVICE_UNAVAILABLE,
+        Err(RpcError::Unauthenticated(_)) => StatusCode::UNAUTHORIZED,
+        Err(RpcError::PermissionDenied(_)) => StatusCode::FORBIDDEN,
+        Err(RpcError::RateLimited { .. }) => StatusCode::TOO_MANY_REQUESTS,
+        Err(RpcError::Conflict(_)) => StatusCode::CONFLICT,
+        Err(_) => StatusCode::OK,
+    };
+    let retry_after = rpc_result.as_ref().err().and_then(RpcError::retry_after);
     let response = match rpc_result {
         Ok(rpc_response) => HttpRpcResponse {
             payload_b64: encode_payload(&rpc_response.payload),
@@ -81,12 +434,282 @@ async fn handle_rpc(
         },
     };
 
-    Ok(Json(response))
+    let mut response = (status, Json(response)).into_response();
+    if let Some(delay) = retry_after {
+        set_retry_after(&mut response, delay);
+    }
+    Ok(response)
+}
+
+fn set_retry_after(response: &mut Response, delay: Duration) {
+    // Retry-After takes whole seconds; round up so clients do not retry early.
+    let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, seconds.into());
+}
+
+/// List every registered method by dispatching the `rpc.list` built-in, so
+/// access policy, rate limits and request signing apply as on `POST /rpc`.
+async fn handle_list_methods(
+    State(state): State<HttpServerState>,
+    CallerPeer(peer): CallerPeer,
+    identity: Option<Extension<PeerIdentity>>,
+    headers: HeaderMap,
+) -> Result<Response, HttpHandlerError> {
+    let principal = state
+        .authenticate(identity, &headers)
+        .map_err(HttpHandlerError::Unauthenticated)?;
+    let request = with_http_context(
+        RpcRequest::new(BUILTIN_SERVICE, METHOD_LIST, Vec::new(), 0),
+        peer,
+        principal,
+        &headers,
+    );
+    Ok(match state.registry.dispatch(request).await {
+        Ok(response) => (
+            [(header::CONTENT_TYPE, "application/json")],
+            response.payload,
+        )
+            .into_response(),
+        Err(err) => rest::error_response(&err),
+    })
+}
+
+/// Fill the request context with the transport, peer, principal and headers.
+///
+/// Every header with a UTF-8 value becomes a metadata entry keyed by its
+/// lower-case name; repeated headers are joined with `", "`. `Authorization`
+/// is left out so credentials never reach handlers. The `X-Request-Id`
+/// header, if present, becomes the request id.
+fn with_http_context(
+    mut request: RpcRequest,
+    peer: Option<Peer>,
+    principal: Option<Principal>,
+    headers: &HeaderMap,
+) -> RpcRequest {
+    request = request.with_transport(HTTP_TRANSPORT_ID);
+    if let Some(peer) = peer {
+        request = request.with_peer(peer);
+    }
+    if let Some(principal) = principal {
+        request = request.with_principal(principal);
+    }
+    for (name, value) in headers {
+        if name == header::AUTHORIZATION {
+            continue;
+        }
+        let Ok(value) = value.to_str() else { continue };
+        request
+            .context
+            .metadata
+            .entry(name.as_str().to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    match request.context.metadata(REQUEST_ID_HEADER) {
+        Some(request_id) if !request_id.is_empty() => {
+            let request_id = request_id.to_string();
+            request.with_request_id(request_id)
+        }
+        _ => request,
+    }
+}
+
+async fn handle_rpc_stream(
+    State(state): State<HttpServerState>,
+    CallerPeer(peer): CallerPeer,
+    identity: Option<Extension<PeerIdentity>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let principal = match state.authenticate(identity, &headers) {
+        Ok(principal) => principal,
+        Err(err) => return HttpHandlerError::Unauthenticated(err).into_response(),
+    };
+    upgrade.on_upgrade(move |socket| async move {
+        let session = run_rpc_stream(socket, state.registry, peer, principal, headers);
+        state.shutdown.run_until_cancelled(session).await;
+    })
+}
+
+/// Serve one streaming call: read the request, forward chunks, then send the
+/// terminating status. The handler is cancelled if the client goes away.
+async fn run_rpc_stream(
+    mut socket: WebSocket,
+    registry: Arc<dyn RpcRegistry>,
+    peer: Option<Peer>,
+    principal: Option<Principal>,
+    headers: HeaderMap,
+) {
+    let text = match socket.recv().await {
+        Some(Ok(Message::Text(text))) => text,
+        _ => return,
+    };
+    let request = match decode_stream_request(&text) {
+        Ok(request) => request,
+        Err(err) => {
+            let end = HttpStreamMessage::End {
+                error: Some(map_rpc_error(&err)),
+            };
+            let _ = send_json(&mut socket, &end).await;
+            return;
+        }
+    };
+
+    let mut stream =
+        registry.dispatch_stream(with_http_context(request, peer, principal, &headers));
+    loop {
+        tokio::select! {
+            chunk = stream.next() => {
+                let Some(chunk) = chunk else { break };
+                let message = HttpStreamMessage::Chunk {
+                    payload_b64: encode_payload(&chunk.payload),
+                };
+                if send_json(&mut socket, &message).await.is_err() {
+                    return;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        }
+    }
+
+    let end = HttpStreamMessage::End {
+        error: stream.finish().await.err().map(|err| map_rpc_error(&err)),
+    };
+    if send_json(&mut socket, &end).await.is_ok() {
+        let _ = socket.send(Message::Close(None)).await;
+    }
+}
+
+fn decode_stream_request(text: &str) -> Result<RpcRequest, RpcError> {
+    let request: HttpRpcRequest =
+        serde_json::from_str(text).map_err(|err| RpcError::Decode(err.to_string()))?;
+    let payload = decode_payload(&request.payload_b64).map_err(RpcError::Decode)?;
+    Ok(RpcRequest::new(
+        request.service,
+        request.method,
+        payload,
+        request.timeout_ms,
+    ))
+}
+
+async fn handle_events(
+    State(state): State<HttpServerState>,
+    identity: Option<Extension<PeerIdentity>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let principal = match state.authenticate(identity, &headers) {
+        Ok(principal) => principal,
+        Err(err) => return HttpHandlerError::Unauthenticated(err).into_response(),
+    };
+    match state.events {
+        Some(events) => upgrade.on_upgrade(move |socket| async move {
+            let session = run_event_socket(socket, events, principal);
+            state.shutdown.run_until_cancelled(session).await;
+        }),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Pump bus events to the client and client publishes onto the bus until
+/// either side closes.
+async fn run_event_socket(
+    mut socket: WebSocket,
+    publisher: ClientEventPublisher,
+    principal: Option<Principal>,
+) {
+    let mut subscription = publisher.events().subscribe();
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if let Some(reply) = handle_client_event(&publisher, principal.as_ref(), &text) {
+                    if send_json(&mut socket, &reply).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            event = subscription.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(EventError::Lagged(_)) => continue,
+                    Err(_) => break,
+                };
+                if !publisher.may_use(principal.as_ref(), &event.topic) {
+                    continue;
+                }
+                let message = HttpEventMessage::Event {
+                    topic: event.topic,
+                    payload_b64: encode_payload(&event.payload),
+                };
+                if send_json(&mut socket, &message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Publish a client message, returning an error reply if it was rejected.
+fn handle_client_event(
+    publisher: &ClientEventPublisher,
+    principal: Option<&Principal>,
+    text: &str,
+) -> Option<HttpEventMessage> {
+    let (topic, payload_b64) = match serde_json::from_str::<HttpEventMessage>(text) {
+        Ok(HttpEventMessage::Publish { topic, payload_b64 }) => (topic, payload_b64),
+        Ok(_) => return Some(event_error("decode", "expected a publish message")),
+        Err(err) => return Some(event_error("decode", err.to_string())),
+    };
+    let payload = match decode_payload(&payload_b64) {
+        Ok(payload) => payload,
+        Err(message) => return Some(event_error("decode", message)),
+    };
+
+    match publisher.publish_as(principal, TransportEvent { topic, payload }) {
+        Ok(()) => None,
+        Err(err @ EventError::TopicNotAllowed(_)) => {
+            Some(event_error("topic_not_allowed", err.to_string()))
+        }
+        Err(err @ EventError::PermissionDenied(_)) => {
+            Some(event_error("permission_denied", err.to_string()))
+        }
+        Err(err) => Some(event_error("internal", err.to_string())),
+    }
+}
+
+fn event_error(code: &str, message: impl Into<String>) -> HttpEventMessage {
+    HttpEventMessage::Error {
+        code: code.to_string(),
+        message: message.into(),
+    }
+}
+
+async fn send_json<T: serde::Serialize>(
+    socket: &mut WebSocket,
+    message: &T,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).expect("websocket message serializes");
+    socket.send(Message::Text(text)).await
 }
 
 #[derive(Debug)]
 enum HttpHandlerError {
     InvalidBase64(String),
+    Unauthenticated(RpcError),
 }
 
 impl IntoResponse for HttpHandlerError {
@@ -99,18 +722,29 @@ impl IntoResponse for HttpHandlerError {
                     error: Some(HttpRpcError {
                         code: "decode".to_string(),
                         message,
+                        retry_after_ms: None,
+                        details: Default::default(),
                     }),
                 }),
             )
                 .into_response(),
+            HttpHandlerError::Unauthenticated(err) => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(HttpRpcResponse {
+                    payload_b64: String::new(),
+                    error: Some(map_rpc_error(&err)),
+                }),
+            )
+                .into_response(),
         }
     }
 }
 
-fn decode_payload(encoded: &str) -> Result<Vec<u8>, HttpHandlerError> {
+fn decode_payload(encoded: &str) -> Result<Vec<u8>, String> {
     general_purpose::STANDARD
         .decode(encoded)
-        .map_err(|err| HttpHandlerError::InvalidBase64(err.to_string()))
+        .map_err(|err| err.to_string())
 }
 
 fn encode_payload(payload: &[u8]) -> String {
@@ -118,15 +752,30 @@ fn encode_payload(payload: &[u8]) -> String {
 }
 
 fn map_rpc_error(err: &RpcError) -> HttpRpcError {
-    let code = match err {
-        RpcError::UnknownMethod => "unknown_method",
-        RpcError::Decode(_) => "decode",
-        RpcError::Internal(_) => "internal",
-    }
-    .to_string();
-
     HttpRpcError {
-        code,
+        code: err.code().to_string(),
         message: err.to_string(),
+        retry_after_ms: err.retry_after().map(|delay| delay.as_millis() as u64),
+        details: err.details(),
+    }
+}
+
+/// HTTP status reflecting the class of an RPC error.
+fn error_status(err: &RpcError) -> StatusCode {
+    match err {
+        RpcError::UnknownMethod => StatusCode::NOT_FOUND,
+        RpcError::Decode(_) => StatusCode::BAD_REQUEST,
+        RpcError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        RpcError::InvalidArgument { .. } => StatusCode::BAD_REQUEST,
+        RpcError::NotFound { .. } => StatusCode::NOT_FOUND,
+        RpcError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        RpcError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        // Non-standard "client closed request", as used by nginx.
+        RpcError::Cancelled => StatusCode::from_u16(499).expect("valid status code"),
+        RpcError::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
+        RpcError::Unauthenticated(_) => StatusCode::UNAUTHORIZED,
+        RpcError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+        RpcError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        RpcError::Conflict(_) => StatusCode::CONFLICT,
     }
 }
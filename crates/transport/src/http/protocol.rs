@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 /// RPC request envelope accepted by the HTTP transport.
@@ -22,4 +24,88 @@ pub struct HttpRpcResponse {
 pub struct HttpRpcError {
     pub code: String,
     pub message: String,
+    /// Suggested delay before retrying, set with `rate_limited`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    /// Structured fields of the error, e.g. `field` for `invalid_argument`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+/// Messages exchanged over the `GET /events` WebSocket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HttpEventMessage {
+    /// Event delivered from the device bus to the client.
+    Event { topic: String, payload_b64: String },
+    /// Event published by the client onto the device bus.
+    Publish { topic: String, payload_b64: String },
+    /// Rejection of a client message.
+    Error { code: String, message: String },
+}
+
+/// Messages sent by the server over the `GET /rpc/stream` WebSocket.
+///
+/// The client opens the socket and sends a single [`HttpRpcRequest`]; the
+/// server answers with any number of chunks followed by one `end` message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HttpStreamMessage {
+    Chunk { payload_b64: String },
+    End { error: Option<HttpRpcError> },
+}
+
+/// JSON-RPC 2.0 version marker, required in every request and response.
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// JSON-RPC 2.0 request accepted by `POST /jsonrpc`.
+///
+/// `method` is `service.method`. `params`, when present, is serialized as
+/// the RPC payload. A request without an `id` is a notification and gets no
+/// response; an explicit `"id": null` is kept as `Some(Value::Null)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub id: Option<serde_json::Value>,
+}
+
+/// Distinguish an explicit `null` from a missing field.
+fn present<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    serde_json::Value::deserialize(deserializer).map(Some)
+}
+
+/// JSON-RPC 2.0 response; exactly one of `result` and `error` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+    pub id: serde_json::Value,
+}
+
+/// JSON-RPC 2.0 error object. `data` carries the transport's
+/// [`HttpRpcError`] so clients see the same string code as on `/rpc`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<HttpRpcError>,
 }
@@ -0,0 +1,77 @@
+//! `POST /rpc/{service}/{method}`: the raw request body is the payload and
+//! the raw response body is the result, for callers that cannot build the
+//! base64 envelope of `POST /rpc`.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use service_core::router::{RpcError, RpcRequest};
+
+use crate::http::{
+    error_status, map_rpc_error, set_retry_after, with_http_context, CallerPeer, HttpServerState,
+    PeerIdentity, TIMEOUT_HEADER,
+};
+
+pub(super) async fn handle_rest(
+    State(state): State<HttpServerState>,
+    Path((service, method)): Path<(String, String)>,
+    CallerPeer(peer): CallerPeer,
+    identity: Option<Extension<PeerIdentity>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let principal = match state.authenticate(identity, &headers) {
+        Ok(principal) => principal,
+        Err(err) => {
+            let mut response = error_response(&err);
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return response;
+        }
+    };
+    let timeout_ms = match headers.get(TIMEOUT_HEADER).map(|value| {
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    }) {
+        None => 0,
+        Some(Some(timeout_ms)) => timeout_ms,
+        Some(None) => {
+            let err =
+                RpcError::Decode(format!("{TIMEOUT_HEADER} must be a number of milliseconds"));
+            return error_response(&err);
+        }
+    };
+
+    let request = with_http_context(
+        RpcRequest::new(service, method, body.to_vec(), timeout_ms),
+        peer,
+        principal,
+        &headers,
+    );
+    let result = state.registry.dispatch(request).await;
+
+    match result {
+        Ok(response) => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            response.payload,
+        )
+            .into_response(),
+        Err(err) => error_response(&err),
+    }
+}
+
+/// The `/rpc` error object with the status of its class.
+pub(super) fn error_response(err: &RpcError) -> Response {
+    let mut response = (error_status(err), Json(map_rpc_error(err))).into_response();
+    if let Some(delay) = err.retry_after() {
+        set_retry_after(&mut response, delay);
+    }
+    response
+}
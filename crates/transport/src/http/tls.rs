@@ -0,0 +1,217 @@
+//! HTTPS serving for [`HttpServerTransport`](super::HttpServerTransport).
+//!
+//! Certificates are read from PEM files and can be reloaded while serving:
+//! new connections pick up the reloaded certificates, established ones keep
+//! the session they negotiated.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use axum::{extract::ConnectInfo, Router};
+use hyper::{body::Incoming, service::service_fn, Request};
+use hyper_util::rt::TokioIo;
+use service_core::{router::Principal, TlsConfig};
+use tokio::{net::TcpListener, task::JoinSet};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tower::ServiceExt as _;
+use x509_parser::{extensions::GeneralName, prelude::parse_x509_certificate};
+
+use crate::http::PeerIdentity;
+
+/// Time a client gets to complete the TLS handshake before its connection
+/// is dropped.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reloadable TLS server settings.
+///
+/// Cloning shares the loaded certificates, so a [`HttpTls::reload`] through
+/// any clone applies to every server using it.
+#[derive(Clone)]
+pub struct HttpTls {
+    config: TlsConfig,
+    current: Arc<RwLock<Arc<ServerConfig>>>,
+    handshake_timeout: Duration,
+}
+
+impl HttpTls {
+    /// Read the certificate chain, key and optional client CA bundle.
+    pub fn load(config: TlsConfig) -> anyhow::Result<Self> {
+        let server_config = build_server_config(&config)?;
+        Ok(Self {
+            config,
+            current: Arc::new(RwLock::new(server_config)),
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        })
+    }
+
+    /// Drop connections that have not finished the handshake within
+    /// `timeout` (default [`DEFAULT_HANDSHAKE_TIMEOUT`]).
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Re-read the files named in the config. On error the previously loaded
+    /// certificates stay in use.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let server_config = build_server_config(&self.config)?;
+        *self.current.write().expect("tls config lock") = server_config;
+        Ok(())
+    }
+
+    /// Reload the certificates whenever the process receives `SIGHUP`,
+    /// passing the outcome of each reload to `on_reload`.
+    #[cfg(unix)]
+    pub fn reload_on_sighup<F>(&self, on_reload: F) -> std::io::Result<tokio::task::JoinHandle<()>>
+    where
+        F: Fn(anyhow::Result<()>) + Send + 'static,
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = signal(SignalKind::hangup())?;
+        let tls = self.clone();
+        Ok(tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                on_reload(tls.reload());
+            }
+        }))
+    }
+
+    fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.current.read().expect("tls config lock").clone())
+    }
+}
+
+fn build_server_config(config: &TlsConfig) -> anyhow::Result<Arc<ServerConfig>> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match &config.client_ca_path {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(path)? {
+                roots.add(cert)?;
+            }
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config =
+        builder.with_single_cert(read_certs(&config.cert_path)?, read_key(&config.key_path)?)?;
+    // WebSocket upgrades (`/rpc/stream`, `/events`) need HTTP/1.1.
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(server_config))
+}
+
+fn read_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path).with_context(|| path.display().to_string())?);
+    let certs = rustls_pemfile::certs(&mut reader)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| path.display().to_string())?;
+    anyhow::ensure!(!certs.is_empty(), "{}: no certificates", path.display());
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> anyhow::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path).with_context(|| path.display().to_string())?);
+    rustls_pemfile::private_key(&mut reader)
+        .with_context(|| path.display().to_string())?
+        .with_context(|| format!("{}: no private key", path.display()))
+}
+
+/// Identity of a verified client certificate: the subject common name (or
+/// first DNS name) as the principal id and organizational units as roles.
+fn client_principal(cert: &CertificateDer<'_>) -> Option<Principal> {
+    let (_, cert) = parse_x509_certificate(cert.as_ref()).ok()?;
+    let subject = cert.subject();
+    let id = subject
+        .iter_common_name()
+        .find_map(|name| name.as_str().ok().map(str::to_string))
+        .or_else(|| {
+            let names = cert.subject_alternative_name().ok()??;
+            names
+                .value
+                .general_names
+                .iter()
+                .find_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    _ => None,
+                })
+        })?;
+    let roles = subject
+        .iter_organizational_unit()
+        .filter_map(|unit| unit.as_str().ok().map(str::to_string))
+        .collect::<Vec<_>>();
+    Some(Principal::new(id).with_roles(roles))
+}
+
+pub(crate) async fn serve(
+    listener: TcpListener,
+    router: Router,
+    tls: HttpTls,
+) -> anyhow::Result<()> {
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(_) => {
+                    // Usually out of file descriptors; back off instead of spinning.
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                }
+            },
+            // Reap finished connections so the set does not grow.
+            Some(_) = connections.join_next() => continue,
+        };
+        let acceptor = tls.acceptor();
+        let handshake_timeout = tls.handshake_timeout;
+        let router = router.clone();
+        connections.spawn(async move {
+            // A client that never finishes the handshake must not hold the
+            // connection open.
+            let handshake = tokio::time::timeout(handshake_timeout, acceptor.accept(stream));
+            let Ok(Ok(stream)) = handshake.await else {
+                return;
+            };
+            let identity = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|chain| chain.first())
+                .and_then(client_principal);
+            let service = service_fn(move |mut request: Request<Incoming>| {
+                request
+                    .extensions_mut()
+                    .insert(ConnectInfo::<SocketAddr>(addr));
+                if let Some(principal) = &identity {
+                    request
+                        .extensions_mut()
+                        .insert(PeerIdentity(principal.clone()));
+                }
+                router.clone().oneshot(request)
+            });
+            let _ = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades()
+                .await;
+        });
+    }
+}
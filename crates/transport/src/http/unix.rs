@@ -0,0 +1,144 @@
+//! Unix domain socket serving for [`HttpServerTransport`](super::HttpServerTransport).
+//!
+//! Local processes reach the same router through a socket file instead of a
+//! TCP port, and the file's permission bits decide who may connect. Calls
+//! are attributed to the connecting process's uid as `local:uid:{uid}`.
+
+use std::{
+    fs::{self, DirBuilder, Permissions},
+    io::ErrorKind,
+    os::unix::fs::{DirBuilderExt as _, FileTypeExt as _, MetadataExt as _, PermissionsExt as _},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use axum::Router;
+use hyper::{body::Incoming, service::service_fn, Request};
+use hyper_util::rt::TokioIo;
+use service_core::{router::Peer, UnixSocketConfig};
+use tokio::{net::UnixListener, task::JoinSet};
+use tower::ServiceExt as _;
+
+use crate::http::ConnectionPeer;
+
+/// Distinguishes staging directories of sockets bound by the same process.
+static STAGING: AtomicUsize = AtomicUsize::new(0);
+
+/// A bound socket whose file is removed again when it is dropped.
+pub(crate) struct UnixSocket {
+    listener: UnixListener,
+    path: PathBuf,
+    /// Device and inode of the socket file, so a file that has since been
+    /// replaced by another server is left alone.
+    file: (u64, u64),
+}
+
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        let ours = fs::symlink_metadata(&self.path)
+            .is_ok_and(|metadata| (metadata.dev(), metadata.ino()) == self.file);
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Bind the socket named in `config` and apply its permission bits.
+///
+/// The socket is created in a private (`0700`) directory next to `path`,
+/// given its mode there and only then moved into place, so nobody can
+/// connect before the permissions apply. A socket file left behind by a
+/// previous run is replaced; binding fails if another server still accepts
+/// on it or the path is not a socket.
+pub(crate) fn bind(config: &UnixSocketConfig) -> anyhow::Result<UnixSocket> {
+    let path = &config.path;
+    match fs::symlink_metadata(path) {
+        Ok(metadata) => {
+            anyhow::ensure!(
+                metadata.file_type().is_socket(),
+                "{}: exists and is not a socket",
+                path.display()
+            );
+            anyhow::ensure!(
+                std::os::unix::net::UnixStream::connect(path).is_err(),
+                "{}: socket is in use",
+                path.display()
+            );
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(err).with_context(|| path.display().to_string()),
+    }
+
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let staging = parent.join(format!(
+        ".bind-{}-{}",
+        std::process::id(),
+        STAGING.fetch_add(1, Ordering::Relaxed)
+    ));
+    DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| staging.display().to_string())?;
+    let staged = staging.join("s");
+    let bound = (|| {
+        let listener = UnixListener::bind(&staged)?;
+        if let Some(mode) = config.mode {
+            fs::set_permissions(&staged, Permissions::from_mode(mode))?;
+        }
+        fs::rename(&staged, path)?;
+        anyhow::Ok(listener)
+    })();
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&staging);
+    let listener = bound.with_context(|| path.display().to_string())?;
+    let metadata = fs::symlink_metadata(path).with_context(|| path.display().to_string())?;
+    Ok(UnixSocket {
+        listener,
+        path: path.clone(),
+        file: (metadata.dev(), metadata.ino()),
+    })
+}
+
+/// Serve `router` on `socket` until the future is dropped, which also
+/// removes the socket file.
+pub(crate) async fn serve(socket: UnixSocket, router: Router) -> anyhow::Result<()> {
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, _) = tokio::select! {
+            accepted = socket.listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(_) => {
+                    // Usually out of file descriptors; back off instead of spinning.
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                }
+            },
+            // Reap finished connections so the set does not grow.
+            Some(_) = connections.join_next() => continue,
+        };
+        let peer = stream
+            .peer_cred()
+            .ok()
+            .map(|cred| Peer::Local(format!("uid:{}", cred.uid())));
+        let router = router.clone();
+        connections.spawn(async move {
+            let service = service_fn(move |mut request: Request<Incoming>| {
+                if let Some(peer) = &peer {
+                    request
+                        .extensions_mut()
+                        .insert(ConnectionPeer(peer.clone()));
+                }
+                router.clone().oneshot(request)
+            });
+            let _ = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades()
+                .await;
+        });
+    }
+}
@@ -4,12 +4,15 @@ use service_core::{
     event::{
         EventBus, EventError, EventPublisher, EventSubscriber, EventSubscription, TransportEvent,
     },
-    router::{InMemoryRouter, RpcError, RpcRegistry, RpcRequest, RpcResponse},
+    router::{InMemoryRouter, RpcError, RpcRegistry, RpcRequest, RpcResponse, RpcStream},
     transport::Transport,
     types::TransportId,
 };
 use tokio::sync::broadcast;
 
+/// Transport id reported by [`MockTransport`] and set on request contexts.
+pub const MOCK_TRANSPORT_ID: &str = "mock";
+
 #[derive(Clone)]
 pub struct MockTransport {
     registry: InMemoryRouter,
@@ -18,8 +21,13 @@ pub struct MockTransport {
 
 impl MockTransport {
     pub fn new() -> Self {
+        Self::with_router(InMemoryRouter::new())
+    }
+
+    /// Use a preconfigured router, e.g. one with concurrency limits.
+    pub fn with_router(registry: InMemoryRouter) -> Self {
         Self {
-            registry: InMemoryRouter::new(),
+            registry,
             events: BroadcastEventBus::new(),
         }
     }
@@ -33,13 +41,31 @@ impl MockTransport {
     }
 
     pub async fn handle_incoming(&self, req: RpcRequest) -> Result<RpcResponse, RpcError> {
-        self.registry.dispatch(req).await
+        self.registry.dispatch(with_mock_context(req)).await
+    }
+
+    pub fn handle_incoming_stream(&self, req: RpcRequest) -> RpcStream {
+        self.registry.dispatch_stream(with_mock_context(req))
+    }
+}
+
+/// Tag requests with the mock transport id unless the caller already set one.
+fn with_mock_context(mut req: RpcRequest) -> RpcRequest {
+    req.context
+        .transport
+        .get_or_insert_with(|| MOCK_TRANSPORT_ID.to_string());
+    req
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Transport for MockTransport {
     fn id(&self) -> TransportId {
-        "mock".to_string()
+        MOCK_TRANSPORT_ID.to_string()
     }
 
     fn start(&self) -> service_core::Result<()> {
@@ -61,10 +87,9 @@ impl BroadcastEventBus {
 
 impl EventPublisher for BroadcastEventBus {
     fn publish(&self, event: TransportEvent) -> Result<(), EventError> {
-        self.sender
-            .send(event)
-            .map(|_| ())
-            .map_err(|err| EventError::Publish(err.to_string()))
+        // Publishing with nobody subscribed is not an error; the event is simply dropped.
+        let _ = self.sender.send(event);
+        Ok(())
     }
 }
 
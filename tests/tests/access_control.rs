@@ -0,0 +1,226 @@
+use std::{sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose, Engine as _};
+use futures_util::{SinkExt, StreamExt};
+use service_core::{
+    event::{ClientEventPublisher, EventError, TopicAllowlist, TransportEvent},
+    router::{rpc_handler, Principal, RpcError, RpcRequest, RpcResponse},
+    AccessPolicy, AccessRule, AppConfig, InMemoryRouter, StaticTokenAuthenticator,
+};
+use service_transport::{
+    http::{
+        protocol::{HttpEventMessage, HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+fn policy() -> Arc<AccessPolicy> {
+    Arc::new(AccessPolicy::new([
+        AccessRule::new()
+            .principals(["*"])
+            .methods(["hello.*"])
+            .topics(["phone/*"]),
+        AccessRule::new()
+            .roles(["admin"])
+            .methods(["*"])
+            .topics(["*"]),
+    ]))
+}
+
+fn transport_with(policy: Arc<AccessPolicy>) -> MockTransport {
+    let transport = MockTransport::with_router(InMemoryRouter::new().with_policy(policy));
+    let registry = transport.registry();
+    for (service, method) in [("hello", "get"), ("admin", "reboot")] {
+        let handler = rpc_handler(|_req: RpcRequest| async move {
+            Ok(RpcResponse {
+                payload: b"ok".to_vec(),
+            })
+        });
+        registry
+            .register(service, method, handler)
+            .expect("register handler");
+    }
+    transport
+}
+
+fn request_as(service: &str, method: &str, principal: Option<Principal>) -> RpcRequest {
+    let mut req = RpcRequest::new(service, method, Vec::new(), 0);
+    req.context.principal = principal;
+    req
+}
+
+#[tokio::test]
+async fn router_enforces_method_rules() {
+    let transport = transport_with(policy());
+    let operator = Principal::new("tablet").with_roles(["admin"]);
+    let viewer = Principal::new("phone");
+
+    for principal in [None, Some(viewer.clone()), Some(operator.clone())] {
+        let allowed = transport
+            .handle_incoming(request_as("hello", "get", principal))
+            .await;
+        assert!(allowed.is_ok());
+    }
+    for principal in [None, Some(viewer)] {
+        let denied = transport
+            .handle_incoming(request_as("admin", "reboot", principal))
+            .await;
+        assert!(matches!(denied, Err(RpcError::PermissionDenied(_))));
+    }
+    let allowed = transport
+        .handle_incoming(request_as("admin", "reboot", Some(operator)))
+        .await;
+    assert!(allowed.is_ok());
+}
+
+#[test]
+fn method_rules_match_dotted_service_names() {
+    let policy = AccessPolicy::new([AccessRule::new()
+        .principals(["*"])
+        .methods(["net.wifi.scan", "sys.power.*"])]);
+
+    assert!(policy.allows_method(None, "net.wifi", "scan"));
+    assert!(!policy.allows_method(None, "net.wifi", "connect"));
+    assert!(!policy.allows_method(None, "net", "wifi"));
+    assert!(policy.allows_method(None, "sys.power", "reboot"));
+    assert!(policy.allows_method(None, "sys", "power.off"));
+    assert!(!policy.allows_method(None, "sys.powerd", "reboot"));
+    assert!(!policy.allows_method(None, "sys", "power"));
+}
+
+#[test]
+fn publisher_enforces_topic_rules() {
+    let transport = MockTransport::new();
+    let publisher = ClientEventPublisher::new(transport.events(), TopicAllowlist::new(["*"]))
+        .with_policy(Some(policy()));
+    let event = |topic: &str| TransportEvent {
+        topic: topic.to_string(),
+        payload: Vec::new(),
+    };
+
+    assert!(publisher.publish(event("phone/battery")).is_ok());
+    assert!(matches!(
+        publisher.publish(event("admin/reboot")),
+        Err(EventError::PermissionDenied(_))
+    ));
+    let operator = Principal::new("tablet").with_roles(["admin"]);
+    assert!(publisher
+        .publish_as(Some(&operator), event("admin/reboot"))
+        .is_ok());
+}
+
+#[tokio::test]
+async fn http_maps_denials_to_403() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [[acl]]
+        principals = ["*"]
+        methods = ["hello.*"]
+        topics = ["phone/*"]
+
+        [[acl]]
+        roles = ["admin"]
+        methods = ["*"]
+        topics = ["*"]
+        "#,
+    )
+    .expect("config");
+    let policy = Arc::new(config.acl.expect("acl configured"));
+    assert_eq!(policy.rules().len(), 2);
+    assert!(AppConfig::default().acl.is_none());
+
+    let transport = transport_with(policy.clone());
+    let authenticator = StaticTokenAuthenticator::new()
+        .with_token("viewer", Principal::new("phone"))
+        .with_token("operator", Principal::new("tablet").with_roles(["admin"]));
+    let server = HttpServerTransport::new(transport.registry())
+        .with_authenticator(Arc::new(authenticator))
+        .with_access_policy(policy)
+        .with_events(transport.events(), TopicAllowlist::new(["*"]));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let client = reqwest::Client::new();
+    let request = HttpRpcRequest {
+        service: "admin".to_string(),
+        method: "reboot".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    let response = client
+        .post(format!("http://{addr}/rpc"))
+        .bearer_auth("viewer")
+        .json(&request)
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: HttpRpcResponse = response.json().await.expect("json body");
+    assert_eq!(body.error.expect("error").code, "permission_denied");
+
+    let response = client
+        .post(format!("http://{addr}/rpc"))
+        .bearer_auth("operator")
+        .json(&request)
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // The listing route is `rpc.list` and needs the same permission.
+    for (token, status) in [
+        ("viewer", reqwest::StatusCode::FORBIDDEN),
+        ("operator", reqwest::StatusCode::OK),
+    ] {
+        let response = client
+            .get(format!("http://{addr}/rpc/methods"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("response");
+        assert_eq!(response.status(), status, "{token}");
+    }
+
+    let mut upgrade = format!("ws://{addr}/events")
+        .into_client_request()
+        .expect("ws request");
+    upgrade.headers_mut().insert(
+        "Authorization",
+        "Bearer viewer".parse().expect("header value"),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(upgrade)
+        .await
+        .expect("websocket connect");
+    let message = HttpEventMessage::Publish {
+        topic: "admin/reboot".to_string(),
+        payload_b64: general_purpose::STANDARD.encode(b"now"),
+    };
+    socket
+        .send(Message::Text(
+            serde_json::to_string(&message).expect("serialize"),
+        ))
+        .await
+        .expect("send publish");
+    let code = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let frame = socket.next().await.expect("frame").expect("frame ok");
+            let Message::Text(text) = frame else { continue };
+            if let HttpEventMessage::Error { code, .. } =
+                serde_json::from_str(&text).expect("decode")
+            {
+                break code;
+            }
+        }
+    })
+    .await
+    .expect("error reply");
+    assert_eq!(code, "permission_denied");
+
+    server_task.abort();
+    let _ = server_task.await;
+}
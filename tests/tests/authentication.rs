@@ -0,0 +1,178 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use base64::{engine::general_purpose, Engine as _};
+use service_core::{
+    router::{rpc_handler, Principal, RpcRegistry, RpcRequest, RpcResponse},
+    AppConfig, AuthError, Authenticator, HmacTokenAuthenticator, StaticTokenAuthenticator,
+};
+use service_transport::{
+    ble::{client::BleClient, link::BleLink, protocol::BleRpcRequest, BleTransport},
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+
+/// Registers `auth.whoami`, which returns the caller's principal id.
+fn register_whoami(registry: &Arc<dyn RpcRegistry>) {
+    let handler = rpc_handler(|req: RpcRequest| async move {
+        let id = req
+            .context
+            .principal
+            .map(|principal| principal.id)
+            .unwrap_or_default();
+        Ok(RpcResponse {
+            payload: id.into_bytes(),
+        })
+    });
+    registry
+        .register("auth", "whoami", handler)
+        .expect("register handler");
+}
+
+#[tokio::test]
+async fn http_requires_bearer_token() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    register_whoami(&registry);
+    let authenticator =
+        StaticTokenAuthenticator::new().with_token("s3cret", Principal::new("phone"));
+
+    let server = HttpServerTransport::new(registry).with_authenticator(Arc::new(authenticator));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let client = reqwest::Client::new();
+    let request = HttpRpcRequest {
+        service: "auth".to_string(),
+        method: "whoami".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    for authorization in [None, Some("Bearer wrong"), Some("Basic s3cret")] {
+        let mut call = client.post(format!("http://{addr}/rpc")).json(&request);
+        if let Some(authorization) = authorization {
+            call = call.header("Authorization", authorization);
+        }
+        let response = call.send().await.expect("response");
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let body: HttpRpcResponse = response.json().await.expect("json body");
+        assert_eq!(body.error.expect("error").code, "unauthenticated");
+    }
+    let listing = client
+        .get(format!("http://{addr}/rpc/methods"))
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(listing.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let body: HttpRpcResponse = client
+        .post(format!("http://{addr}/rpc"))
+        .bearer_auth("s3cret")
+        .json(&request)
+        .send()
+        .await
+        .expect("response")
+        .json()
+        .await
+        .expect("json body");
+    assert!(body.error.is_none());
+    let payload = general_purpose::STANDARD
+        .decode(body.payload_b64)
+        .expect("decode payload");
+    assert_eq!(payload, b"phone");
+
+    server_task.abort();
+    let _ = server_task.await;
+}
+
+#[test]
+fn hmac_tokens_verify_signature_and_expiry() {
+    let authenticator = HmacTokenAuthenticator::new(b"shared-key".to_vec());
+    let principal = Principal::new("tablet").with_roles(["admin"]);
+
+    let token = authenticator.issue(&principal, None);
+    assert_eq!(authenticator.authenticate(&token), Ok(principal.clone()));
+
+    let other_key = HmacTokenAuthenticator::new(b"other-key".to_vec());
+    assert_eq!(other_key.authenticate(&token), Err(AuthError::Invalid));
+    let (claims, _) = token.split_once('.').expect("two parts");
+    let forged = format!("{claims}.AAAA");
+    assert_eq!(authenticator.authenticate(&forged), Err(AuthError::Invalid));
+
+    let expired = authenticator.issue(&principal, Some(1));
+    assert_eq!(
+        authenticator.authenticate(&expired),
+        Err(AuthError::Expired)
+    );
+}
+
+#[tokio::test]
+async fn ble_requires_handshake() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    register_whoami(&registry);
+    let authenticator = HmacTokenAuthenticator::new(b"shared-key".to_vec());
+    let token = authenticator.issue(&Principal::new("watch"), None);
+
+    let ble = BleTransport::new(registry).with_authenticator(Arc::new(authenticator));
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let request = BleRpcRequest {
+        service: "auth".to_string(),
+        method: "whoami".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 0,
+        stream: false,
+        request_id: None,
+        metadata: BTreeMap::new(),
+    };
+    let response = client.call(&request).await.expect("response");
+    assert_eq!(response.error.expect("error").code, "unauthenticated");
+
+    let rejected = client.authenticate("not-a-token").await.expect("response");
+    assert_eq!(rejected.error.expect("error").code, "unauthenticated");
+
+    let accepted = client.authenticate(&token).await.expect("response");
+    assert!(accepted.error.is_none());
+    let response = client.call(&request).await.expect("response");
+    let payload = general_purpose::STANDARD
+        .decode(response.payload_b64)
+        .expect("decode payload");
+    assert_eq!(payload, b"watch");
+
+    drop(client);
+    server_task.await.expect("serve exits");
+}
+
+#[test]
+fn auth_config_accepts_static_and_hmac_tokens() {
+    let config = AppConfig::from_toml_str(
+        "[auth]\nhmac_key = \"shared-key\"\n\n\
+         [[auth.tokens]]\ntoken = \"s3cret\"\nprincipal = \"dashboard\"\nroles = [\"admin\"]",
+    )
+    .expect("config");
+    let authenticator = config.auth.expect("auth section").authenticator();
+
+    assert_eq!(
+        authenticator.authenticate("s3cret"),
+        Ok(Principal::new("dashboard").with_roles(["admin"]))
+    );
+    let token =
+        HmacTokenAuthenticator::new(b"shared-key".to_vec()).issue(&Principal::new("tablet"), None);
+    assert_eq!(
+        authenticator.authenticate(&token),
+        Ok(Principal::new("tablet"))
+    );
+    assert_eq!(authenticator.authenticate("wrong"), Err(AuthError::Invalid));
+
+    assert!(AppConfig::from_toml_str("[auth]").is_err());
+    assert!(AppConfig::from_toml_str("[auth]\nhmac_key = \"\"").is_err());
+    assert!(AppConfig::from_toml_str("[[auth.tokens]]\ntoken = \"\"\nprincipal = \"a\"").is_err());
+}
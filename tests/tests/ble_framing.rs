@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use service_transport::ble::framing::{
+    split_message, BleFrame, BleMessage, FrameKind, FramingError, Reassembler, FLAG_FIRST,
+    MAX_PARTIAL_MESSAGES,
+};
+
+fn first_frame(msg_id: u32, total_len: u32) -> BleFrame {
+    BleFrame {
+        msg_id,
+        kind: FrameKind::Request,
+        flags: FLAG_FIRST,
+        seq: 0,
+        total_len,
+        chunk: vec![0; 4],
+    }
+}
+
+#[test]
+fn reassembly_is_bounded_per_peer() {
+    let mut reassembler = Reassembler::new(32 * 1024, Duration::from_secs(5));
+    let now = Instant::now();
+
+    // Tiny first frames announcing the largest allowed message.
+    for msg_id in 0..MAX_PARTIAL_MESSAGES as u32 {
+        assert_eq!(
+            reassembler.push(first_frame(msg_id, 32 * 1024), now),
+            Ok(None)
+        );
+    }
+    assert_eq!(
+        reassembler.push(first_frame(100, 32 * 1024), now),
+        Err(FramingError::TooManyPartial { msg_id: 100 })
+    );
+
+    // Completing or expiring a message frees its slot.
+    let frames = split_message(0, FrameKind::Request, b"done", 64);
+    assert_eq!(
+        reassembler.push(frames.into_iter().next().expect("frame"), now),
+        Ok(Some(BleMessage {
+            msg_id: 0,
+            kind: FrameKind::Request,
+            payload: b"done".to_vec(),
+        }))
+    );
+    assert_eq!(reassembler.push(first_frame(100, 32 * 1024), now), Ok(None));
+    assert_eq!(
+        reassembler.expire(now + Duration::from_secs(5)).len(),
+        MAX_PARTIAL_MESSAGES
+    );
+    assert_eq!(reassembler.push(first_frame(101, 8), now), Ok(None));
+}
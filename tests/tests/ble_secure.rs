@@ -0,0 +1,247 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose, Engine as _};
+use service_core::{
+    router::{rpc_handler, Principal, RpcRequest, RpcResponse},
+    AppConfig, StaticTokenAuthenticator,
+};
+use service_transport::{
+    ble::{
+        client::{BleClient, BleClientError},
+        framing::FrameKind,
+        link::BleLink,
+        protocol::BleRpcRequest,
+        secure::{FilePairingStore, MemoryPairingStore, PairingStore, SecurePairing},
+        BleTransport,
+    },
+    mock::MockTransport,
+};
+
+fn echo_transport() -> MockTransport {
+    let transport = MockTransport::new();
+    let handler = rpc_handler(|req: RpcRequest| async move {
+        Ok(RpcResponse {
+            payload: req.payload,
+        })
+    });
+    transport
+        .registry()
+        .register("echo", "say", handler)
+        .expect("register handler");
+    transport
+}
+
+fn echo_request(text: &str) -> BleRpcRequest {
+    BleRpcRequest {
+        service: "echo".to_string(),
+        method: "say".to_string(),
+        payload_b64: general_purpose::STANDARD.encode(text),
+        timeout_ms: 0,
+        stream: false,
+        request_id: None,
+        metadata: BTreeMap::new(),
+    }
+}
+
+async fn echo(client: &mut BleClient, text: &str) -> Vec<u8> {
+    let response = client.call(&echo_request(text)).await.expect("response");
+    assert!(response.error.is_none(), "{:?}", response.error);
+    general_purpose::STANDARD
+        .decode(response.payload_b64)
+        .expect("decode payload")
+}
+
+#[tokio::test]
+async fn paired_central_resumes_encrypted_session() {
+    let transport = echo_transport();
+    let store = Arc::new(MemoryPairingStore::new());
+    let ble = BleTransport::new(transport.registry())
+        .with_secure_pairing(SecurePairing::new("482913", store.clone()));
+
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 48);
+    let device = ble.clone();
+    let server_task = tokio::spawn(async move { device.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let response = client.call(&echo_request("hi")).await.expect("response");
+    assert_eq!(response.error.expect("error").code, "unauthenticated");
+
+    let pairing_key = client.pair("phone-1", "482913").await.expect("pair");
+    assert!(store.get("phone-1").is_none(), "stored only once confirmed");
+    assert_eq!(
+        echo(&mut client, "a longer message over several frames").await,
+        b"a longer message over several frames"
+    );
+    assert_eq!(store.get("phone-1"), Some(pairing_key));
+    drop(client);
+    server_task.await.expect("serve exits");
+
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 48);
+    let device = ble.clone();
+    let server_task = tokio::spawn(async move { device.serve(link).await });
+    let mut client = BleClient::new(central);
+    assert!(matches!(
+        client.resume("phone-2", &pairing_key).await,
+        Err(BleClientError::Pairing(_))
+    ));
+    client
+        .resume("phone-1", &pairing_key)
+        .await
+        .expect("resume");
+    assert_eq!(echo(&mut client, "again").await, b"again");
+    drop(client);
+    server_task.await.expect("serve exits");
+}
+
+#[tokio::test]
+async fn wrong_pairing_code_is_rejected_and_disconnected() {
+    let transport = echo_transport();
+    let store = Arc::new(MemoryPairingStore::new());
+    let ble = BleTransport::new(transport.registry())
+        .with_secure_pairing(SecurePairing::new("482913", store.clone()));
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    assert!(matches!(
+        client.pair("intruder", "000000").await,
+        Err(BleClientError::Pairing(_))
+    ));
+    // Messages the device cannot open count against the link.
+    for _ in 0..3 {
+        client
+            .send(FrameKind::Request, &echo_request("guess"))
+            .await
+            .expect("send");
+    }
+    tokio::time::timeout(Duration::from_secs(5), server_task)
+        .await
+        .expect("device drops the link")
+        .expect("serve exits");
+    assert!(store.get("intruder").is_none());
+}
+
+#[tokio::test]
+async fn pairing_again_requires_a_new_handshake() {
+    let transport = echo_transport();
+    let store = Arc::new(MemoryPairingStore::new());
+    let authenticator = StaticTokenAuthenticator::new().with_token(
+        "operator-token",
+        Principal::new("operator").with_roles(["admin"]),
+    );
+    let ble = BleTransport::new(transport.registry())
+        .with_authenticator(Arc::new(authenticator))
+        .with_secure_pairing(SecurePairing::new("482913", store.clone()));
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let pairing_key = client.pair("phone-1", "482913").await.expect("pair");
+    let response = client
+        .authenticate("operator-token")
+        .await
+        .expect("handshake");
+    assert!(response.error.is_none(), "{:?}", response.error);
+    assert_eq!(echo(&mut client, "hi").await, b"hi");
+
+    // A fresh session on the same link starts unauthenticated.
+    client
+        .resume("phone-1", &pairing_key)
+        .await
+        .expect("resume");
+    let response = client.call(&echo_request("hi")).await.expect("response");
+    assert_eq!(response.error.expect("error").code, "unauthenticated");
+
+    drop(client);
+    server_task.await.expect("serve exits");
+}
+
+#[tokio::test]
+async fn pairing_attempts_are_limited_across_links() {
+    let transport = echo_transport();
+    let store = Arc::new(MemoryPairingStore::new());
+    let ble = BleTransport::new(transport.registry()).with_secure_pairing(
+        SecurePairing::new("482913", store.clone()).with_lockout(Duration::from_millis(300)),
+    );
+
+    async fn attempt(ble: &BleTransport, code: &str) -> Result<(), BleClientError> {
+        let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+        let device = ble.clone();
+        let server_task = tokio::spawn(async move { device.serve(link).await });
+        let mut client = BleClient::new(central);
+        let paired = client.pair("phone-1", code).await;
+        if paired.is_ok() {
+            assert_eq!(echo(&mut client, "hi").await, b"hi");
+        }
+        drop(client);
+        server_task.await.expect("serve exits");
+        paired.map(|_| ())
+    }
+
+    // Each guess gets a fresh link, so the per-link failure limit never trips.
+    for guess in 0..5 {
+        assert!(attempt(&ble, &format!("{guess:06}")).await.is_err());
+    }
+    match attempt(&ble, "482913").await {
+        Err(BleClientError::Pairing(reason)) => {
+            assert!(reason.contains("unauthenticated"), "{reason}")
+        }
+        other => panic!("expected the code to be locked, got {other:?}"),
+    }
+    assert!(store.get("phone-1").is_none());
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    attempt(&ble, "482913").await.expect("pair after lockout");
+    assert!(store.get("phone-1").is_some());
+}
+
+#[test]
+fn file_pairing_store_persists_keys() {
+    let path = std::env::temp_dir().join(format!("service-pairings-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let store = FilePairingStore::open(&path).expect("open empty store");
+    store.insert("phone-1", [7; 32]).expect("insert");
+    store.insert("phone-2", [9; 32]).expect("insert");
+    assert!(store.remove("phone-2").expect("remove"));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        let mode = std::fs::metadata(&path)
+            .expect("metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600, "pairing keys readable by others");
+    }
+
+    let reopened = FilePairingStore::open(&path).expect("reopen store");
+    assert_eq!(reopened.get("phone-1"), Some([7; 32]));
+    assert_eq!(reopened.get("phone-2"), None);
+    std::fs::remove_file(&path).expect("clean up");
+}
+
+#[test]
+fn pairing_settings_come_from_the_ble_section() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [ble.pairing]
+        code = "482913"
+        store_path = "/var/lib/service/pairings.json"
+        "#,
+    )
+    .expect("config");
+    let pairing = config.ble_pairing.expect("pairing configured");
+    assert_eq!(pairing.code.as_deref(), Some("482913"));
+    assert_eq!(
+        pairing.store_path,
+        std::path::Path::new("/var/lib/service/pairings.json")
+    );
+    assert!(AppConfig::default().ble_pairing.is_none());
+
+    let random = AppConfig::from_toml_str("[ble.pairing]\nstore_path = \"p.json\"")
+        .expect("config without a code");
+    assert_eq!(random.ble_pairing.expect("pairing").code, None);
+    assert!(AppConfig::from_toml_str("[ble.pairing]\ncode = \"123\"\nstore_path = \"p\"").is_err());
+    assert!(AppConfig::from_toml_str("[ble.pairing]\ncode = \"123456\"").is_err());
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine as _};
+use futures_util::{SinkExt, StreamExt};
+use service_core::{
+    event::{TopicAllowlist, TransportEvent},
+    AppConfig,
+};
+use service_transport::{
+    ble::{
+        client::BleClient,
+        framing::FrameKind,
+        link::BleLink,
+        protocol::{BleEvent, TRANSPORT_ERROR_TOPIC},
+        BleTransport,
+    },
+    http::{protocol::HttpEventMessage, HttpServerTransport},
+    mock::MockTransport,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+#[tokio::test]
+async fn websocket_client_publishes_allowed_topics() {
+    let transport = MockTransport::new();
+    let events = transport.events();
+    let mut bus = events.subscribe();
+
+    let server = HttpServerTransport::new(transport.registry())
+        .with_events(events.clone(), TopicAllowlist::new(["phone/*"]));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/events"))
+        .await
+        .expect("websocket connect");
+
+    let publish = |topic: &str| {
+        let message = HttpEventMessage::Publish {
+            topic: topic.to_string(),
+            payload_b64: general_purpose::STANDARD.encode(b"42%"),
+        };
+        Message::Text(serde_json::to_string(&message).expect("serialize"))
+    };
+
+    socket
+        .send(publish("phone/battery"))
+        .await
+        .expect("send publish");
+    let event = bus.recv().await.expect("bus event");
+    assert_eq!(event.topic, "phone/battery");
+    assert_eq!(event.payload, b"42%");
+
+    socket
+        .send(publish("admin/reboot"))
+        .await
+        .expect("send publish");
+    let reply = loop {
+        let frame = socket.next().await.expect("frame").expect("frame ok");
+        let Message::Text(text) = frame else { continue };
+        match serde_json::from_str(&text).expect("decode") {
+            HttpEventMessage::Error { code, .. } => break code,
+            _ => continue,
+        }
+    };
+    assert_eq!(reply, "topic_not_allowed");
+
+    server_task.abort();
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn ble_client_publishes_allowed_topics() {
+    let transport = MockTransport::new();
+    let events = transport.events();
+    let mut bus = events.subscribe();
+
+    let ble = BleTransport::new(transport.registry())
+        .with_events(events.clone(), TopicAllowlist::new(["phone/*"]));
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 23);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let payload = vec![7u8; 64];
+    client
+        .publish("phone/notification", &payload)
+        .await
+        .expect("publish");
+    let event = bus.recv().await.expect("bus event");
+    assert_eq!(event.topic, "phone/notification");
+    assert_eq!(event.payload, payload);
+
+    client.publish("admin/reboot", b"").await.expect("publish");
+    let rejection = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            let event = client.next_event().await.expect("event");
+            if event.topic == TRANSPORT_ERROR_TOPIC {
+                break event;
+            }
+        }
+    })
+    .await
+    .expect("transport error event");
+    let body = general_purpose::STANDARD
+        .decode(rejection.payload_b64)
+        .expect("decode payload");
+    let body: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+    assert_eq!(body["kind"], "event");
+
+    drop(client);
+    server_task.await.expect("serve exits on disconnect");
+}
+
+#[tokio::test]
+async fn ble_event_messages_get_distinct_ids() {
+    let transport = MockTransport::new();
+    let events = transport.events();
+    let ble = BleTransport::new(transport.registry())
+        .with_events(events.clone(), TopicAllowlist::new(["phone/*"]));
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 23);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    // The rejection also proves the link is served, so the bus event below
+    // is not published before the link subscribes.
+    let rejected = BleEvent {
+        topic: "admin/reboot".to_string(),
+        payload_b64: String::new(),
+    };
+    let client_msg_id = client
+        .send(FrameKind::Event, &rejected)
+        .await
+        .expect("publish");
+    let error = client.recv_message().await.expect("error event");
+
+    events
+        .publish(TransportEvent {
+            topic: "system/status".to_string(),
+            payload: b"up".to_vec(),
+        })
+        .expect("publish");
+    let status = client.recv_message().await.expect("status event");
+
+    assert_eq!(status.kind, FrameKind::Event);
+    assert_eq!(error.kind, FrameKind::Event);
+    assert_ne!(status.msg_id, error.msg_id);
+    let error: BleEvent = serde_json::from_slice(&error.payload).expect("event body");
+    assert_eq!(error.topic, TRANSPORT_ERROR_TOPIC);
+    let body = general_purpose::STANDARD
+        .decode(error.payload_b64)
+        .expect("decode payload");
+    let body: serde_json::Value = serde_json::from_slice(&body).expect("json body");
+    assert_eq!(body["msg_id"], client_msg_id);
+
+    drop(client);
+    server_task.await.expect("serve exits on disconnect");
+}
+
+#[test]
+fn client_topics_come_from_the_events_section() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [events]
+        client_topics = ["phone/*", "watch/steps"]
+        "#,
+    )
+    .expect("config");
+    let allowlist = TopicAllowlist::new(config.events_client_topics);
+    assert!(allowlist.allows("phone/battery"));
+    assert!(allowlist.allows("watch/steps"));
+    assert!(!allowlist.allows("system/reboot"));
+    assert!(AppConfig::default().events_client_topics.is_empty());
+
+    for pattern in ["", "/*", "phone/*/battery", "phone*"] {
+        let text = format!("[events]\nclient_topics = [{pattern:?}]");
+        assert!(AppConfig::from_toml_str(&text).is_err(), "{pattern:?}");
+    }
+}
@@ -0,0 +1,166 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use service_core::{
+    router::{rpc_handler, ConcurrencyLimit, MethodDescriptor, RpcError, RpcRequest, RpcResponse},
+    AppConfig, InMemoryRouter,
+};
+use service_transport::{
+    ble::{client::BleClient, link::BleLink, protocol::BleRpcRequest, BleTransport},
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+use tokio::sync::{mpsc, Semaphore};
+
+/// Handler that reports when a call starts and then waits for the gate.
+struct Gated {
+    gate: Arc<Semaphore>,
+    started: mpsc::UnboundedReceiver<()>,
+}
+
+fn register_slow(transport: &MockTransport, descriptor: MethodDescriptor) -> Gated {
+    let gate = Arc::new(Semaphore::new(0));
+    let (started_tx, started) = mpsc::unbounded_channel();
+    let handler_gate = gate.clone();
+    let handler = rpc_handler(move |_req: RpcRequest| {
+        let gate = handler_gate.clone();
+        let started = started_tx.clone();
+        async move {
+            let _ = started.send(());
+            gate.acquire().await.expect("gate open").forget();
+            Ok(RpcResponse {
+                payload: b"done".to_vec(),
+            })
+        }
+    });
+    transport
+        .registry()
+        .register_described(descriptor, handler)
+        .expect("register");
+    Gated { gate, started }
+}
+
+fn slow_request() -> RpcRequest {
+    RpcRequest::new("slow", "run", Vec::new(), 0)
+}
+
+#[tokio::test]
+async fn excess_calls_queue_then_shed() {
+    let transport = MockTransport::new();
+    let mut slow = register_slow(
+        &transport,
+        MethodDescriptor::new("slow", "run").limit(ConcurrencyLimit::new(1).with_queue_depth(1)),
+    );
+
+    let running = tokio::spawn(transport.registry().dispatch(slow_request()));
+    slow.started.recv().await.expect("first call started");
+    let queued = tokio::spawn(transport.registry().dispatch(slow_request()));
+    tokio::task::yield_now().await;
+
+    let shed = transport.handle_incoming(slow_request()).await;
+    assert!(matches!(shed, Err(RpcError::Overloaded)));
+
+    slow.gate.add_permits(2);
+    assert!(running.await.expect("join").is_ok());
+    slow.started.recv().await.expect("queued call started");
+    assert!(queued.await.expect("join").is_ok());
+
+    let described = transport
+        .registry()
+        .methods()
+        .into_iter()
+        .find(|method| method.service == "slow")
+        .expect("listed");
+    assert_eq!(
+        described.limit,
+        Some(ConcurrencyLimit::new(1).with_queue_depth(1))
+    );
+}
+
+#[tokio::test]
+async fn configured_limit_maps_to_http_503() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [transport]
+        mode = "http"
+
+        [limits."slow.run"]
+        max_concurrency = 1
+        "#,
+    )
+    .expect("config");
+    assert_eq!(config.transport, "http");
+    assert!(AppConfig::from_toml_str("[limits.\"a.b\"]\nmax_concurrency = 0").is_err());
+
+    let transport = MockTransport::with_router(InMemoryRouter::new().with_limits(config.limits));
+    // The descriptor asks for more; config wins.
+    let mut slow = register_slow(
+        &transport,
+        MethodDescriptor::new("slow", "run").limit(ConcurrencyLimit::new(8)),
+    );
+    let running = tokio::spawn(transport.registry().dispatch(slow_request()));
+    slow.started.recv().await.expect("first call started");
+
+    let server = HttpServerTransport::new(transport.registry());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let request = HttpRpcRequest {
+        service: "slow".to_string(),
+        method: "run".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    let response = reqwest::Client::new()
+        .post(format!("http://{addr}/rpc"))
+        .json(&request)
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    let body: HttpRpcResponse = response.json().await.expect("json body");
+    assert_eq!(body.error.expect("error").code, "overloaded");
+
+    slow.gate.add_permits(1);
+    assert!(running.await.expect("join").is_ok());
+    server_task.abort();
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn ble_reports_overloaded_error() {
+    let transport = MockTransport::new();
+    let mut slow = register_slow(
+        &transport,
+        MethodDescriptor::new("slow", "run").limit(ConcurrencyLimit::new(1)),
+    );
+    let running = tokio::spawn(transport.registry().dispatch(slow_request()));
+    slow.started.recv().await.expect("first call started");
+
+    let ble = BleTransport::new(transport.registry());
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let request = BleRpcRequest {
+        service: "slow".to_string(),
+        method: "run".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 0,
+        stream: false,
+        request_id: None,
+        metadata: BTreeMap::new(),
+    };
+    let response = client.call(&request).await.expect("response");
+    assert_eq!(response.error.expect("error").code, "overloaded");
+
+    slow.gate.add_permits(1);
+    assert!(running.await.expect("join").is_ok());
+    drop(client);
+    server_task.await.expect("serve exits");
+}
@@ -0,0 +1,273 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose, Engine as _};
+use futures_util::StreamExt as _;
+use service_core::{
+    router::{rpc_handler, RpcRequest, RpcResponse},
+    AppConfig, EnabledTransports, Error, InMemoryRouter, TopicAllowlist, TransportEvent,
+    TransportSupervisor, UnixSocketConfig,
+};
+use service_transport::{
+    ble::{client::BleClient, link::BleLink, protocol::BleRpcRequest, BleTransport},
+    http::{
+        client::{HttpClient, HttpClientError},
+        protocol::HttpRpcRequest,
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+use tokio::sync::{mpsc, Mutex};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+fn http_request() -> HttpRpcRequest {
+    HttpRpcRequest {
+        service: "peer".to_string(),
+        method: "transport".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    }
+}
+
+async fn transport_over(client: &HttpClient) -> Result<String, HttpClientError> {
+    let response = client.call(&http_request()).await?;
+    let payload = general_purpose::STANDARD
+        .decode(response.payload_b64)
+        .expect("base64");
+    Ok(String::from_utf8(payload).expect("utf-8"))
+}
+
+/// Retry until the transport behind `client` accepts calls.
+async fn wait_until_served(client: &HttpClient) {
+    for _ in 0..100 {
+        if transport_over(client).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("{:?} never served", client.endpoint());
+}
+
+#[test]
+fn transports_are_enabled_individually() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [transport.enable]
+        http = true
+        local = true
+
+        [http.unix]
+        path = "/run/service.sock"
+        "#,
+    )
+    .expect("config");
+    assert_eq!(
+        config.transport_enable,
+        Some(EnabledTransports {
+            http: true,
+            ble: false,
+            local: true,
+        })
+    );
+    assert!(AppConfig::default().transport_enable.is_none());
+    assert!(AppConfig::from_toml_str("[transport.enable]\nlocal = true").is_err());
+}
+
+#[test]
+fn unknown_transport_modes_are_rejected() {
+    for mode in ["auto", "http", "ble", "mock"] {
+        let config =
+            AppConfig::from_toml_str(&format!("[transport]\nmode = {mode:?}")).expect("known mode");
+        assert_eq!(config.transport, mode);
+    }
+    assert!(AppConfig::from_toml_str("[transport]\nmode = \"wifi\"").is_err());
+}
+
+#[tokio::test]
+async fn transports_share_one_router_and_stop_independently() {
+    let transport = MockTransport::with_router(InMemoryRouter::new());
+    let registry = transport.registry();
+    let events = transport.events();
+    let handler = rpc_handler(|req: RpcRequest| async move {
+        Ok(RpcResponse {
+            payload: req.context.transport.unwrap_or_default().into_bytes(),
+        })
+    });
+    registry
+        .register("peer", "transport", handler)
+        .expect("register");
+
+    let supervisor = TransportSupervisor::new();
+    let http = HttpServerTransport::new(registry.clone());
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("free port");
+    let server = http.clone();
+    supervisor
+        .add("http", move || {
+            let server = server.clone();
+            async move {
+                server
+                    .serve(addr)
+                    .await
+                    .map_err(|err| Error::Transport(err.to_string()))
+            }
+        })
+        .expect("add http");
+
+    let path = std::env::temp_dir().join(format!("service-{}-local.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let unix = UnixSocketConfig::new(&path);
+    supervisor
+        .add("local", move || {
+            let (server, unix) = (http.clone(), unix.clone());
+            async move {
+                server
+                    .serve_unix(&unix)
+                    .await
+                    .map_err(|err| Error::Transport(err.to_string()))
+            }
+        })
+        .expect("add local");
+
+    let ble =
+        BleTransport::new(registry.clone()).with_events(events.clone(), TopicAllowlist::default());
+    let (link_sender, links) = mpsc::channel(4);
+    let links = Arc::new(Mutex::new(links));
+    supervisor
+        .add("ble", move || {
+            let (ble, links) = (ble.clone(), links.clone());
+            async move {
+                ble.serve_links(&mut *links.lock().await).await;
+                Ok(())
+            }
+        })
+        .expect("add ble");
+    assert!(supervisor.add("ble", || async { Ok(()) }).is_err());
+
+    supervisor.start_all().expect("start");
+    assert_eq!(supervisor.running(), ["ble", "http", "local"]);
+
+    let tcp = HttpClient::tcp(addr);
+    let local = HttpClient::unix(&path);
+    wait_until_served(&tcp).await;
+    wait_until_served(&local).await;
+    assert_eq!(transport_over(&tcp).await.expect("tcp call"), "http");
+    assert_eq!(transport_over(&local).await.expect("local call"), "http");
+
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    link_sender.send(link).await.expect("hand over link");
+    let mut central = BleClient::new(central);
+    let response = central
+        .call(&BleRpcRequest {
+            service: "peer".to_string(),
+            method: "transport".to_string(),
+            payload_b64: String::new(),
+            timeout_ms: 1_000,
+            stream: false,
+            request_id: None,
+            metadata: BTreeMap::new(),
+        })
+        .await
+        .expect("ble call");
+    assert_eq!(
+        response.payload_b64,
+        general_purpose::STANDARD.encode("ble")
+    );
+
+    // Events on the shared bus reach the BLE central.
+    events
+        .publish(TransportEvent {
+            topic: "system/status".to_string(),
+            payload: b"up".to_vec(),
+        })
+        .expect("publish");
+    let event = central.next_event().await.expect("event");
+    assert_eq!(event.topic, "system/status");
+
+    // Stopping one transport leaves the others serving.
+    supervisor.stop("local").await.expect("stop local");
+    assert!(!supervisor.is_running("local"));
+    assert!(matches!(
+        transport_over(&local).await,
+        Err(HttpClientError::Io(_))
+    ));
+    assert_eq!(transport_over(&tcp).await.expect("tcp call"), "http");
+
+    supervisor.start("local").expect("restart local");
+    wait_until_served(&local).await;
+    assert!(supervisor.start("bluetooth").is_err());
+
+    supervisor.stop_all().await;
+    assert!(supervisor.running().is_empty());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn failed_transports_are_reported_to_the_handler() {
+    let (failures, mut failed) = mpsc::unbounded_channel();
+    let supervisor = TransportSupervisor::new().with_failure_handler(move |id, err| {
+        let _ = failures.send((id, err.to_string()));
+    });
+    supervisor
+        .add("http", || async {
+            Err(Error::Transport("address in use".to_string()))
+        })
+        .expect("add http");
+    supervisor.add("ble", || async { Ok(()) }).expect("add ble");
+    supervisor.start_all().expect("start");
+
+    let (id, err) = tokio::time::timeout(Duration::from_secs(2), failed.recv())
+        .await
+        .expect("failure reported")
+        .expect("handler kept");
+    assert_eq!(id, "http");
+    assert!(err.contains("address in use"), "{err}");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(failed.try_recv().is_err());
+    assert!(supervisor.running().is_empty());
+}
+
+#[tokio::test]
+async fn stopping_http_closes_open_websockets() {
+    let transport = MockTransport::new();
+    let server = HttpServerTransport::new(transport.registry())
+        .with_events(transport.events(), TopicAllowlist::default());
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("free port");
+    let supervisor = TransportSupervisor::new();
+    supervisor
+        .add("http", move || {
+            let server = server.clone();
+            async move {
+                server
+                    .serve(addr)
+                    .await
+                    .map_err(|err| Error::Transport(err.to_string()))
+            }
+        })
+        .expect("add http");
+    supervisor.start("http").expect("start");
+
+    let mut socket = None;
+    for _ in 0..100 {
+        if let Ok((connected, _)) = connect_async(format!("ws://{addr}/events")).await {
+            socket = Some(connected);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let mut socket = socket.expect("websocket connected");
+
+    supervisor.stop("http").await.expect("stop http");
+    let closed = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            match socket.next().await {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            }
+        }
+    })
+    .await;
+    assert!(closed.is_ok(), "websocket still open after stop");
+}
@@ -0,0 +1,186 @@
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use service_core::{AppConfig, ProbeConfig, TransportSupervisor};
+use service_platform::{
+    autoselect::follow_wifi,
+    network::NetworkWatcher,
+    wifi::{
+        detector::{Connectivity, ConnectivityProber, ProbeReport},
+        mock::{MockWifiDetector, MockWifiState},
+    },
+};
+use service_transport::mock::MockTransport;
+use tokio::{net::TcpListener, sync::oneshot};
+
+const ROUTES_WITH_DEFAULT: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+
+const ROUTES_WITHOUT_DEFAULT: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+
+/// The kernel's unreachable default route on `lo`, which must not count.
+const IPV6_ROUTES: &str = "\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+
+fn fixture(name: &str, routes: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("service-probe-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("proc/net")).expect("create proc dir");
+    fs::write(root.join("proc/net/route"), routes).expect("write route");
+    fs::write(root.join("proc/net/ipv6_route"), IPV6_ROUTES).expect("write ipv6_route");
+    root
+}
+
+#[tokio::test]
+async fn probes_route_dns_and_tcp_reachability() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let port = listener.local_addr().expect("addr").port();
+    let config = ProbeConfig::new("localhost")
+        .with_port(port)
+        .with_timeout_ms(1000);
+
+    let root = fixture("reachable", ROUTES_WITH_DEFAULT);
+    let mut prober = ConnectivityProber::new(config.clone()).with_root(&root);
+    assert_eq!(prober.state(), None);
+    assert_eq!(
+        prober.probe().await,
+        ProbeReport {
+            default_route: true,
+            dns: true,
+            tcp: Some(true),
+        }
+    );
+    assert!(prober.is_online());
+
+    drop(listener);
+    let report = prober.check().await;
+    assert_eq!(report.tcp, Some(false));
+    assert!(!report.passed());
+
+    let isolated_root = fixture("isolated", ROUTES_WITHOUT_DEFAULT);
+    let isolated = ConnectivityProber::new(config).with_root(&isolated_root);
+    assert_eq!(
+        isolated.check().await,
+        ProbeReport {
+            default_route: false,
+            dns: false,
+            tcp: Some(false),
+        }
+    );
+    let _ = fs::remove_dir_all(root);
+    let _ = fs::remove_dir_all(isolated_root);
+}
+
+#[test]
+fn state_changes_only_after_consecutive_results() {
+    let mut prober = ConnectivityProber::new(ProbeConfig::new("localhost").with_thresholds(2, 3));
+
+    assert_eq!(prober.record(true), Connectivity::Online);
+    // Failures must come three in a row; a pass in between resets them.
+    assert_eq!(prober.record(false), Connectivity::Online);
+    assert_eq!(prober.record(false), Connectivity::Online);
+    assert_eq!(prober.record(true), Connectivity::Online);
+    assert_eq!(prober.record(false), Connectivity::Online);
+    assert_eq!(prober.record(false), Connectivity::Online);
+    assert_eq!(prober.record(false), Connectivity::Offline);
+    assert!(!prober.is_online());
+
+    assert_eq!(prober.record(true), Connectivity::Offline);
+    assert_eq!(prober.record(true), Connectivity::Online);
+}
+
+#[tokio::test]
+async fn network_events_do_not_feed_the_probe_hysteresis() {
+    let supervisor = Arc::new(TransportSupervisor::new());
+    for id in ["http", "ble"] {
+        supervisor
+            .add(id, || async { std::future::pending().await })
+            .expect("add transport");
+    }
+    let root = fixture("follow", ROUTES_WITH_DEFAULT);
+    let prober = ConnectivityProber::new(ProbeConfig::new("localhost").with_thresholds(1, 2))
+        .with_root(&root);
+    let detector = MockWifiDetector::with_state(MockWifiState::Connected {
+        ssid: "office".to_string(),
+        signal_dbm: -50,
+    });
+    let (watcher, events) = NetworkWatcher::mock();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let follower = tokio::spawn({
+        let supervisor = supervisor.clone();
+        async move {
+            let registry = MockTransport::new().registry();
+            let shutdown = async {
+                let _ = stopped.await;
+            };
+            follow_wifi(
+                &supervisor,
+                registry.as_ref(),
+                &detector,
+                Some(watcher),
+                Some(prober),
+                shutdown,
+                |_| {},
+            )
+            .await
+        }
+    });
+    for _ in 0..100 {
+        if supervisor.running() == ["http"] {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(supervisor.running(), ["http"]);
+
+    // The network is now unreachable, but only the probe interval may
+    // count that; a burst of address events must not flip to BLE.
+    fs::write(root.join("proc/net/route"), ROUTES_WITHOUT_DEFAULT).expect("write route");
+    for host in 1..=5 {
+        let address = IpAddr::V4(Ipv4Addr::new(192, 168, 1, host));
+        assert!(events.address_added("wlan0", address).await);
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(supervisor.running(), ["http"]);
+
+    stop.send(()).expect("follower running");
+    follower.await.expect("follower task").expect("follow wifi");
+    supervisor.stop_all().await;
+    let _ = fs::remove_dir_all(root);
+}
+
+#[test]
+fn probe_settings_come_from_the_transport_section() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [transport.probe]
+        host = "updates.example.net"
+        port = 443
+        offline_after = 5
+        "#,
+    )
+    .expect("config");
+    assert_eq!(
+        config.transport_probe,
+        Some(
+            ProbeConfig::new("updates.example.net")
+                .with_port(443)
+                .with_thresholds(2, 5)
+        )
+    );
+    assert!(AppConfig::default().transport_probe.is_none());
+    assert!(AppConfig::from_toml_str("[transport.probe]\nhost = \"\"").is_err());
+    assert!(AppConfig::from_toml_str("[transport.probe]\nhost = \"a\"\nonline_after = 0").is_err());
+}
@@ -0,0 +1,185 @@
+use std::{net::SocketAddr, time::Duration};
+
+use reqwest::StatusCode;
+use service_core::{
+    router::{rpc_handler, RpcError, RpcRequest, RpcResponse},
+    AppConfig, InMemoryRouter,
+};
+use service_transport::{
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+use tokio::sync::oneshot;
+
+/// `checks.validate` rejects any payload naming its field, `checks.lookup`
+/// finds nothing, `checks.backend` is down, and `checks.slow` never answers.
+fn transport() -> MockTransport {
+    let transport = MockTransport::with_router(InMemoryRouter::new());
+    let registry = transport.registry();
+    let handlers = [
+        (
+            "validate",
+            rpc_handler(|_req: RpcRequest| async move {
+                Err(RpcError::InvalidArgument {
+                    field: Some("name".to_string()),
+                    message: "must not be empty".to_string(),
+                })
+            }),
+        ),
+        (
+            "lookup",
+            rpc_handler(|_req: RpcRequest| async move {
+                Err(RpcError::NotFound {
+                    resource: "user 42".to_string(),
+                })
+            }),
+        ),
+        (
+            "backend",
+            rpc_handler(|_req: RpcRequest| async move {
+                Err(RpcError::Unavailable("sensor offline".to_string()))
+            }),
+        ),
+        (
+            "slow",
+            rpc_handler(|_req: RpcRequest| async move {
+                std::future::pending::<()>().await;
+                Ok(RpcResponse {
+                    payload: Vec::new(),
+                })
+            }),
+        ),
+    ];
+    for (method, handler) in handlers {
+        registry
+            .register("checks", method, handler)
+            .expect("register");
+    }
+    transport
+}
+
+async fn serve(server: HttpServerTransport) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move { server.serve_with_listener(listener).await });
+    addr
+}
+
+async fn call(addr: SocketAddr, method: &str, timeout_ms: u64) -> (StatusCode, HttpRpcResponse) {
+    let response = reqwest::Client::new()
+        .post(format!("http://{addr}/rpc"))
+        .json(&HttpRpcRequest {
+            service: "checks".to_string(),
+            method: method.to_string(),
+            payload_b64: String::new(),
+            timeout_ms,
+        })
+        .send()
+        .await
+        .expect("send request");
+    let status = response.status();
+    (status, response.json().await.expect("decode response"))
+}
+
+#[tokio::test]
+async fn errors_carry_stable_codes_and_details() {
+    let invalid = RpcError::InvalidArgument {
+        field: Some("name".to_string()),
+        message: "must not be empty".to_string(),
+    };
+    assert_eq!(invalid.code(), "invalid_argument");
+    assert_eq!(invalid.details()["field"], "name");
+    assert_eq!(
+        invalid.to_string(),
+        "invalid argument name: must not be empty"
+    );
+    let limited = RpcError::RateLimited {
+        retry_after: Duration::from_millis(1_500),
+    };
+    assert_eq!(limited.details()["retry_after_ms"], "1500");
+    assert_eq!(limited.retry_after(), Some(Duration::from_millis(1_500)));
+    assert!(RpcError::Timeout.details().is_empty());
+
+    let transport = transport();
+    let started = tokio::time::Instant::now();
+    let timed_out = transport
+        .handle_incoming(RpcRequest::new("checks", "slow", Vec::new(), 50))
+        .await;
+    assert!(matches!(timed_out, Err(RpcError::Timeout)));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn deadline_cancels_work_spawned_by_the_handler() {
+    let transport = MockTransport::with_router(InMemoryRouter::new());
+    let (cancelled, on_cancel) = oneshot::channel();
+    let cancelled = std::sync::Mutex::new(Some(cancelled));
+    let handler = rpc_handler(move |req: RpcRequest| {
+        let cancelled = cancelled.lock().expect("lock").take();
+        let token = req.context.cancellation().clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            if let Some(cancelled) = cancelled {
+                let _ = cancelled.send(());
+            }
+        });
+        async move {
+            std::future::pending::<()>().await;
+            Ok(RpcResponse {
+                payload: Vec::new(),
+            })
+        }
+    });
+    transport
+        .registry()
+        .register("checks", "spawn", handler)
+        .expect("register");
+
+    let result = transport
+        .handle_incoming(RpcRequest::new("checks", "spawn", Vec::new(), 20))
+        .await;
+    assert!(matches!(result, Err(RpcError::Timeout)));
+    tokio::time::timeout(Duration::from_secs(2), on_cancel)
+        .await
+        .expect("work cancelled")
+        .expect("cancel signal");
+}
+
+#[tokio::test]
+async fn http_status_reflects_error_class_when_enabled() {
+    let transport = transport();
+    let envelope = serve(HttpServerTransport::new(transport.registry())).await;
+    let config = AppConfig::from_toml_str("[http]\nerror_status = true").expect("config");
+    assert!(config.http_error_status);
+    let classified = serve(
+        HttpServerTransport::new(transport.registry()).with_error_status(config.http_error_status),
+    )
+    .await;
+
+    let cases = [
+        ("validate", 0, StatusCode::BAD_REQUEST, "invalid_argument"),
+        ("lookup", 0, StatusCode::NOT_FOUND, "not_found"),
+        ("missing", 0, StatusCode::NOT_FOUND, "unknown_method"),
+        ("backend", 0, StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+        ("slow", 50, StatusCode::GATEWAY_TIMEOUT, "timeout"),
+    ];
+    for (method, timeout_ms, status, code) in cases {
+        let (default_status, body) = call(envelope, method, timeout_ms).await;
+        assert_eq!(default_status, StatusCode::OK, "{method}");
+        assert_eq!(body.error.expect("error").code, code);
+
+        let (classified_status, body) = call(classified, method, timeout_ms).await;
+        assert_eq!(classified_status, status, "{method}");
+        assert_eq!(body.error.expect("error").code, code);
+    }
+
+    let (_, body) = call(classified, "validate", 0).await;
+    assert_eq!(body.error.expect("error").details["field"], "name");
+    let (_, body) = call(classified, "lookup", 0).await;
+    assert_eq!(body.error.expect("error").details["resource"], "user 42");
+}
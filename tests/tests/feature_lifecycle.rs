@@ -0,0 +1,286 @@
+use std::{sync::Arc, time::Duration};
+
+use service_core::{
+    feature::{
+        FeatureFuture, ADMIN_ROLE, ADMIN_SERVICE, METHOD_DISABLE_FEATURE, METHOD_ENABLE_FEATURE,
+        METHOD_FEATURES,
+    },
+    router::{rpc_handler, Principal, RouterError, RpcError, RpcHandler, RpcRequest, RpcResponse},
+    types::Clock,
+    Feature, FeatureContext, FeatureRegistry, FeatureStatus, MethodDescriptor, TransportEvent,
+};
+use service_features::hello_world::{api, HelloWorldFeature};
+use service_transport::mock::MockTransport;
+use tokio::sync::mpsc;
+
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now_rfc3339(&self) -> String {
+        "2025-12-21T00:00:00Z".to_string()
+    }
+}
+
+fn constant(payload: &'static str) -> RpcHandler {
+    rpc_handler(move |_req: RpcRequest| async move {
+        Ok(RpcResponse {
+            payload: payload.as_bytes().to_vec(),
+        })
+    })
+}
+
+/// Feature that forwards every bus event to the test until it is shut down.
+struct Listener {
+    seen: mpsc::UnboundedSender<String>,
+}
+
+impl Feature for Listener {
+    fn name(&self) -> &'static str {
+        "listener"
+    }
+
+    fn init(&self, ctx: FeatureContext) -> FeatureFuture<'_> {
+        let seen = self.seen.clone();
+        Box::pin(async move {
+            let mut subscription = ctx.events.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        biased;
+                        _ = ctx.shutdown.cancelled() => break,
+                        event = subscription.recv() => match event {
+                            Ok(event) => {
+                                let _ = seen.send(event.topic);
+                            }
+                            Err(_) => break,
+                        },
+                    }
+                }
+            });
+            Ok(())
+        })
+    }
+}
+
+/// Feature that tries to take over `hello.get` and reports what happened.
+struct Intruder {
+    outcomes: mpsc::UnboundedSender<String>,
+}
+
+impl Feature for Intruder {
+    fn name(&self) -> &'static str {
+        "intruder"
+    }
+
+    fn init(&self, ctx: FeatureContext) -> FeatureFuture<'_> {
+        let outcomes = self.outcomes.clone();
+        Box::pin(async move {
+            let replaced = ctx.router.replace_described(
+                MethodDescriptor::new(api::SERVICE, api::METHOD_GET),
+                constant("hijacked"),
+            );
+            let unregistered = ctx.router.unregister(api::SERVICE, api::METHOD_GET);
+            let removed = ctx.router.unregister_feature(api::FEATURE);
+            for outcome in [
+                format!("{replaced:?}"),
+                format!("{unregistered:?}"),
+                format!("removed {}", removed.len()),
+            ] {
+                let _ = outcomes.send(outcome);
+            }
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn features_cannot_touch_other_features_methods() {
+    let transport = MockTransport::new();
+    let clock = Arc::new(FixedClock);
+    let (outcomes_tx, mut outcomes) = mpsc::unbounded_channel();
+    let features = FeatureRegistry::new(transport.registry(), transport.events(), clock.clone());
+    features
+        .add(Arc::new(HelloWorldFeature::with_clock(clock)))
+        .expect("add feature");
+    features
+        .add(Arc::new(Intruder {
+            outcomes: outcomes_tx,
+        }))
+        .expect("add feature");
+    features.enable(api::FEATURE).await.expect("enable hello");
+    features.enable("intruder").await.expect("enable intruder");
+
+    for _ in 0..2 {
+        let outcome = outcomes.recv().await.expect("outcome");
+        assert!(outcome.contains("NotOwned"), "{outcome}");
+    }
+    assert_eq!(outcomes.recv().await.as_deref(), Some("removed 0"));
+    let response = transport
+        .handle_incoming(RpcRequest::new(
+            api::SERVICE,
+            api::METHOD_GET,
+            Vec::new(),
+            1_000,
+        ))
+        .await
+        .expect("response");
+    assert!(String::from_utf8_lossy(&response.payload).ends_with("hello world"));
+}
+
+#[tokio::test]
+async fn replace_and_unregister_handlers() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    registry
+        .register("greet", "say", constant("v1"))
+        .expect("register");
+
+    registry
+        .replace_described(MethodDescriptor::new("greet", "say"), constant("v2"))
+        .expect("replace");
+    let response = transport
+        .handle_incoming(RpcRequest::new("greet", "say", Vec::new(), 1_000))
+        .await
+        .expect("response");
+    assert_eq!(response.payload, b"v2");
+
+    registry.unregister("greet", "say").expect("unregister");
+    let missing = transport
+        .handle_incoming(RpcRequest::new("greet", "say", Vec::new(), 1_000))
+        .await;
+    assert!(matches!(missing, Err(RpcError::UnknownMethod)));
+    assert!(matches!(
+        registry.unregister("greet", "say"),
+        Err(RouterError::NotRegistered { .. })
+    ));
+}
+
+#[tokio::test]
+async fn admin_rpc_disables_and_reenables_feature() {
+    let transport = MockTransport::new();
+    let clock = Arc::new(FixedClock);
+    let features = Arc::new(FeatureRegistry::new(
+        transport.registry(),
+        transport.events(),
+        clock.clone(),
+    ));
+    features
+        .add(Arc::new(HelloWorldFeature::with_clock(clock)))
+        .expect("add feature");
+    features.enable_all().await.expect("enable");
+    features.register_admin().expect("register admin");
+
+    let hello = || RpcRequest::new(api::SERVICE, api::METHOD_GET, Vec::new(), 1_000);
+    let operator = Principal::new("tablet").with_roles([ADMIN_ROLE]);
+    let admin = |method: &str| {
+        RpcRequest::new(
+            ADMIN_SERVICE,
+            method,
+            api::FEATURE.as_bytes().to_vec(),
+            1_000,
+        )
+        .with_principal(operator.clone())
+    };
+
+    transport.handle_incoming(hello()).await.expect("enabled");
+
+    let anonymous = RpcRequest::new(
+        ADMIN_SERVICE,
+        METHOD_DISABLE_FEATURE,
+        api::FEATURE.as_bytes().to_vec(),
+        1_000,
+    );
+    assert!(matches!(
+        transport.handle_incoming(anonymous.clone()).await,
+        Err(RpcError::Unauthenticated(_))
+    ));
+    assert!(matches!(
+        transport
+            .handle_incoming(anonymous.with_principal(Principal::new("phone")))
+            .await,
+        Err(RpcError::PermissionDenied(_))
+    ));
+    transport
+        .handle_incoming(hello())
+        .await
+        .expect("still enabled");
+
+    transport
+        .handle_incoming(admin(METHOD_DISABLE_FEATURE))
+        .await
+        .expect("disable");
+    assert!(matches!(
+        transport.handle_incoming(hello()).await,
+        Err(RpcError::UnknownMethod)
+    ));
+    let listed = transport
+        .handle_incoming(
+            RpcRequest::new(ADMIN_SERVICE, METHOD_FEATURES, Vec::new(), 1_000)
+                .with_principal(operator.clone()),
+        )
+        .await
+        .expect("features");
+    let listed: serde_json::Value = serde_json::from_slice(&listed.payload).expect("json");
+    assert_eq!(listed[0]["name"], api::FEATURE);
+    assert_eq!(listed[0]["enabled"], false);
+
+    transport
+        .handle_incoming(admin(METHOD_ENABLE_FEATURE))
+        .await
+        .expect("re-enable");
+    let response = transport.handle_incoming(hello()).await.expect("enabled");
+    assert!(String::from_utf8_lossy(&response.payload).ends_with("hello world"));
+    assert_eq!(
+        features.features(),
+        [FeatureStatus {
+            name: api::FEATURE.to_string(),
+            enabled: true,
+        }]
+    );
+
+    let unknown = transport
+        .handle_incoming(
+            RpcRequest::new(
+                ADMIN_SERVICE,
+                METHOD_DISABLE_FEATURE,
+                b"missing".to_vec(),
+                1_000,
+            )
+            .with_principal(operator),
+        )
+        .await;
+    match unknown {
+        Err(RpcError::NotFound { resource }) => assert!(resource.contains("missing")),
+        other => panic!("expected not_found, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn disabling_feature_stops_its_subscriptions() {
+    let transport = MockTransport::new();
+    let events = transport.events();
+    let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+    let features = FeatureRegistry::new(transport.registry(), events.clone(), Arc::new(FixedClock));
+    features
+        .add(Arc::new(Listener { seen: seen_tx }))
+        .expect("add feature");
+    features.enable("listener").await.expect("enable");
+
+    let publish = |topic: &str| {
+        events
+            .publish(TransportEvent {
+                topic: topic.to_string(),
+                payload: Vec::new(),
+            })
+            .expect("publish");
+    };
+
+    publish("before");
+    assert_eq!(seen_rx.recv().await.as_deref(), Some("before"));
+
+    features.disable("listener").await.expect("disable");
+    assert!(!features.is_enabled("listener"));
+    publish("after");
+    let next = tokio::time::timeout(Duration::from_millis(100), seen_rx.recv()).await;
+    assert!(next.is_err(), "disabled feature still received {next:?}");
+}
@@ -0,0 +1,206 @@
+use std::{io::Read, net::SocketAddr};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use futures_util::{SinkExt, StreamExt};
+use reqwest::StatusCode;
+use service_core::{
+    router::{rpc_handler, rpc_stream_handler, RpcRequest, RpcResponse},
+    AppConfig, CorsConfig, InMemoryRouter,
+};
+use service_transport::{
+    http::{protocol::HttpRpcRequest, HttpServerTransport},
+    mock::MockTransport,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+/// `blob.echo` echoes its payload; `blob.lines` streams one chunk.
+async fn serve(configure: impl FnOnce(HttpServerTransport) -> HttpServerTransport) -> SocketAddr {
+    let transport = MockTransport::with_router(InMemoryRouter::new());
+    let registry = transport.registry();
+    let echo = rpc_handler(|req: RpcRequest| async move {
+        Ok(RpcResponse {
+            payload: req.payload,
+        })
+    });
+    registry.register("blob", "echo", echo).expect("register");
+    let lines = rpc_stream_handler(|_req: RpcRequest, sender| async move {
+        sender
+            .send(RpcResponse {
+                payload: b"line".to_vec(),
+            })
+            .await?;
+        Ok(())
+    });
+    registry
+        .register_stream("blob", "lines", lines)
+        .expect("register");
+
+    let server = configure(HttpServerTransport::new(registry));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move { server.serve_with_listener(listener).await });
+    addr
+}
+
+#[tokio::test]
+async fn oversized_bodies_are_rejected() {
+    let config = AppConfig::from_toml_str("[http]\nmax_body_bytes = 1024").expect("config");
+    assert_eq!(config.http_max_body_bytes, 1024);
+    assert!(AppConfig::from_toml_str("[http]\nmax_body_bytes = 0").is_err());
+    let addr = serve(|server| server.with_max_body_bytes(config.http_max_body_bytes)).await;
+    let client = reqwest::Client::new();
+
+    let small = client
+        .post(format!("http://{addr}/rpc/blob/echo"))
+        .body(vec![b'a'; 512])
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(small.status(), StatusCode::OK);
+
+    for path in ["rpc/blob/echo", "rpc", "jsonrpc"] {
+        let large = client
+            .post(format!("http://{addr}/{path}"))
+            .header("content-type", "application/json")
+            .body(vec![b' '; 4096])
+            .send()
+            .await
+            .expect("send request");
+        assert_eq!(large.status(), StatusCode::PAYLOAD_TOO_LARGE, "{path}");
+    }
+}
+
+#[tokio::test]
+async fn responses_are_compressed_when_accepted() {
+    let config = AppConfig::from_toml_str("[http]\ncompression = true").expect("config");
+    assert!(config.http_compression);
+    let addr = serve(|server| server.with_compression(config.http_compression)).await;
+    let client = reqwest::Client::new();
+    let body = "hello ".repeat(200);
+
+    let post = |encoding: &'static str| {
+        client
+            .post(format!("http://{addr}/rpc/blob/echo"))
+            .header("accept-encoding", encoding)
+            .body(body.clone())
+            .send()
+    };
+
+    let gzip = post("gzip").await.expect("send request");
+    assert_eq!(gzip.headers()["content-encoding"], "gzip");
+    let compressed = gzip.bytes().await.expect("body");
+    assert!(compressed.len() < body.len());
+    let mut decoded = String::new();
+    GzDecoder::new(compressed.as_ref())
+        .read_to_string(&mut decoded)
+        .expect("gunzip");
+    assert_eq!(decoded, body);
+
+    let deflate = post("deflate").await.expect("send request");
+    assert_eq!(deflate.headers()["content-encoding"], "deflate");
+    let mut decoded = String::new();
+    ZlibDecoder::new(deflate.bytes().await.expect("body").as_ref())
+        .read_to_string(&mut decoded)
+        .expect("inflate");
+    assert_eq!(decoded, body);
+
+    let identity = post("identity").await.expect("send request");
+    assert!(identity.headers().get("content-encoding").is_none());
+    assert_eq!(identity.text().await.expect("body"), body);
+
+    // WebSocket upgrades are unaffected.
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/rpc/stream"))
+        .await
+        .expect("websocket connect");
+    let open = HttpRpcRequest {
+        service: "blob".to_string(),
+        method: "lines".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    socket
+        .send(Message::Text(serde_json::to_string(&open).expect("encode")))
+        .await
+        .expect("send");
+    let Some(Ok(Message::Text(chunk))) = socket.next().await else {
+        panic!("expected a chunk");
+    };
+    assert!(chunk.contains("chunk"));
+}
+
+#[tokio::test]
+async fn cors_allows_configured_origins() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [http.cors]
+        allowed_origins = ["http://dashboard.local"]
+        allowed_headers = ["x-dashboard"]
+        "#,
+    )
+    .expect("config");
+    let cors = config.http_cors.expect("cors");
+    assert_eq!(cors.max_age_secs, 600);
+    assert!(AppConfig::from_toml_str("[http.cors]\nallowed_origins = [\"dashboard\"]").is_err());
+    let addr = serve(|server| server.with_cors(cors)).await;
+    let client = reqwest::Client::new();
+
+    let preflight = client
+        .request(reqwest::Method::OPTIONS, format!("http://{addr}/rpc"))
+        .header("origin", "http://dashboard.local")
+        .header("access-control-request-method", "POST")
+        .header(
+            "access-control-request-headers",
+            "content-type,authorization,x-dashboard",
+        )
+        .send()
+        .await
+        .expect("send preflight");
+    assert!(preflight.status().is_success());
+    let headers = preflight.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "http://dashboard.local"
+    );
+    let allowed = headers["access-control-allow-headers"]
+        .to_str()
+        .expect("ascii");
+    assert!(allowed.contains("authorization"));
+    assert!(allowed.contains("x-dashboard"));
+    assert_eq!(headers["access-control-max-age"], "600");
+
+    let call = client
+        .post(format!("http://{addr}/rpc/blob/echo"))
+        .header("origin", "http://dashboard.local")
+        .body("x")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(
+        call.headers()["access-control-allow-origin"],
+        "http://dashboard.local"
+    );
+
+    let foreign = client
+        .post(format!("http://{addr}/rpc/blob/echo"))
+        .header("origin", "http://evil.example")
+        .body("x")
+        .send()
+        .await
+        .expect("send request");
+    assert!(foreign
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+
+    let any = serve(|server| server.with_cors(CorsConfig::new(["*"]))).await;
+    let response = client
+        .post(format!("http://{any}/rpc/blob/echo"))
+        .header("origin", "http://anywhere.example")
+        .body("x")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.headers()["access-control-allow-origin"], "*");
+}
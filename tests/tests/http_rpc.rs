@@ -49,7 +49,7 @@ async fn http_rpc_hello_world() {
         .expect("bind listener");
     let addr = listener.local_addr().expect("local addr");
 
-    let server_task = tokio::spawn(async move { server.serve(addr).await });
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
 
     let client = reqwest::Client::new();
     let url = format!("http://{}/rpc", addr);
@@ -0,0 +1,272 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair,
+};
+use service_core::{
+    router::{rpc_handler, RpcRegistry, RpcRequest, RpcResponse},
+    AppConfig, TlsConfig,
+};
+use service_transport::{
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        tls::HttpTls,
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+
+struct Authority {
+    cert: Certificate,
+    key: KeyPair,
+}
+
+impl Authority {
+    fn new(name: &str) -> Self {
+        let key = KeyPair::generate().expect("ca key");
+        let mut params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.distinguished_name.push(DnType::CommonName, name);
+        let cert = params.self_signed(&key).expect("self-signed ca");
+        Self { cert, key }
+    }
+
+    /// Issue a leaf certificate; returns `(cert PEM, key PEM)`.
+    fn issue(&self, params: CertificateParams) -> (String, String) {
+        let key = KeyPair::generate().expect("leaf key");
+        let cert = params
+            .signed_by(&key, &self.cert, &self.key)
+            .expect("sign leaf");
+        (cert.pem(), key.serialize_pem())
+    }
+
+    fn server_cert(&self) -> (String, String) {
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]).expect("params");
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        self.issue(params)
+    }
+
+    fn client_cert(&self, name: &str, unit: &str) -> (String, String) {
+        let mut params = CertificateParams::new(Vec::<String>::new()).expect("params");
+        params.distinguished_name.push(DnType::CommonName, name);
+        params
+            .distinguished_name
+            .push(DnType::OrganizationalUnitName, unit);
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        self.issue(params)
+    }
+
+    fn root(&self) -> reqwest::Certificate {
+        reqwest::Certificate::from_pem(self.cert.pem().as_bytes()).expect("root cert")
+    }
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("service-tls-{}-{name}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create scratch dir");
+    dir
+}
+
+fn write_pair(dir: &Path, (cert, key): &(String, String)) {
+    fs::write(dir.join("server.pem"), cert).expect("write cert");
+    fs::write(dir.join("server.key"), key).expect("write key");
+}
+
+/// Registers `auth.whoami`, which returns `id:role,role` for the caller.
+fn register_whoami(registry: &Arc<dyn RpcRegistry>) {
+    let handler = rpc_handler(|req: RpcRequest| async move {
+        let caller = req
+            .context
+            .principal
+            .map(|principal| format!("{}:{}", principal.id, principal.roles.join(",")))
+            .unwrap_or_default();
+        Ok(RpcResponse {
+            payload: caller.into_bytes(),
+        })
+    });
+    registry
+        .register("auth", "whoami", handler)
+        .expect("register handler");
+}
+
+async fn start(tls: HttpTls) -> (std::net::SocketAddr, tokio::task::JoinHandle<()>) {
+    let transport = MockTransport::new();
+    register_whoami(&transport.registry());
+    let server = HttpServerTransport::new(transport.registry()).with_tls(tls);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let task = tokio::spawn(async move {
+        let _ = server.serve_with_listener(listener).await;
+    });
+    (addr, task)
+}
+
+async fn whoami(client: &reqwest::Client, addr: std::net::SocketAddr) -> reqwest::Result<String> {
+    let request = HttpRpcRequest {
+        service: "auth".to_string(),
+        method: "whoami".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    let body: HttpRpcResponse = client
+        .post(format!("https://localhost:{}/rpc", addr.port()))
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+    let payload = general_purpose::STANDARD
+        .decode(body.payload_b64)
+        .expect("decode payload");
+    Ok(String::from_utf8(payload).expect("utf-8"))
+}
+
+fn client(addr: std::net::SocketAddr, root: reqwest::Certificate) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .use_rustls_tls()
+        .tls_built_in_root_certs(false)
+        .add_root_certificate(root)
+        .resolve("localhost", addr)
+}
+
+#[tokio::test]
+async fn mutual_tls_sets_principal_from_client_certificate() {
+    let ca = Authority::new("service test ca");
+    let dir = scratch_dir("mtls");
+    write_pair(&dir, &ca.server_cert());
+    fs::write(dir.join("clients.pem"), ca.cert.pem()).expect("write client ca");
+
+    let config = AppConfig::from_toml_str(&format!(
+        r#"
+        [http.tls]
+        cert_path = "{0}/server.pem"
+        key_path = "{0}/server.key"
+        client_ca_path = "{0}/clients.pem"
+        "#,
+        dir.display()
+    ))
+    .expect("config");
+    let tls_config = config.http_tls.expect("tls configured");
+    assert_eq!(
+        tls_config,
+        TlsConfig::new(dir.join("server.pem"), dir.join("server.key"))
+            .with_client_ca(dir.join("clients.pem"))
+    );
+    let (addr, server_task) = start(HttpTls::load(tls_config).expect("load tls")).await;
+
+    let (cert, key) = ca.client_cert("phone", "admin");
+    let identity =
+        reqwest::Identity::from_pem(format!("{cert}{key}").as_bytes()).expect("identity");
+    let with_cert = client(addr, ca.root())
+        .identity(identity)
+        .build()
+        .expect("client");
+    assert_eq!(whoami(&with_cert, addr).await.expect("call"), "phone:admin");
+
+    let without_cert = client(addr, ca.root()).build().expect("client");
+    assert!(whoami(&without_cert, addr).await.is_err());
+
+    let stranger = Authority::new("other ca");
+    let (cert, key) = stranger.client_cert("mallory", "admin");
+    let identity =
+        reqwest::Identity::from_pem(format!("{cert}{key}").as_bytes()).expect("identity");
+    let untrusted = client(addr, ca.root())
+        .identity(identity)
+        .build()
+        .expect("client");
+    assert!(whoami(&untrusted, addr).await.is_err());
+
+    server_task.abort();
+    let _ = server_task.await;
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn sighup_reloads_certificates_for_new_connections() {
+    let old_ca = Authority::new("old ca");
+    let new_ca = Authority::new("new ca");
+    let dir = scratch_dir("reload");
+    write_pair(&dir, &old_ca.server_cert());
+
+    let tls = HttpTls::load(TlsConfig::new(
+        dir.join("server.pem"),
+        dir.join("server.key"),
+    ))
+    .expect("load tls");
+    let (reloads, mut reloaded) = tokio::sync::mpsc::unbounded_channel();
+    let reloader = tls
+        .reload_on_sighup(move |result| {
+            let _ = reloads.send(result.map_err(|err| err.to_string()));
+        })
+        .expect("install SIGHUP handler");
+    let (addr, server_task) = start(tls).await;
+
+    let established = client(addr, old_ca.root()).build().expect("client");
+    assert_eq!(whoami(&established, addr).await.expect("call"), "");
+
+    write_pair(&dir, &new_ca.server_cert());
+    let status = Command::new("kill")
+        .args(["-HUP", &std::process::id().to_string()])
+        .status()
+        .expect("send SIGHUP");
+    assert!(status.success());
+    let reload = tokio::time::timeout(Duration::from_secs(5), reloaded.recv())
+        .await
+        .expect("reload reported");
+    assert_eq!(reload, Some(Ok(())));
+
+    let fresh = client(addr, new_ca.root()).build().expect("client");
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while whoami(&fresh, addr).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("new certificate served");
+
+    // The pooled connection negotiated before the reload is still usable.
+    assert_eq!(whoami(&established, addr).await.expect("call"), "");
+    let stale = client(addr, old_ca.root()).build().expect("client");
+    assert!(whoami(&stale, addr).await.is_err());
+
+    reloader.abort();
+    server_task.abort();
+    let _ = server_task.await;
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn stalled_handshakes_are_dropped() {
+    use tokio::io::AsyncReadExt as _;
+
+    let dir = scratch_dir("handshake");
+    write_pair(&dir, &Authority::new("ca").server_cert());
+    let tls = HttpTls::load(TlsConfig::new(
+        dir.join("server.pem"),
+        dir.join("server.key"),
+    ))
+    .expect("load tls")
+    .with_handshake_timeout(Duration::from_millis(100));
+    let (addr, server_task) = start(tls).await;
+
+    // Connect but never send a ClientHello.
+    let mut stream = tokio::net::TcpStream::connect(addr).await.expect("connect");
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut [0; 16]))
+        .await
+        .expect("server closed the stalled connection");
+    assert_eq!(read.expect("read"), 0);
+
+    server_task.abort();
+    let _ = server_task.await;
+    let _ = fs::remove_dir_all(dir);
+}
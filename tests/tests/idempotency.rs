@@ -0,0 +1,231 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use service_core::{
+    router::{rpc_handler, Principal, RpcError, RpcRequest, RpcResponse, IDEMPOTENCY_KEY_METADATA},
+    AppConfig, IdempotencyConfig, InMemoryRouter, RequestSigner, RequestVerifier,
+};
+use service_transport::{
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+
+const KEY: &[u8] = b"shared-signing-key";
+
+/// Transport whose `counter.bump` handler returns how often it has run.
+fn counting_transport(
+    router: InMemoryRouter,
+    delay: Duration,
+) -> (MockTransport, Arc<AtomicUsize>) {
+    let transport = MockTransport::with_router(router);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let handler = rpc_handler(move |_req: RpcRequest| {
+        let counter = counter.clone();
+        async move {
+            tokio::time::sleep(delay).await;
+            let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(RpcResponse {
+                payload: count.to_string().into_bytes(),
+            })
+        }
+    });
+    transport
+        .registry()
+        .register("counter", "bump", handler)
+        .expect("register handler");
+    (transport, calls)
+}
+
+fn bump(caller: &str, key: &str, payload: &[u8]) -> RpcRequest {
+    RpcRequest::new("counter", "bump", payload.to_vec(), 0)
+        .with_principal(Principal::new(caller))
+        .with_metadata(IDEMPOTENCY_KEY_METADATA, key)
+}
+
+fn signed(metadata: impl IntoIterator<Item = (String, String)>) -> RpcRequest {
+    metadata.into_iter().fold(
+        RpcRequest::new("counter", "bump", b"{}".to_vec(), 0),
+        |req, (key, value)| req.with_metadata(key, value),
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock after epoch")
+        .as_secs()
+}
+
+#[tokio::test]
+async fn retries_with_the_same_key_reuse_the_first_result() {
+    let router = InMemoryRouter::new().with_idempotency(IdempotencyConfig::default());
+    let (transport, calls) = counting_transport(router, Duration::ZERO);
+
+    let first = transport
+        .handle_incoming(bump("phone", "order-1", b"{}"))
+        .await
+        .expect("first call");
+    let retry = transport
+        .handle_incoming(bump("phone", "order-1", b"{}"))
+        .await
+        .expect("retry");
+    assert_eq!(first.payload, b"1");
+    assert_eq!(retry.payload, b"1");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Another caller, or another key, runs the handler again.
+    let other_caller = transport
+        .handle_incoming(bump("tablet", "order-1", b"{}"))
+        .await
+        .expect("other caller");
+    assert_eq!(other_caller.payload, b"2");
+    let other_key = transport
+        .handle_incoming(bump("phone", "order-2", b"{}"))
+        .await
+        .expect("other key");
+    assert_eq!(other_key.payload, b"3");
+    // Calls without a key are never cached.
+    let unkeyed = RpcRequest::new("counter", "bump", Vec::new(), 0);
+    let response = transport.handle_incoming(unkeyed).await.expect("unkeyed");
+    assert_eq!(response.payload, b"4");
+
+    match transport
+        .handle_incoming(bump("phone", "order-1", b"{\"other\":true}"))
+        .await
+    {
+        Err(RpcError::Conflict(message)) => assert!(message.contains("order-1")),
+        other => panic!("expected conflict, got {other:?}"),
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn concurrent_duplicates_wait_for_the_first_call_and_entries_expire() {
+    let router =
+        InMemoryRouter::new().with_idempotency(IdempotencyConfig::new(Duration::from_secs(1)));
+    let (transport, calls) = counting_transport(router, Duration::from_millis(100));
+
+    let (first, second) = tokio::join!(
+        transport.handle_incoming(bump("phone", "order-1", b"{}")),
+        transport.handle_incoming(bump("phone", "order-1", b"{}")),
+    );
+    assert_eq!(first.expect("first").payload, b"1");
+    assert_eq!(second.expect("second").payload, b"1");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(1_000)).await;
+    let after_window = transport
+        .handle_incoming(bump("phone", "order-1", b"{}"))
+        .await
+        .expect("after window");
+    assert_eq!(after_window.payload, b"2");
+}
+
+#[tokio::test]
+async fn signed_requests_reject_bad_signatures_replays_and_stale_timestamps() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [idempotency]
+        window_secs = 60
+
+        [signing]
+        key = "shared-signing-key"
+        required = true
+        "#,
+    )
+    .expect("config");
+    let signing = config.signing.expect("signing section");
+    assert_eq!(signing.max_skew_secs, 30);
+    assert_eq!(config.idempotency.expect("idempotency").max_entries, 1024);
+    assert!(AppConfig::from_toml_str("[signing]\nkey = \"\"").is_err());
+
+    let router = InMemoryRouter::new()
+        .with_request_verifier(Arc::new(RequestVerifier::from_config(&signing)));
+    let (transport, calls) = counting_transport(router, Duration::ZERO);
+    let signer = RequestSigner::new(KEY);
+
+    let metadata = signer.sign("counter", "bump", b"{}");
+    assert!(transport
+        .handle_incoming(signed(metadata.clone()))
+        .await
+        .is_ok());
+    match transport.handle_incoming(signed(metadata)).await {
+        Err(RpcError::Unauthenticated(message)) => assert!(message.contains("replayed")),
+        other => panic!("expected replay rejection, got {other:?}"),
+    }
+
+    let stale = signer.sign_with("counter", "bump", b"{}", unix_now() - 120, "stale");
+    match transport.handle_incoming(signed(stale)).await {
+        Err(RpcError::Unauthenticated(message)) => assert!(message.contains("window")),
+        other => panic!("expected stale rejection, got {other:?}"),
+    }
+
+    let forged = RequestSigner::new(b"wrong-key".to_vec()).sign("counter", "bump", b"{}");
+    match transport.handle_incoming(signed(forged)).await {
+        Err(RpcError::Unauthenticated(message)) => assert!(message.contains("signature")),
+        other => panic!("expected signature rejection, got {other:?}"),
+    }
+    // Signed for another method.
+    let moved = signer.sign("counter", "reset", b"{}");
+    assert!(matches!(
+        transport.handle_incoming(signed(moved)).await,
+        Err(RpcError::Unauthenticated(_))
+    ));
+    assert!(matches!(
+        transport
+            .handle_incoming(RpcRequest::new("counter", "bump", Vec::new(), 0))
+            .await,
+        Err(RpcError::Unauthenticated(_))
+    ));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn http_idempotency_key_header_and_conflict_status() {
+    let router = InMemoryRouter::new().with_idempotency(IdempotencyConfig::default());
+    let (transport, calls) = counting_transport(router, Duration::ZERO);
+    let server = HttpServerTransport::new(transport.registry());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let client = reqwest::Client::new();
+    let post = |payload_b64: &str| {
+        client
+            .post(format!("http://{addr}/rpc"))
+            .header("Idempotency-Key", "order-1")
+            .json(&HttpRpcRequest {
+                service: "counter".to_string(),
+                method: "bump".to_string(),
+                payload_b64: payload_b64.to_string(),
+                timeout_ms: 1_000,
+            })
+            .send()
+    };
+
+    for _ in 0..2 {
+        let response = post("").await.expect("send request");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: HttpRpcResponse = response.json().await.expect("decode response");
+        assert_eq!(body.payload_b64, "MQ==");
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let conflict = post("e30=").await.expect("send request");
+    assert_eq!(conflict.status(), reqwest::StatusCode::CONFLICT);
+    let body: HttpRpcResponse = conflict.json().await.expect("decode response");
+    assert_eq!(body.error.expect("error").code, "conflict");
+
+    server_task.abort();
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use service_core::{
+    feature::FeatureContext,
+    router::{
+        rpc_handler, rpc_stream_handler, MethodDescriptor, RpcError, RpcRequest, RpcResponse,
+    },
+    types::Clock,
+    Feature,
+};
+use service_features::hello_world::{api, HelloWorldFeature};
+use service_transport::{http::HttpServerTransport, mock::MockTransport};
+
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now_rfc3339(&self) -> String {
+        "2025-12-21T00:00:00Z".to_string()
+    }
+}
+
+async fn transport_with_methods() -> MockTransport {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    let clock = Arc::new(FixedClock);
+    HelloWorldFeature::with_clock(clock.clone())
+        .init(FeatureContext::new(
+            registry.clone(),
+            transport.events(),
+            clock,
+        ))
+        .await
+        .expect("feature init");
+
+    let tail = rpc_stream_handler(|_req, _sender| async { Ok(()) });
+    registry
+        .register_stream_described(
+            MethodDescriptor::new("logs", "tail").description("Follow the service log"),
+            tail,
+        )
+        .expect("register stream");
+    transport
+}
+
+#[tokio::test]
+async fn rpc_list_and_describe() {
+    let transport = transport_with_methods().await;
+
+    let response = transport
+        .handle_incoming(RpcRequest::new("rpc", "list", Vec::new(), 1_000))
+        .await
+        .expect("list");
+    let methods: serde_json::Value = serde_json::from_slice(&response.payload).expect("json");
+    let names: Vec<String> = methods
+        .as_array()
+        .expect("array")
+        .iter()
+        .map(|method| format!("{}.{}", method["service"], method["method"]).replace('"', ""))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "hello.get",
+            "logs.tail",
+            "rpc.cancel",
+            "rpc.describe",
+            "rpc.list"
+        ]
+    );
+    assert_eq!(methods[1]["streaming"], true);
+
+    let response = transport
+        .handle_incoming(RpcRequest::new(
+            "rpc",
+            "describe",
+            b"hello.get".to_vec(),
+            1_000,
+        ))
+        .await
+        .expect("describe");
+    let descriptor: serde_json::Value = serde_json::from_slice(&response.payload).expect("json");
+    assert_eq!(descriptor["feature"], api::FEATURE);
+    assert_eq!(descriptor["streaming"], false);
+    assert!(descriptor["description"].is_string());
+    assert!(descriptor["response_schema"].is_string());
+
+    let missing = transport
+        .handle_incoming(RpcRequest::new(
+            "rpc",
+            "describe",
+            b"hello.missing".to_vec(),
+            1_000,
+        ))
+        .await;
+    assert!(matches!(missing, Err(RpcError::UnknownMethod)));
+}
+
+#[tokio::test]
+async fn describe_matches_dotted_names() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    let handler = rpc_handler(|_req: RpcRequest| async {
+        Ok(RpcResponse {
+            payload: Vec::new(),
+        })
+    });
+    registry
+        .register("sensors.v2", "read", handler.clone())
+        .expect("register dotted service");
+    registry
+        .register("config", "wifi.set", handler)
+        .expect("register dotted method");
+
+    for (name, service, method) in [
+        ("sensors.v2.read", "sensors.v2", "read"),
+        ("config.wifi.set", "config", "wifi.set"),
+    ] {
+        let response = transport
+            .handle_incoming(RpcRequest::new(
+                "rpc",
+                "describe",
+                name.as_bytes().to_vec(),
+                1_000,
+            ))
+            .await
+            .expect(name);
+        let descriptor: serde_json::Value =
+            serde_json::from_slice(&response.payload).expect("json");
+        assert_eq!(descriptor["service"], service);
+        assert_eq!(descriptor["method"], method);
+    }
+
+    let unsplittable = transport
+        .handle_incoming(RpcRequest::new(
+            "rpc",
+            "describe",
+            b"config".to_vec(),
+            1_000,
+        ))
+        .await;
+    assert!(matches!(unsplittable, Err(RpcError::Decode(_))));
+}
+
+#[tokio::test]
+async fn http_lists_registered_methods() {
+    let transport = transport_with_methods().await;
+    let server = HttpServerTransport::new(transport.registry());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let methods: serde_json::Value = reqwest::get(format!("http://{addr}/rpc/methods"))
+        .await
+        .expect("response")
+        .json()
+        .await
+        .expect("json body");
+    let hello = methods
+        .as_array()
+        .expect("array")
+        .iter()
+        .find(|method| method["service"] == api::SERVICE)
+        .expect("hello listed");
+    assert_eq!(hello["method"], api::METHOD_GET);
+    assert_eq!(hello["feature"], api::FEATURE);
+
+    server_task.abort();
+    let _ = server_task.await;
+}
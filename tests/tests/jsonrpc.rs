@@ -0,0 +1,248 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use service_core::{
+    router::{rpc_handler, RpcError, RpcRegistry, RpcRequest, RpcResponse},
+    AccessPolicy, AccessRule, InMemoryRouter,
+};
+use service_transport::{
+    http::{protocol::JsonRpcResponse, HttpServerTransport},
+    mock::MockTransport,
+};
+
+/// Serve `math.add` (sums `params.a` and `params.b`), `math.fail`, and
+/// `notes.add` (counts calls) over HTTP.
+async fn serve(router: InMemoryRouter) -> (SocketAddr, Arc<AtomicUsize>) {
+    let transport = MockTransport::with_router(router);
+    let registry = transport.registry();
+    let add = rpc_handler(|req: RpcRequest| async move {
+        let params: Value = serde_json::from_slice(&req.payload)
+            .map_err(|err| RpcError::Decode(err.to_string()))?;
+        let (Some(a), Some(b)) = (params["a"].as_i64(), params["b"].as_i64()) else {
+            return Err(RpcError::Decode("expected integers a and b".to_string()));
+        };
+        Ok(RpcResponse {
+            payload: serde_json::to_vec(&json!({ "sum": a + b })).expect("encode"),
+        })
+    });
+    registry.register("math", "add", add).expect("register");
+    let fail =
+        rpc_handler(|_req: RpcRequest| async move { Err(RpcError::Internal("boom".to_string())) });
+    registry.register("math", "fail", fail).expect("register");
+
+    let notes = Arc::new(AtomicUsize::new(0));
+    let counter = notes.clone();
+    let note = rpc_handler(move |_req: RpcRequest| {
+        let counter = counter.clone();
+        async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(RpcResponse {
+                payload: Vec::new(),
+            })
+        }
+    });
+    registry.register("notes", "add", note).expect("register");
+
+    let server = HttpServerTransport::new(registry);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move { server.serve_with_listener(listener).await });
+    (addr, notes)
+}
+
+async fn post(addr: SocketAddr, body: impl Into<reqwest::Body>) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{addr}/jsonrpc"))
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .expect("send request")
+}
+
+#[tokio::test]
+async fn single_calls_map_results_and_errors() {
+    let (addr, _) = serve(InMemoryRouter::new()).await;
+
+    let request =
+        json!({"jsonrpc": "2.0", "method": "math.add", "params": {"a": 2, "b": 3}, "id": 1});
+    let response = post(addr, request.to_string()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response: JsonRpcResponse = response.json().await.expect("decode response");
+    assert_eq!(response.jsonrpc, "2.0");
+    assert_eq!(response.id, json!(1));
+    assert_eq!(response.result, Some(json!({"sum": 5})));
+    assert!(response.error.is_none());
+
+    let cases = [
+        (
+            json!({"jsonrpc": "2.0", "method": "math.missing", "id": "a"}),
+            -32601,
+            "unknown_method",
+        ),
+        (
+            json!({"jsonrpc": "2.0", "method": "math.add", "params": {"a": 1}, "id": "b"}),
+            -32602,
+            "decode",
+        ),
+        (
+            json!({"jsonrpc": "2.0", "method": "math.fail", "id": "c"}),
+            -32603,
+            "internal",
+        ),
+    ];
+    for (request, code, data_code) in cases {
+        let id = request["id"].clone();
+        let response: JsonRpcResponse = post(addr, request.to_string())
+            .await
+            .json()
+            .await
+            .expect("decode response");
+        assert_eq!(response.id, id);
+        assert!(response.result.is_none());
+        let error = response.error.expect("error");
+        assert_eq!(error.code, code);
+        assert_eq!(error.data.expect("data").code, data_code);
+    }
+
+    let response: JsonRpcResponse = post(addr, "{not json").await.json().await.expect("decode");
+    assert_eq!(response.error.expect("error").code, -32700);
+    assert_eq!(response.id, Value::Null);
+
+    let response: JsonRpcResponse =
+        post(addr, r#"{"jsonrpc": "1.0", "method": "math.add", "id": 7}"#)
+            .await
+            .json()
+            .await
+            .expect("decode");
+    assert_eq!(response.error.expect("error").code, -32600);
+    assert_eq!(response.id, json!(7));
+}
+
+#[tokio::test]
+async fn dotted_service_names_resolve() {
+    let router = InMemoryRouter::new();
+    let scan = rpc_handler(|_req: RpcRequest| async move {
+        Ok(RpcResponse {
+            payload: b"[\"home\"]".to_vec(),
+        })
+    });
+    router.register("net.wifi", "scan", scan).expect("register");
+    let (addr, _) = serve(router).await;
+
+    let request = json!({"jsonrpc": "2.0", "method": "net.wifi.scan", "id": 1});
+    let response: JsonRpcResponse = post(addr, request.to_string())
+        .await
+        .json()
+        .await
+        .expect("decode response");
+    assert_eq!(response.result, Some(json!(["home"])));
+
+    let request = json!({"jsonrpc": "2.0", "method": "net.wifi.connect", "id": 2});
+    let response: JsonRpcResponse = post(addr, request.to_string())
+        .await
+        .json()
+        .await
+        .expect("decode response");
+    assert_eq!(response.error.expect("error").code, -32601);
+}
+
+#[tokio::test]
+async fn batches_and_notifications() {
+    let (addr, notes) = serve(InMemoryRouter::new()).await;
+
+    let batch = json!([
+        {"jsonrpc": "2.0", "method": "math.add", "params": {"a": 1, "b": 1}, "id": 1},
+        {"jsonrpc": "2.0", "method": "notes.add", "params": {"text": "hi"}},
+        {"jsonrpc": "2.0", "method": "notes.add", "id": null},
+        {"foo": "bar"},
+        {"jsonrpc": "2.0", "method": "math.add", "params": {"a": 2, "b": 2}, "id": 2},
+    ]);
+    let responses: Vec<JsonRpcResponse> = post(addr, batch.to_string())
+        .await
+        .json()
+        .await
+        .expect("decode batch");
+    assert_eq!(responses.len(), 4);
+    assert_eq!(responses[0].result, Some(json!({"sum": 2})));
+    // An explicit null id is a call, not a notification.
+    assert_eq!(responses[1].id, Value::Null);
+    assert_eq!(responses[1].result, Some(Value::Null));
+    assert_eq!(responses[2].error.as_ref().expect("error").code, -32600);
+    assert_eq!(responses[3].result, Some(json!({"sum": 4})));
+    assert_eq!(notes.load(Ordering::SeqCst), 2);
+
+    let only_notifications = json!([
+        {"jsonrpc": "2.0", "method": "notes.add"},
+        {"jsonrpc": "2.0", "method": "notes.add"},
+    ]);
+    let response = post(addr, only_notifications.to_string()).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(notes.load(Ordering::SeqCst), 4);
+
+    let response: JsonRpcResponse = post(addr, "[]").await.json().await.expect("decode");
+    assert_eq!(response.error.expect("error").code, -32600);
+}
+
+#[tokio::test]
+async fn explicit_null_id_is_answered() {
+    let (addr, notes) = serve(InMemoryRouter::new()).await;
+
+    let request = json!({"jsonrpc": "2.0", "method": "notes.add", "id": null});
+    let response = post(addr, request.to_string()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response: JsonRpcResponse = response.json().await.expect("decode");
+    assert_eq!(response.id, Value::Null);
+    assert_eq!(response.result, Some(Value::Null));
+    assert_eq!(notes.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn batches_reject_single_request_headers() {
+    let (addr, notes) = serve(InMemoryRouter::new()).await;
+
+    let batch = json!([
+        {"jsonrpc": "2.0", "method": "notes.add", "id": 1},
+        {"jsonrpc": "2.0", "method": "notes.add", "id": 2},
+    ]);
+    for header in ["idempotency-key", "x-signature", "x-nonce", "x-timestamp"] {
+        let response: JsonRpcResponse = reqwest::Client::new()
+            .post(format!("http://{addr}/jsonrpc"))
+            .header("content-type", "application/json")
+            .header(header, "k-1")
+            .body(batch.to_string())
+            .send()
+            .await
+            .expect("send request")
+            .json()
+            .await
+            .expect("decode");
+        assert_eq!(response.error.expect("error").code, -32600, "{header}");
+    }
+    assert_eq!(notes.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn router_errors_use_server_error_codes() {
+    let policy = AccessPolicy::new([AccessRule::new().principals(["*"]).methods(["math.add"])]);
+    let (addr, _) = serve(InMemoryRouter::new().with_policy(Arc::new(policy))).await;
+
+    let request = json!({"jsonrpc": "2.0", "method": "math.fail", "id": 1});
+    let response: JsonRpcResponse = post(addr, request.to_string())
+        .await
+        .json()
+        .await
+        .expect("decode response");
+    let error = response.error.expect("error");
+    assert!((-32099..=-32000).contains(&error.code));
+    assert_eq!(error.data.expect("data").code, "permission_denied");
+}
@@ -0,0 +1,130 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use service_platform::network::{netlink, NetworkChanged, NetworkWatcher};
+
+// rtnetlink constants from <linux/rtnetlink.h> and <linux/if.h>.
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const RTM_NEWADDR: u16 = 20;
+const NLMSG_DONE: u16 = 3;
+const IFLA_IFNAME: u16 = 3;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFF_UP: u32 = 0x1;
+const IFF_RUNNING: u32 = 0x40;
+const AF_INET: u8 = 2;
+
+fn attribute(kind: u16, value: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(((4 + value.len()) as u16).to_ne_bytes());
+    bytes.extend(kind.to_ne_bytes());
+    bytes.extend(value);
+    bytes.resize((bytes.len() + 3) & !3, 0);
+    bytes
+}
+
+fn message(kind: u16, body: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(((16 + body.len()) as u32).to_ne_bytes());
+    bytes.extend(kind.to_ne_bytes());
+    bytes.extend([0u8; 10]);
+    bytes.extend(body);
+    bytes
+}
+
+fn link(kind: u16, name: &str, flags: u32) -> Vec<u8> {
+    let mut body = vec![0u8; 4];
+    body.extend(7i32.to_ne_bytes());
+    body.extend(flags.to_ne_bytes());
+    body.extend(0u32.to_ne_bytes());
+    body.extend(attribute(IFLA_IFNAME, format!("{name}\0").as_bytes()));
+    message(kind, &body)
+}
+
+#[tokio::test]
+async fn watcher_reports_link_transitions_and_address_changes() {
+    let (mut watcher, events) = NetworkWatcher::mock();
+    let address = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+
+    assert!(events.link_up("wlan0").await);
+    // The kernel re-announces links on any attribute change.
+    assert!(events.link_up("wlan0").await);
+    assert!(events.address_added("wlan0", address).await);
+    assert!(events.link_down("wlan0").await);
+    assert!(events.link_down("wlan0").await);
+    assert!(events.address_removed("wlan0", address).await);
+    assert!(events.link_up("eth0").await);
+    drop(events);
+
+    let mut seen = Vec::new();
+    while let Some(change) = watcher.next().await {
+        seen.push(change);
+    }
+    assert_eq!(
+        seen,
+        [
+            NetworkChanged::LinkUp {
+                interface: "wlan0".to_string(),
+            },
+            NetworkChanged::AddressAdded {
+                interface: "wlan0".to_string(),
+                address,
+            },
+            NetworkChanged::LinkDown {
+                interface: "wlan0".to_string(),
+            },
+            NetworkChanged::AddressRemoved {
+                interface: "wlan0".to_string(),
+                address,
+            },
+            NetworkChanged::LinkUp {
+                interface: "eth0".to_string(),
+            },
+        ]
+    );
+    assert_eq!(seen[1].to_string(), "wlan0 gained 192.168.1.20");
+}
+
+#[test]
+fn decodes_rtnetlink_datagrams() {
+    let mut address = vec![AF_INET, 24, 0, 0];
+    address.extend(7u32.to_ne_bytes());
+    address.extend(attribute(IFA_LOCAL, &[10, 0, 0, 5]));
+    address.extend(attribute(IFA_LABEL, b"wlan0\0"));
+
+    let datagram = [
+        link(RTM_NEWLINK, "wlan0", IFF_UP | IFF_RUNNING),
+        message(RTM_NEWADDR, &address),
+        link(RTM_NEWLINK, "wlan0", IFF_UP),
+        link(RTM_DELLINK, "wlan1", IFF_UP | IFF_RUNNING),
+        message(NLMSG_DONE, &[0; 4]),
+    ]
+    .concat();
+
+    assert_eq!(
+        netlink::decode(&datagram),
+        [
+            NetworkChanged::LinkUp {
+                interface: "wlan0".to_string(),
+            },
+            NetworkChanged::AddressAdded {
+                interface: "wlan0".to_string(),
+                address: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)),
+            },
+            NetworkChanged::LinkDown {
+                interface: "wlan0".to_string(),
+            },
+            NetworkChanged::LinkDown {
+                interface: "wlan1".to_string(),
+            },
+        ]
+    );
+    // Truncated messages are dropped rather than misread.
+    assert!(netlink::decode(&datagram[..20]).is_empty());
+}
+
+#[tokio::test]
+async fn netlink_socket_subscribes_to_route_groups() {
+    let watcher = NetworkWatcher::netlink().expect("open rtnetlink socket");
+    drop(watcher);
+}
@@ -0,0 +1,176 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use service_core::{
+    router::{rpc_handler, Principal, RpcError, RpcRequest, RpcResponse},
+    AppConfig, InMemoryRouter, RateLimit, RateLimits,
+};
+use service_transport::{
+    ble::{client::BleClient, link::BleLink, protocol::BleRpcRequest, BleTransport},
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+
+fn transport_with(limits: RateLimits) -> MockTransport {
+    let transport = MockTransport::with_router(InMemoryRouter::new().with_rate_limits(limits));
+    for method in ["get", "set"] {
+        let handler = rpc_handler(|_req: RpcRequest| async move {
+            Ok(RpcResponse {
+                payload: b"ok".to_vec(),
+            })
+        });
+        transport
+            .registry()
+            .register("hello", method, handler)
+            .expect("register handler");
+    }
+    transport
+}
+
+fn call_as(method: &str, caller: &str) -> RpcRequest {
+    RpcRequest::new("hello", method, Vec::new(), 0).with_principal(Principal::new(caller))
+}
+
+#[tokio::test]
+async fn per_client_bucket_is_independent_per_caller() {
+    let transport = transport_with(RateLimits::new().with_per_client(RateLimit::new(0.5, 2)));
+
+    for method in ["get", "set"] {
+        assert!(transport
+            .handle_incoming(call_as(method, "phone"))
+            .await
+            .is_ok());
+    }
+    match transport.handle_incoming(call_as("get", "phone")).await {
+        Err(RpcError::RateLimited { retry_after }) => {
+            assert!(retry_after > Duration::from_secs(1));
+            assert!(retry_after <= Duration::from_secs(2));
+        }
+        other => panic!("expected rate_limited, got {other:?}"),
+    }
+    assert!(transport
+        .handle_incoming(call_as("get", "tablet"))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn method_bucket_refills_over_time() {
+    let config = AppConfig::from_toml_str(
+        r#"
+        [rate_limits.methods."hello.get"]
+        per_second = 20.0
+        burst = 1
+        "#,
+    )
+    .expect("config");
+    assert!(config.rate_limits.per_client.is_none());
+    assert!(
+        AppConfig::from_toml_str("[rate_limits.per_client]\nper_second = 1.0\nburst = 0").is_err()
+    );
+    assert!(
+        AppConfig::from_toml_str("[rate_limits.methods.\"a.b\"]\nper_second = 0.0\nburst = 1")
+            .is_err()
+    );
+
+    let transport = transport_with(config.rate_limits);
+    assert!(transport
+        .handle_incoming(call_as("get", "phone"))
+        .await
+        .is_ok());
+    let retry_after = match transport.handle_incoming(call_as("get", "phone")).await {
+        Err(RpcError::RateLimited { retry_after }) => retry_after,
+        other => panic!("expected rate_limited, got {other:?}"),
+    };
+    assert!(retry_after <= Duration::from_millis(50));
+    // Other methods are not limited.
+    assert!(transport
+        .handle_incoming(call_as("set", "phone"))
+        .await
+        .is_ok());
+
+    tokio::time::sleep(retry_after + Duration::from_millis(5)).await;
+    assert!(transport
+        .handle_incoming(call_as("get", "phone"))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn http_returns_429_with_retry_after() {
+    let transport = transport_with(RateLimits::new().with_per_client(RateLimit::new(0.1, 1)));
+    let server = HttpServerTransport::new(transport.registry());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let request = HttpRpcRequest {
+        service: "hello".to_string(),
+        method: "get".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    // Separate clients use separate connections but share the peer address.
+    let first = reqwest::Client::new()
+        .post(format!("http://{addr}/rpc"))
+        .json(&request)
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(first.status(), reqwest::StatusCode::OK);
+
+    let limited = reqwest::Client::new()
+        .post(format!("http://{addr}/rpc"))
+        .json(&request)
+        .send()
+        .await
+        .expect("response");
+    assert_eq!(limited.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        limited
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok()),
+        Some("10")
+    );
+    let body: HttpRpcResponse = limited.json().await.expect("json body");
+    let error = body.error.expect("error");
+    assert_eq!(error.code, "rate_limited");
+    assert!(error.retry_after_ms.expect("retry hint") > 9_000);
+
+    server_task.abort();
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn ble_reports_rate_limited_with_hint() {
+    let transport =
+        transport_with(RateLimits::new().with_method("hello.get", RateLimit::new(1.0, 1)));
+    let ble = BleTransport::new(transport.registry());
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let request = BleRpcRequest {
+        service: "hello".to_string(),
+        method: "get".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 0,
+        stream: false,
+        request_id: None,
+        metadata: BTreeMap::new(),
+    };
+    let first = client.call(&request).await.expect("response");
+    assert!(first.error.is_none());
+    let limited = client.call(&request).await.expect("response");
+    let error = limited.error.expect("error");
+    assert_eq!(error.code, "rate_limited");
+    assert!(error.retry_after_ms.expect("retry hint") <= 1_000);
+
+    drop(client);
+    server_task.await.expect("serve exits");
+}
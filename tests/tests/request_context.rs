@@ -0,0 +1,120 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use base64::{engine::general_purpose, Engine as _};
+use service_core::router::{rpc_handler, Peer, RpcRegistry, RpcRequest, RpcResponse};
+use service_transport::{
+    ble::{client::BleClient, link::BleLink, protocol::BleRpcRequest, BleTransport},
+    http::{
+        protocol::{HttpRpcRequest, HttpRpcResponse},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+
+/// Registers `ctx.echo`, which returns the request context as JSON.
+fn register_echo(registry: &Arc<dyn RpcRegistry>) {
+    let handler = rpc_handler(|req: RpcRequest| async move {
+        let context = &req.context;
+        let peer = match &context.peer {
+            Some(Peer::Socket(addr)) => format!("socket:{}", addr.ip()),
+            Some(peer) => peer.to_string(),
+            None => String::new(),
+        };
+        let body = serde_json::json!({
+            "request_id": context.request_id,
+            "transport": context.transport,
+            "peer": peer,
+            "has_deadline": context.deadline.is_some(),
+            "metadata": context.metadata,
+        });
+        Ok(RpcResponse {
+            payload: body.to_string().into_bytes(),
+        })
+    });
+    registry
+        .register("ctx", "echo", handler)
+        .expect("register handler");
+}
+
+#[tokio::test]
+async fn http_context_carries_peer_and_headers() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    register_echo(&registry);
+
+    let server = HttpServerTransport::new(registry);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let request = HttpRpcRequest {
+        service: "ctx".to_string(),
+        method: "echo".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 1_000,
+    };
+    let body: HttpRpcResponse = reqwest::Client::new()
+        .post(format!("http://{addr}/rpc"))
+        .header("X-Request-Id", "abc-123")
+        .header("X-Client-Version", "1.2.0")
+        .json(&request)
+        .send()
+        .await
+        .expect("response")
+        .json()
+        .await
+        .expect("json body");
+
+    let payload = general_purpose::STANDARD
+        .decode(body.payload_b64)
+        .expect("decode payload");
+    let context: serde_json::Value = serde_json::from_slice(&payload).expect("context json");
+    assert_eq!(context["request_id"], "abc-123");
+    assert_eq!(context["transport"], "http");
+    assert_eq!(context["peer"], "socket:127.0.0.1");
+    assert_eq!(context["has_deadline"], true);
+    assert_eq!(context["metadata"]["x-client-version"], "1.2.0");
+    assert_eq!(context["metadata"]["content-type"], "application/json");
+
+    server_task.abort();
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn ble_context_carries_device_address_and_metadata() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    register_echo(&registry);
+
+    let ble = BleTransport::new(registry);
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 64);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let request = BleRpcRequest {
+        service: "ctx".to_string(),
+        method: "echo".to_string(),
+        payload_b64: String::new(),
+        timeout_ms: 0,
+        stream: false,
+        request_id: None,
+        metadata: BTreeMap::from([("app".to_string(), "companion".to_string())]),
+    };
+    let response = client.call(&request).await.expect("response");
+    let payload = general_purpose::STANDARD
+        .decode(response.payload_b64)
+        .expect("decode payload");
+    let context: serde_json::Value = serde_json::from_slice(&payload).expect("context json");
+    assert_eq!(context["transport"], "ble");
+    assert_eq!(context["peer"], "ble:AA:BB:CC:DD:EE:FF");
+    assert_eq!(context["has_deadline"], false);
+    assert_eq!(context["metadata"]["app"], "companion");
+    assert!(context["request_id"]
+        .as_str()
+        .is_some_and(|id| id.starts_with("req-")));
+
+    drop(client);
+    server_task.await.expect("serve exits");
+}
@@ -0,0 +1,138 @@
+use std::{net::SocketAddr, time::Duration};
+
+use reqwest::StatusCode;
+use service_core::{
+    router::{rpc_handler, RpcError, RpcRequest, RpcResponse},
+    InMemoryRouter,
+};
+use service_transport::{
+    http::{protocol::HttpRpcError, HttpServerTransport, TIMEOUT_HEADER},
+    mock::MockTransport,
+};
+use tokio::sync::oneshot;
+
+/// Serve `text.upper`, which upper-cases a UTF-8 body, and `text.slow`,
+/// which never finishes and reports its cancellation on `cancelled`.
+async fn serve(cancelled: oneshot::Sender<()>) -> SocketAddr {
+    let transport = MockTransport::with_router(InMemoryRouter::new());
+    let registry = transport.registry();
+    let upper = rpc_handler(|req: RpcRequest| async move {
+        let text =
+            String::from_utf8(req.payload).map_err(|err| RpcError::Decode(err.to_string()))?;
+        Ok(RpcResponse {
+            payload: text.to_uppercase().into_bytes(),
+        })
+    });
+    registry.register("text", "upper", upper).expect("register");
+
+    let cancelled = std::sync::Mutex::new(Some(cancelled));
+    let slow = rpc_handler(move |req: RpcRequest| {
+        let cancelled = cancelled.lock().expect("lock").take();
+        let token = req.context.cancellation().clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            if let Some(cancelled) = cancelled {
+                let _ = cancelled.send(());
+            }
+        });
+        async move {
+            std::future::pending::<()>().await;
+            Ok(RpcResponse {
+                payload: Vec::new(),
+            })
+        }
+    });
+    registry.register("text", "slow", slow).expect("register");
+
+    let server = HttpServerTransport::new(registry);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move { server.serve_with_listener(listener).await });
+    addr
+}
+
+#[tokio::test]
+async fn raw_body_in_raw_body_out() {
+    let (cancelled, _) = oneshot::channel();
+    let addr = serve(cancelled).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{addr}/rpc/text/upper"))
+        .body("hello")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/octet-stream"
+    );
+    assert_eq!(response.bytes().await.expect("body").as_ref(), b"HELLO");
+
+    let cases: [(&str, Vec<u8>, StatusCode, &str); 2] = [
+        (
+            "text/missing",
+            b"x".to_vec(),
+            StatusCode::NOT_FOUND,
+            "unknown_method",
+        ),
+        (
+            "text/upper",
+            vec![0xff, 0xfe],
+            StatusCode::BAD_REQUEST,
+            "decode",
+        ),
+    ];
+    for (path, body, status, code) in cases {
+        let response = client
+            .post(format!("http://{addr}/rpc/{path}"))
+            .body(body)
+            .send()
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), status);
+        let error: HttpRpcError = response.json().await.expect("decode error");
+        assert_eq!(error.code, code);
+    }
+
+    // The envelope endpoints are still served.
+    let methods = client
+        .get(format!("http://{addr}/rpc/methods"))
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(methods.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn timeout_header_cancels_the_handler() {
+    let (cancelled, on_cancel) = oneshot::channel();
+    let addr = serve(cancelled).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{addr}/rpc/text/slow"))
+        .header(TIMEOUT_HEADER, "50")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    let error: HttpRpcError = response.json().await.expect("decode error");
+    assert_eq!(error.code, "timeout");
+    tokio::time::timeout(Duration::from_secs(2), on_cancel)
+        .await
+        .expect("handler cancelled")
+        .expect("cancel signal");
+
+    let response = client
+        .post(format!("http://{addr}/rpc/text/upper"))
+        .header(TIMEOUT_HEADER, "soon")
+        .body("x")
+        .send()
+        .await
+        .expect("send request");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
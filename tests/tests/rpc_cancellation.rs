@@ -0,0 +1,195 @@
+use std::{sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose, Engine as _};
+use service_core::{
+    router::{rpc_handler, Principal, RpcError, RpcRegistry, RpcRequest, RpcResponse},
+    TransportSupervisor,
+};
+use service_platform::autoselect::switch_transport;
+use service_transport::{
+    http::{protocol::HttpRpcRequest, HttpServerTransport},
+    mock::MockTransport,
+};
+use tokio::sync::oneshot;
+
+/// Signals sent by the `jobs.wait` handler.
+struct WaitSignals {
+    started: oneshot::Receiver<()>,
+    observed: oneshot::Receiver<()>,
+}
+
+/// Registers `jobs.wait`, which never finishes on its own. A task spawned by
+/// the handler watches the cancellation token and signals `observed`.
+fn register_wait(registry: &Arc<dyn RpcRegistry>) -> WaitSignals {
+    let (started_tx, started) = oneshot::channel();
+    let (observed_tx, observed) = oneshot::channel();
+    let senders = Arc::new(std::sync::Mutex::new(Some((started_tx, observed_tx))));
+    let handler = rpc_handler(move |req: RpcRequest| {
+        let senders = senders.clone();
+        async move {
+            let token = req.context.cancellation().clone();
+            if let Some((started, observed)) = senders.lock().expect("lock").take() {
+                tokio::spawn(async move {
+                    token.cancelled().await;
+                    let _ = observed.send(());
+                });
+                let _ = started.send(());
+            }
+            std::future::pending::<()>().await;
+            Ok(RpcResponse {
+                payload: Vec::new(),
+            })
+        }
+    });
+    registry
+        .register("jobs", "wait", handler)
+        .expect("register handler");
+    WaitSignals { started, observed }
+}
+
+#[tokio::test]
+async fn rpc_cancel_aborts_matching_request() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    let signals = register_wait(&registry);
+
+    let call = tokio::spawn(
+        registry.dispatch(RpcRequest::new("jobs", "wait", Vec::new(), 0).with_request_id("job-1")),
+    );
+    signals.started.await.expect("handler started");
+
+    let unknown = transport
+        .handle_incoming(RpcRequest::new("rpc", "cancel", b"job-2".to_vec(), 0))
+        .await
+        .expect("cancel response");
+    assert_eq!(unknown.payload, b"false");
+
+    let cancelled = transport
+        .handle_incoming(RpcRequest::new("rpc", "cancel", b"job-1".to_vec(), 0))
+        .await
+        .expect("cancel response");
+    assert_eq!(cancelled.payload, b"true");
+
+    assert!(matches!(
+        call.await.expect("join"),
+        Err(RpcError::Cancelled)
+    ));
+    tokio::time::timeout(Duration::from_secs(1), signals.observed)
+        .await
+        .expect("handler observed cancellation")
+        .expect("signal sent");
+}
+
+#[tokio::test]
+async fn rpc_cancel_rejects_other_callers_requests() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    let signals = register_wait(&registry);
+
+    let owner = Principal::new("phone");
+    let call = tokio::spawn(
+        registry.dispatch(
+            RpcRequest::new("jobs", "wait", Vec::new(), 0)
+                .with_request_id("job-1")
+                .with_principal(owner.clone()),
+        ),
+    );
+    signals.started.await.expect("handler started");
+
+    let cancel = |caller: Principal| {
+        RpcRequest::new("rpc", "cancel", b"job-1".to_vec(), 0).with_principal(caller)
+    };
+    assert!(matches!(
+        transport
+            .handle_incoming(cancel(Principal::new("mallory")))
+            .await,
+        Err(RpcError::PermissionDenied(_))
+    ));
+    assert!(!call.is_finished());
+
+    let cancelled = transport
+        .handle_incoming(cancel(owner))
+        .await
+        .expect("cancel response");
+    assert_eq!(cancelled.payload, b"true");
+    assert!(matches!(
+        call.await.expect("join"),
+        Err(RpcError::Cancelled)
+    ));
+}
+
+#[tokio::test]
+async fn transport_switch_cancels_in_flight_requests() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    let signals = register_wait(&registry);
+
+    let supervisor = TransportSupervisor::new();
+    for id in ["http", "ble"] {
+        supervisor
+            .add(id, || async { std::future::pending().await })
+            .expect("add transport");
+    }
+    let selected = switch_transport(&supervisor, registry.as_ref(), "http")
+        .await
+        .expect("select http");
+    assert_eq!(selected.as_deref(), Some("http"));
+
+    let call = tokio::spawn(registry.dispatch(RpcRequest::new("jobs", "wait", Vec::new(), 0)));
+    signals.started.await.expect("handler started");
+    switch_transport(&supervisor, registry.as_ref(), "ble")
+        .await
+        .expect("switch");
+
+    assert!(matches!(
+        call.await.expect("join"),
+        Err(RpcError::Cancelled)
+    ));
+    signals
+        .observed
+        .await
+        .expect("handler observed cancellation");
+    assert_eq!(supervisor.running(), ["ble"]);
+    supervisor.stop_all().await;
+}
+
+#[tokio::test]
+async fn http_client_disconnect_cancels_handler() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    let signals = register_wait(&registry);
+
+    let server = HttpServerTransport::new(registry);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(200))
+        .build()
+        .expect("client");
+    let request = HttpRpcRequest {
+        service: "jobs".to_string(),
+        method: "wait".to_string(),
+        payload_b64: general_purpose::STANDARD.encode([]),
+        timeout_ms: 0,
+    };
+    let result = client
+        .post(format!("http://{addr}/rpc"))
+        .header("X-Request-Id", "slow-1")
+        .json(&request)
+        .send()
+        .await;
+    assert!(result.is_err(), "client gave up waiting");
+
+    signals.started.await.expect("handler started");
+    tokio::time::timeout(Duration::from_secs(2), signals.observed)
+        .await
+        .expect("handler observed cancellation")
+        .expect("signal sent");
+
+    server_task.abort();
+    let _ = server_task.await;
+}
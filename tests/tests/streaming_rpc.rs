@@ -0,0 +1,223 @@
+use std::{sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose, Engine as _};
+use futures_util::{SinkExt, StreamExt};
+use service_core::router::{
+    rpc_stream_handler, RpcError, RpcRegistry, RpcRequest, RpcResponse, RpcStreamSender,
+};
+use service_transport::{
+    ble::{client::BleClient, link::BleLink, protocol::BleRpcRequest, BleTransport},
+    http::{
+        protocol::{HttpRpcRequest, HttpStreamMessage},
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Registers `logs.tail`, which streams `count` numbered lines.
+fn register_tail(registry: &Arc<dyn RpcRegistry>) {
+    let handler = rpc_stream_handler(|req: RpcRequest, sender: RpcStreamSender| async move {
+        let count = req.payload.first().copied().unwrap_or(0);
+        for line in 0..count {
+            sender
+                .send(RpcResponse {
+                    payload: format!("line {line}").into_bytes(),
+                })
+                .await?;
+        }
+        if count == 0 {
+            return Err(RpcError::Internal("nothing to tail".to_string()));
+        }
+        Ok(())
+    });
+    registry
+        .register_stream("logs", "tail", handler)
+        .expect("register stream");
+}
+
+/// Signals `done` when dropped, i.e. when the handler future is cancelled.
+struct DropSignal(Option<oneshot::Sender<()>>);
+
+impl Drop for DropSignal {
+    fn drop(&mut self) {
+        if let Some(done) = self.0.take() {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Registers `sensor.sample`, which streams forever and reports cancellation.
+fn register_endless(registry: &Arc<dyn RpcRegistry>) -> oneshot::Receiver<()> {
+    let (done_tx, done_rx) = oneshot::channel();
+    let signal = Arc::new(std::sync::Mutex::new(Some(done_tx)));
+    let handler = rpc_stream_handler(move |_req: RpcRequest, sender: RpcStreamSender| {
+        let signal = signal.clone();
+        async move {
+            let _guard = DropSignal(signal.lock().expect("signal lock").take());
+            loop {
+                sender
+                    .send(RpcResponse {
+                        payload: b"sample".to_vec(),
+                    })
+                    .await?;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    });
+    registry
+        .register_stream("sensor", "sample", handler)
+        .expect("register stream");
+    done_rx
+}
+
+#[tokio::test]
+async fn stream_yields_chunks_then_status() {
+    let transport = MockTransport::new();
+    register_tail(&transport.registry());
+
+    let mut stream =
+        transport.handle_incoming_stream(RpcRequest::new("logs", "tail", vec![3], 1_000));
+    let mut lines = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        lines.push(String::from_utf8(chunk.payload).expect("utf-8"));
+    }
+    assert_eq!(lines, ["line 0", "line 1", "line 2"]);
+    stream.finish().await.expect("ok status");
+
+    let stream = transport.handle_incoming_stream(RpcRequest::new("logs", "tail", vec![0], 1_000));
+    assert!(matches!(stream.finish().await, Err(RpcError::Internal(_))));
+
+    let response = transport
+        .handle_incoming(RpcRequest::new("logs", "tail", vec![1], 1_000))
+        .await;
+    assert!(
+        response.is_err(),
+        "unary dispatch of a streaming method fails"
+    );
+}
+
+#[tokio::test]
+async fn http_websocket_stream_and_cancellation() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    register_tail(&registry);
+    let cancelled = register_endless(&registry);
+
+    let server = HttpServerTransport::new(registry);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let server_task = tokio::spawn(async move { server.serve_with_listener(listener).await });
+
+    let open = |service: &str, method: &str, payload: &[u8]| {
+        let request = HttpRpcRequest {
+            service: service.to_string(),
+            method: method.to_string(),
+            payload_b64: general_purpose::STANDARD.encode(payload),
+            timeout_ms: 1_000,
+        };
+        Message::Text(serde_json::to_string(&request).expect("serialize"))
+    };
+
+    let url = format!("ws://{addr}/rpc/stream");
+    let (mut socket, _) = tokio_tungstenite::connect_async(&url)
+        .await
+        .expect("websocket connect");
+    socket.send(open("logs", "tail", &[2])).await.expect("send");
+
+    let mut chunks = Vec::new();
+    let end = loop {
+        let Some(Ok(Message::Text(text))) = socket.next().await else {
+            panic!("stream closed before end message");
+        };
+        match serde_json::from_str(&text).expect("decode") {
+            HttpStreamMessage::Chunk { payload_b64 } => chunks.push(
+                general_purpose::STANDARD
+                    .decode(payload_b64)
+                    .expect("base64"),
+            ),
+            HttpStreamMessage::End { error } => break error,
+        }
+    };
+    assert_eq!(chunks, [b"line 0".to_vec(), b"line 1".to_vec()]);
+    assert!(end.is_none());
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(&url)
+        .await
+        .expect("websocket connect");
+    socket
+        .send(open("sensor", "sample", &[]))
+        .await
+        .expect("send");
+    socket.next().await.expect("first chunk").expect("frame");
+    drop(socket);
+
+    tokio::time::timeout(Duration::from_secs(2), cancelled)
+        .await
+        .expect("handler cancelled after disconnect")
+        .expect("signal sent");
+
+    server_task.abort();
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn ble_stream_and_cancellation_on_link_drop() {
+    let transport = MockTransport::new();
+    let registry = transport.registry();
+    register_tail(&registry);
+    let cancelled = register_endless(&registry);
+
+    let ble = BleTransport::new(registry);
+    let (link, central) = BleLink::simulated("AA:BB:CC:DD:EE:FF", 23);
+    let server_task = tokio::spawn(async move { ble.serve(link).await });
+    let mut client = BleClient::new(central);
+
+    let request = |service: &str, method: &str, payload: &[u8]| BleRpcRequest {
+        service: service.to_string(),
+        method: method.to_string(),
+        payload_b64: general_purpose::STANDARD.encode(payload),
+        timeout_ms: 1_000,
+        stream: true,
+        request_id: None,
+        metadata: Default::default(),
+    };
+
+    let responses = client
+        .call_stream(&request("logs", "tail", &[3]))
+        .await
+        .expect("stream");
+    assert_eq!(responses.len(), 4);
+    assert!(responses[..3].iter().all(|response| response.more));
+    let last = responses.last().expect("terminal response");
+    assert!(!last.more && last.error.is_none());
+
+    let failed = client
+        .call_stream(&request("logs", "tail", &[0]))
+        .await
+        .expect("stream");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(
+        failed[0].error.as_ref().map(|err| err.code.as_str()),
+        Some("internal")
+    );
+
+    let msg_id = client
+        .send(
+            service_transport::ble::framing::FrameKind::Request,
+            &request("sensor", "sample", &[]),
+        )
+        .await
+        .expect("send");
+    client.next_response(msg_id).await.expect("first chunk");
+    drop(client);
+
+    tokio::time::timeout(Duration::from_secs(2), cancelled)
+        .await
+        .expect("handler cancelled after link drop")
+        .expect("signal sent");
+    server_task.await.expect("serve exits");
+}
@@ -0,0 +1,137 @@
+use std::{
+    os::unix::fs::{MetadataExt as _, PermissionsExt as _},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use service_core::{
+    router::{rpc_handler, RpcRegistry, RpcRequest, RpcResponse},
+    AppConfig, InMemoryRouter, UnixSocketConfig,
+};
+use service_transport::{
+    http::{
+        client::{HttpClient, HttpClientError},
+        protocol::HttpRpcRequest,
+        HttpServerTransport,
+    },
+    mock::MockTransport,
+};
+
+/// `peer.whoami` answers with the peer the router saw.
+fn registry() -> Arc<dyn RpcRegistry> {
+    let transport = MockTransport::with_router(InMemoryRouter::new());
+    let registry = transport.registry();
+    let whoami = rpc_handler(|req: RpcRequest| async move {
+        let peer = req.context.peer.map(|peer| peer.to_string());
+        Ok(RpcResponse {
+            payload: peer.unwrap_or_default().into_bytes(),
+        })
+    });
+    registry
+        .register("peer", "whoami", whoami)
+        .expect("register");
+    registry
+}
+
+fn socket_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("service-{}-{name}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+async fn wait_for_socket(path: &Path) {
+    for _ in 0..100 {
+        if tokio::net::UnixStream::connect(path).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("{} never accepted connections", path.display());
+}
+
+async fn whoami(client: &HttpClient) -> String {
+    let response = client
+        .call(&HttpRpcRequest {
+            service: "peer".to_string(),
+            method: "whoami".to_string(),
+            payload_b64: String::new(),
+            timeout_ms: 1_000,
+        })
+        .await
+        .expect("call");
+    assert!(response.error.is_none(), "{:?}", response.error);
+    let payload = general_purpose::STANDARD
+        .decode(response.payload_b64)
+        .expect("base64");
+    String::from_utf8(payload).expect("utf-8")
+}
+
+#[tokio::test]
+async fn unix_socket_serves_the_router_with_configured_mode() {
+    let path = socket_path("mode");
+    let config = AppConfig::from_toml_str(&format!(
+        "[http.unix]\npath = {:?}\nmode = 0o600",
+        path.display().to_string()
+    ))
+    .expect("config");
+    let unix = config.http_unix.expect("unix socket");
+    assert_eq!(unix, UnixSocketConfig::new(&path).with_mode(0o600));
+    assert!(AppConfig::from_toml_str("[http.unix]\npath = \"/tmp/x\"\nmode = 0o7777").is_err());
+
+    let server = HttpServerTransport::new(registry()).with_unix_socket(unix);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("local addr");
+    let serving = tokio::spawn(async move { server.serve_with_listener(listener).await });
+    wait_for_socket(&path).await;
+
+    let metadata = std::fs::metadata(&path).expect("socket metadata");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+    let local = HttpClient::unix(&path);
+    assert_eq!(
+        whoami(&local).await,
+        format!("local:uid:{}", metadata.uid())
+    );
+    let methods = local.methods().await.expect("methods");
+    assert!(methods.iter().any(|method| method.method == "whoami"));
+
+    // TCP keeps working alongside the socket.
+    let tcp = HttpClient::tcp(addr);
+    assert!(whoami(&tcp).await.starts_with("127.0.0.1:"));
+
+    // Stopping the server removes the socket file.
+    serving.abort();
+    let _ = serving.await;
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn stale_socket_files_are_replaced_but_live_ones_are_not() {
+    let path = socket_path("stale");
+    drop(std::os::unix::net::UnixListener::bind(&path).expect("bind stale socket"));
+    assert!(path.exists());
+
+    let config = UnixSocketConfig::new(&path);
+    let server = HttpServerTransport::new(registry());
+    let serving = config.clone();
+    tokio::spawn(async move { server.serve_unix(&serving).await });
+    wait_for_socket(&path).await;
+    assert!(whoami(&HttpClient::unix(&path)).await.starts_with("local:"));
+
+    let second = HttpServerTransport::new(registry())
+        .serve_unix(&config)
+        .await
+        .expect_err("socket in use");
+    assert!(second.to_string().contains("in use"), "{second}");
+
+    let missing = HttpClient::unix(socket_path("missing"))
+        .methods()
+        .await
+        .expect_err("no server");
+    assert!(matches!(missing, HttpClientError::Io(_)));
+    let _ = std::fs::remove_file(&path);
+}
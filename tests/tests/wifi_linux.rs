@@ -0,0 +1,102 @@
+use std::{fs, path::PathBuf};
+
+use service_platform::wifi::{
+    linux::{LinkQuality, LinuxWifi, OperState},
+    WifiDetector,
+};
+
+const PROC_NET_WIRELESS: &str = "\
+Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+ wlan0: 0000   58.  -52.  -256        0      0      0      0     12        0
+";
+
+/// Build a fixture root with `wlan0`, `wlan1` and the wired `eth0`.
+fn fixture(name: &str, wlan0_state: &str, wlan0_carrier: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("service-wifi-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let interfaces = [
+        ("wlan0", true, wlan0_state, Some(wlan0_carrier)),
+        ("wlan1", true, "down", None),
+        ("eth0", false, "up", Some("1")),
+    ];
+    for (interface, wireless, operstate, carrier) in interfaces {
+        let dir = root.join("sys/class/net").join(interface);
+        fs::create_dir_all(&dir).expect("create interface dir");
+        if wireless {
+            fs::create_dir(dir.join("wireless")).expect("create wireless dir");
+        }
+        fs::write(dir.join("operstate"), format!("{operstate}\n")).expect("write operstate");
+        if let Some(carrier) = carrier {
+            fs::write(dir.join("carrier"), format!("{carrier}\n")).expect("write carrier");
+        }
+    }
+    fs::create_dir_all(root.join("proc/net")).expect("create proc dir");
+    fs::write(root.join("proc/net/wireless"), PROC_NET_WIRELESS).expect("write proc");
+    root
+}
+
+#[test]
+fn reads_wireless_interfaces_from_sysfs_and_procfs() {
+    let root = fixture("up", "up", "1");
+    let wifi = LinuxWifi::with_root(&root);
+
+    let interfaces = wifi.interfaces().expect("interfaces");
+    let names: Vec<_> = interfaces.iter().map(|iface| iface.name.as_str()).collect();
+    assert_eq!(names, ["wlan0", "wlan1"]);
+
+    let wlan0 = &interfaces[0];
+    assert_eq!(wlan0.operstate, OperState::Up);
+    assert!(wlan0.carrier);
+    assert_eq!(
+        wlan0.quality,
+        Some(LinkQuality {
+            link: 58.0,
+            level_dbm: -52.0,
+            noise_dbm: -256.0,
+        })
+    );
+    assert!(wlan0.is_connected());
+
+    let wlan1 = &interfaces[1];
+    assert_eq!(wlan1.operstate, OperState::Down);
+    assert!(!wlan1.carrier, "unreadable carrier counts as none");
+    assert_eq!(wlan1.quality, None);
+    assert!(!wlan1.is_connected());
+
+    let connected = wifi
+        .connected_interface()
+        .expect("read")
+        .expect("connected");
+    assert_eq!(connected.name, "wlan0");
+    assert_eq!(
+        WifiDetector::with_root(&root).detect().as_deref(),
+        Some("http")
+    );
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn detector_falls_back_to_ble_without_a_connected_interface() {
+    // Associating: the link is dormant until authentication completes.
+    let root = fixture("dormant", "dormant", "1");
+    let wifi = LinuxWifi::with_root(&root);
+    assert_eq!(
+        wifi.interfaces().expect("interfaces")[0].operstate,
+        OperState::Dormant
+    );
+    assert!(wifi.connected_interface().expect("read").is_none());
+    assert_eq!(
+        WifiDetector::with_root(&root).detect().as_deref(),
+        Some("ble")
+    );
+    let _ = fs::remove_dir_all(&root);
+
+    // No wireless hardware at all: nothing to judge by.
+    let empty = std::env::temp_dir().join(format!("service-wifi-{}-none", std::process::id()));
+    assert!(LinuxWifi::with_root(&empty)
+        .interfaces()
+        .expect("interfaces")
+        .is_empty());
+    assert_eq!(WifiDetector::with_root(&empty).detect(), None);
+}
@@ -0,0 +1,186 @@
+use std::{sync::Arc, time::Duration};
+
+use service_core::{
+    router::{rpc_handler, RpcError, RpcRequest},
+    TransportSupervisor,
+};
+use service_platform::{
+    autoselect::{follow_wifi, Selection},
+    wifi::mock::{MockWifiDetector, MockWifiState, WifiEvent, WifiTimeline},
+};
+use service_transport::mock::MockTransport;
+use tokio::{sync::oneshot, time::Instant};
+
+fn connect(ssid: &str, signal_dbm: i32) -> WifiEvent {
+    WifiEvent::Connect {
+        ssid: ssid.to_string(),
+        signal_dbm,
+    }
+}
+
+#[test]
+fn state_changes_drive_detection() {
+    let detector = MockWifiDetector::new();
+    assert_eq!(detector.detect().as_deref(), Some("ble"));
+    assert_eq!(detector.current_network(), None);
+
+    detector.connect("office", -48);
+    detector.set_signal(-71);
+    assert_eq!(detector.detect().as_deref(), Some("http"));
+    let network = detector.current_network().expect("connected");
+    assert_eq!(
+        (network.ssid.as_str(), network.signal_dbm),
+        ("office", Some(-71))
+    );
+
+    detector.disconnect();
+    detector.set_signal(-30);
+    assert_eq!(detector.state(), MockWifiState::Disconnected);
+    detector.remove_interface();
+    assert_eq!(detector.detect(), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn scripted_timeline_switches_transports_at_exact_times() {
+    let supervisor = Arc::new(TransportSupervisor::new());
+    for id in ["http", "ble"] {
+        supervisor
+            .add(id, || async { std::future::pending().await })
+            .expect("add transport");
+    }
+    let registry = MockTransport::new().registry();
+    let stuck = rpc_handler(|_req: RpcRequest| async { std::future::pending().await });
+    registry
+        .register("slow", "wait", stuck)
+        .expect("register handler");
+
+    let detector = MockWifiDetector::new();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let follower = tokio::spawn({
+        let (supervisor, registry, detector) =
+            (supervisor.clone(), registry.clone(), detector.clone());
+        async move {
+            let watcher = detector.watcher();
+            let shutdown = async {
+                let _ = stopped.await;
+            };
+            follow_wifi(
+                &supervisor,
+                registry.as_ref(),
+                &detector,
+                Some(watcher),
+                None,
+                shutdown,
+                |_| {},
+            )
+            .await
+        }
+    });
+
+    let start = Instant::now();
+    let playback = detector.play(
+        WifiTimeline::new()
+            .at(Duration::from_secs(1), connect("office", -50))
+            // A weaker signal on the same network must not switch.
+            .at(Duration::from_secs(4), WifiEvent::Signal { dbm: -80 })
+            .at(Duration::from_secs(10), WifiEvent::Disconnect)
+            .at(Duration::from_secs(25), connect("office", -55)),
+    );
+    let running_at = |millis: u64| {
+        let supervisor = supervisor.clone();
+        async move {
+            tokio::time::sleep_until(start + Duration::from_millis(millis)).await;
+            supervisor.running()
+        }
+    };
+
+    assert_eq!(running_at(999).await, ["ble"]);
+    assert_eq!(running_at(1_001).await, ["http"]);
+    assert_eq!(running_at(5_000).await, ["http"]);
+    let call = tokio::spawn({
+        let registry = registry.clone();
+        async move {
+            registry
+                .dispatch(RpcRequest::new("slow", "wait", Vec::new(), 0))
+                .await
+        }
+    });
+    assert_eq!(running_at(9_999).await, ["http"]);
+    assert!(!call.is_finished());
+    assert_eq!(running_at(10_001).await, ["ble"]);
+    // Switching transports cancels calls still in flight.
+    assert!(matches!(
+        call.await.expect("call task"),
+        Err(RpcError::Cancelled)
+    ));
+    assert_eq!(running_at(24_999).await, ["ble"]);
+    assert_eq!(running_at(25_001).await, ["http"]);
+    playback.await.expect("timeline finished");
+
+    stop.send(()).expect("follower running");
+    follower.await.expect("follower task").expect("follow wifi");
+    supervisor.stop_all().await;
+}
+
+#[tokio::test]
+async fn missing_transport_falls_back_to_a_built_in_one() {
+    let supervisor = Arc::new(TransportSupervisor::new());
+    supervisor
+        .add("ble", || async { std::future::pending().await })
+        .expect("add transport");
+    let registry = MockTransport::new().registry();
+    let detector = MockWifiDetector::new();
+    detector.connect("office", -50);
+
+    let (selections, mut selected) = tokio::sync::mpsc::unbounded_channel();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let follower = tokio::spawn({
+        let (supervisor, detector) = (supervisor.clone(), detector.clone());
+        async move {
+            let watcher = detector.watcher();
+            let shutdown = async {
+                let _ = stopped.await;
+            };
+            follow_wifi(
+                &supervisor,
+                registry.as_ref(),
+                &detector,
+                Some(watcher),
+                None,
+                shutdown,
+                |selection| {
+                    let _ = selections.send(selection);
+                },
+            )
+            .await
+        }
+    });
+
+    // Wi-Fi is up but HTTP was not built in, so BLE serves instead of nothing.
+    assert_eq!(
+        selected.recv().await,
+        Some(Selection::Switched {
+            reason: None,
+            selected: "http".to_string(),
+            running: Some("ble".to_string()),
+        })
+    );
+    assert_eq!(supervisor.running(), ["ble"]);
+
+    detector.disconnect();
+    let Some(Selection::Switched {
+        selected, running, ..
+    }) = selected.recv().await
+    else {
+        panic!("expected a switch");
+    };
+    assert_eq!(
+        (selected.as_str(), running.as_deref()),
+        ("ble", Some("ble"))
+    );
+    assert_eq!(supervisor.running(), ["ble"]);
+
+    stop.send(()).expect("follower running");
+    follower.await.expect("follower task").expect("follow wifi");
+    supervisor.stop_all().await;
+}
@@ -0,0 +1,218 @@
+use std::{fs, os::unix::net::UnixDatagram, path::PathBuf, thread};
+
+use service_platform::wifi::{
+    linux::{NetworkInfo, WifiNetwork, WifiSecurity},
+    networkmanager::NetworkManager,
+    wpa_supplicant::WpaSupplicant,
+};
+use zbus::{connection, interface, zvariant::OwnedObjectPath, Connection, Guid};
+
+const MANAGER: &str = "/org/freedesktop/NetworkManager";
+const ETH0: &str = "/org/freedesktop/NetworkManager/Devices/1";
+const WLAN0: &str = "/org/freedesktop/NetworkManager/Devices/2";
+const ACCESS_POINT: &str = "/org/freedesktop/NetworkManager/AccessPoint/7";
+
+struct Manager;
+
+#[interface(name = "org.freedesktop.NetworkManager")]
+impl Manager {
+    #[zbus(property)]
+    fn devices(&self) -> Vec<OwnedObjectPath> {
+        [ETH0, WLAN0]
+            .into_iter()
+            .map(|path| OwnedObjectPath::try_from(path).expect("object path"))
+            .collect()
+    }
+}
+
+struct Device {
+    device_type: u32,
+    interface: &'static str,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device")]
+impl Device {
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        self.device_type
+    }
+
+    #[zbus(property)]
+    fn interface(&self) -> String {
+        self.interface.to_string()
+    }
+}
+
+struct Wireless {
+    active_access_point: &'static str,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+impl Wireless {
+    #[zbus(property)]
+    fn active_access_point(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(self.active_access_point).expect("object path")
+    }
+}
+
+struct AccessPoint;
+
+#[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+impl AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> Vec<u8> {
+        b"home-network".to_vec()
+    }
+
+    #[zbus(property)]
+    fn hw_address(&self) -> String {
+        "AA:BB:CC:DD:EE:FF".to_string()
+    }
+
+    #[zbus(property)]
+    fn strength(&self) -> u8 {
+        82
+    }
+
+    #[zbus(property)]
+    fn flags(&self) -> u32 {
+        0x1
+    }
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> u32 {
+        0
+    }
+
+    /// Pairwise/group CCMP plus PSK key management.
+    #[zbus(property)]
+    fn rsn_flags(&self) -> u32 {
+        0x188
+    }
+}
+
+/// Serve a mock NetworkManager over a peer-to-peer connection. The returned
+/// server connection must be kept alive for the client to work.
+async fn mock_networkmanager(active_access_point: &'static str) -> (NetworkManager, Connection) {
+    let (client, server) = tokio::net::UnixStream::pair().expect("socket pair");
+    let server = connection::Builder::unix_stream(server)
+        .server(Guid::generate())
+        .expect("server guid")
+        .p2p()
+        .serve_at(MANAGER, Manager)
+        .and_then(|builder| {
+            builder.serve_at(
+                ETH0,
+                Device {
+                    device_type: 1,
+                    interface: "eth0",
+                },
+            )
+        })
+        .and_then(|builder| {
+            builder.serve_at(
+                WLAN0,
+                Device {
+                    device_type: 2,
+                    interface: "wlan0",
+                },
+            )
+        })
+        .and_then(|builder| {
+            builder.serve_at(
+                WLAN0,
+                Wireless {
+                    active_access_point,
+                },
+            )
+        })
+        .and_then(|builder| builder.serve_at(ACCESS_POINT, AccessPoint))
+        .expect("serve mock objects")
+        .build();
+    let client = connection::Builder::unix_stream(client).p2p().build();
+    let (server, client) = tokio::try_join!(server, client).expect("p2p handshake");
+    (NetworkManager::with_connection(client), server)
+}
+
+/// Answer `STATUS` and `SIGNAL_POLL` on a fake control socket for `wlan0`
+/// until `requests` have been served.
+fn fake_wpa_supplicant(name: &str, requests: usize) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("service-wpa-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("create control dir");
+    let socket = UnixDatagram::bind(dir.join("wlan0")).expect("bind control socket");
+    thread::spawn(move || {
+        let mut buffer = [0u8; 64];
+        for _ in 0..requests {
+            let (len, client) = socket.recv_from(&mut buffer).expect("receive command");
+            let reply = match &buffer[..len] {
+                b"STATUS" => {
+                    "bssid=aa:bb:cc:dd:ee:ff\nfreq=5180\nssid=caf\\xc3\\xa9 \\\"5G\\\"\n\
+                     id=0\nmode=station\npairwise_cipher=CCMP\ngroup_cipher=CCMP\n\
+                     key_mgmt=SAE\nwpa_state=COMPLETED\nip_address=192.168.1.20\n"
+                }
+                b"SIGNAL_POLL" => "RSSI=-61\nLINKSPEED=390\nNOISE=9999\nFREQUENCY=5180\n",
+                _ => "UNKNOWN COMMAND\n",
+            };
+            socket
+                .send_to_addr(reply.as_bytes(), &client)
+                .expect("send reply");
+        }
+    });
+    dir
+}
+
+#[tokio::test]
+async fn reads_the_active_access_point_from_networkmanager() {
+    let (networkmanager, _server) = mock_networkmanager(ACCESS_POINT).await;
+
+    let network = networkmanager
+        .current_network()
+        .await
+        .expect("query mock NetworkManager");
+    assert_eq!(
+        network,
+        Some(WifiNetwork {
+            interface: "wlan0".to_string(),
+            ssid: "home-network".to_string(),
+            bssid: Some("AA:BB:CC:DD:EE:FF".to_string()),
+            strength: Some(82),
+            signal_dbm: None,
+            security: WifiSecurity::WpaPersonal,
+        })
+    );
+}
+
+#[tokio::test]
+async fn falls_back_to_wpa_supplicant_when_networkmanager_has_no_network() {
+    let (networkmanager, _server) = mock_networkmanager("/").await;
+    assert_eq!(networkmanager.current_network().await.expect("query"), None);
+
+    let control_dir = fake_wpa_supplicant("fallback", 2);
+    let info = NetworkInfo::new(
+        Some(networkmanager),
+        WpaSupplicant::with_control_dir(&control_dir),
+    );
+
+    assert_eq!(
+        info.current_network().await,
+        Some(WifiNetwork {
+            interface: "wlan0".to_string(),
+            ssid: "café \"5G\"".to_string(),
+            bssid: Some("aa:bb:cc:dd:ee:ff".to_string()),
+            strength: Some(78),
+            signal_dbm: Some(-61),
+            security: WifiSecurity::Wpa3Personal,
+        })
+    );
+    let _ = fs::remove_dir_all(control_dir);
+}
+
+#[tokio::test]
+async fn missing_wpa_supplicant_control_dir_means_no_network() {
+    let info = NetworkInfo::new(
+        None,
+        WpaSupplicant::with_control_dir("/nonexistent/wpa_supplicant"),
+    );
+    assert_eq!(info.current_network().await, None);
+}